@@ -0,0 +1,28 @@
+// Packs sprites into a texture atlas of fixed width, dropping each one into
+// the lowest spot where it fits.
+
+extern crate skyline;
+
+use skyline::{Rect, Skyline, Up, Down};
+
+fn main() {
+    let width = 64.0;
+    let sprites = [(16.0, 16.0), (32.0, 8.0), (8.0, 24.0), (24.0, 12.0),
+                   (16.0, 16.0), (40.0, 10.0), (12.0, 6.0)];
+
+    let mut atlas = Skyline::<Up>::bounded(0.0, width);
+    atlas.merge(&Skyline::single(0.0, 0.0, width, 0.0));
+
+    let mut height: f64 = 0.0;
+    for &(w, h) in sprites.iter() {
+        let bottom = Skyline::<Down>::single(0.0, 0.0, w, 0.0);
+        let p = atlas.fit(&bottom, (0.0, width - w));
+        let rect = Rect::new(0.0, 0.0, w, h).translate(p.dx, p.dy);
+
+        atlas.merge(&Skyline::from_rects(&[rect]));
+        height = height.max(rect.y1);
+        println!("{} x {} sprite at ({}, {})", w, h, rect.x0, rect.y0);
+    }
+
+    println!("atlas is {} x {}", width, height);
+}
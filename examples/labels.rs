@@ -0,0 +1,33 @@
+// Places a label above each point of a line chart, stacking labels on top of
+// the chart and of each other so that nothing overlaps.
+
+extern crate skyline;
+
+use skyline::{Rect, Skyline, Up, Down};
+
+fn main() {
+    let points = [(0.0, 1.0), (2.0, 3.0), (4.0, 2.5), (6.0, 4.0), (8.0, 3.5)];
+    let (label_width, label_height) = (3.0, 1.0);
+    let padding = 0.25;
+
+    let mut obstacles = Skyline::<Up>::empty();
+    for pair in points.windows(2) {
+        let (x0, y0) = pair[0];
+        let (x1, y1) = pair[1];
+        obstacles.merge(&Skyline::single(x0, y0, x1, y1));
+    }
+
+    for &(x, y) in points.iter() {
+        let label = Rect::new(x - label_width / 2.0, 0.0, x + label_width / 2.0, label_height);
+
+        // The label starts out with its bottom at zero, so the overlap is
+        // exactly how high it needs to go.
+        let mut bottom = Skyline::<Down>::from_rects(&[label]);
+        bottom.dilate(padding);
+        let lift = obstacles.overlap(&bottom);
+
+        let placed = label.translate(0.0, lift);
+        obstacles.merge(&Skyline::from_rects(&[placed]));
+        println!("label for ({}, {}) goes from y = {} to y = {}", x, y, placed.y0, placed.y1);
+    }
+}
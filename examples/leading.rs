@@ -0,0 +1,35 @@
+// Finds how tightly two lines of text can be set, by comparing the bottom of
+// the first line with the top of the second instead of using the largest
+// ascender and descender in the font.
+
+extern crate skyline;
+
+use skyline::{Rect, Skyline, Up, Down};
+
+// Glyph boxes for "glyph" and "Tall", relative to their baselines.
+fn first_line() -> Vec<Rect> {
+    vec![Rect::new(0.0, -3.0, 5.0, 5.0),   // g
+         Rect::new(5.0, 0.0, 7.0, 9.0),    // l
+         Rect::new(7.0, -3.0, 12.0, 5.0),  // y
+         Rect::new(12.0, -3.0, 17.0, 5.0), // p
+         Rect::new(17.0, 0.0, 22.0, 9.0)]  // h
+}
+
+fn second_line() -> Vec<Rect> {
+    vec![Rect::new(0.0, 0.0, 6.0, 9.0),    // T
+         Rect::new(6.0, 0.0, 11.0, 5.0),   // a
+         Rect::new(11.0, 0.0, 13.0, 9.0),  // l
+         Rect::new(13.0, 0.0, 15.0, 9.0)]  // l
+}
+
+fn main() {
+    let min_gap = 1.0;
+    let first = Skyline::<Down>::from_rects(first_line().as_slice());
+    let second = Skyline::<Up>::from_rects(second_line().as_slice());
+
+    // With both baselines at zero, the overlap is how far the first line
+    // has to be raised to clear the second.
+    let tight = second.overlap(&first) + min_gap;
+    let naive = 9.0 + 3.0 + min_gap;
+    println!("tight leading: {}, naive leading: {}", tight, naive);
+}
@@ -0,0 +1,34 @@
+// Nests irregular parts into a strip of fixed width, dropping each one as far
+// down as it will go.
+
+extern crate skyline;
+
+use skyline::{Skyline, Up, Down};
+
+fn main() {
+    let width = 20.0;
+    let parts = vec![
+        vec![(0.0, 0.0), (8.0, 0.0), (4.0, 6.0)],                          // triangle
+        vec![(0.0, 0.0), (6.0, 0.0), (6.0, 2.0), (2.0, 2.0), (2.0, 7.0), (0.0, 7.0)], // L
+        vec![(0.0, 0.0), (10.0, 0.0), (8.0, 3.0), (2.0, 3.0)],             // trapezoid
+        vec![(0.0, 2.0), (2.0, 0.0), (7.0, 0.0), (9.0, 2.0), (4.5, 5.0)],  // gem
+        vec![(4.0, 0.0), (8.0, 6.0), (0.0, 6.0)],                          // upside-down triangle
+    ];
+
+    let mut strip = Skyline::<Up>::bounded(0.0, width);
+    strip.merge(&Skyline::single(0.0, 0.0, width, 0.0));
+
+    for part in parts.iter() {
+        let bottom = Skyline::<Down>::from_polygon(part.as_slice());
+        let p = strip.fit(&bottom, (-width, width));
+
+        let mut top = Skyline::<Up>::from_polygon(part.as_slice());
+        top.slide(p.dx);
+        top.bump(p.dy);
+        strip.merge(&top);
+        println!("part with {} corners moved by ({}, {})", part.len(), p.dx, p.dy);
+    }
+
+    let length = strip.segments().fold(0.0 as f64, |h, s| h.max(s.y0).max(s.y1));
+    println!("used {} of the strip", length);
+}
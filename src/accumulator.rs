@@ -0,0 +1,196 @@
+use {Building, Direction, IntervalSet, Skyline};
+
+// Collects skylines into a single envelope, optionally refusing anything
+// that would make the envelope grow past a ceiling.
+//
+// The ceiling is in world coordinates and limits how far the envelope may
+// extend in its own direction: it's an upper bound on the height of an
+// upward skyline, and a lower bound for a downward one.
+#[deriving(Clone, Show)]
+pub struct SkylineAccumulator<T: Direction> {
+    skyline: Skyline<T>,
+    // The ceiling, in the same units as the buildings' heights (i.e., already
+    // multiplied by the direction multiplier).
+    ceiling: Option<f64>,
+    // The parts of the x axis covered by anything that was added.
+    occupied: IntervalSet
+}
+
+// Describes a rejected merge: the part of the x axis on which the ceiling
+// would have been exceeded, and the most extreme height reached there.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Overflow {
+    pub x1: f64,
+    pub x2: f64,
+    pub height: f64
+}
+
+impl<T: Direction> SkylineAccumulator<T> {
+    pub fn new() -> SkylineAccumulator<T> {
+        SkylineAccumulator {
+            skyline: Skyline::empty(),
+            ceiling: None,
+            occupied: IntervalSet::new()
+        }
+    }
+
+    pub fn with_ceiling(y_max: f64) -> SkylineAccumulator<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        SkylineAccumulator {
+            skyline: Skyline::empty(),
+            ceiling: Some(y_max * mult),
+            occupied: IntervalSet::new()
+        }
+    }
+
+    pub fn ceiling(&self) -> Option<f64> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        self.ceiling.map(|c| c * mult)
+    }
+
+    pub fn skyline(&self) -> &Skyline<T> {
+        &self.skyline
+    }
+
+    // The union of the supports of everything that was added, regardless of
+    // height.
+    pub fn occupied(&self) -> &IntervalSet {
+        &self.occupied
+    }
+
+    pub fn into_skyline(self) -> Skyline<T> {
+        self.skyline
+    }
+
+    // Merges `sky` into the envelope, unless that would exceed the ceiling.
+    // In that case, the envelope is left alone.
+    pub fn add(&mut self, sky: &Skyline<T>) -> Result<(), Overflow> {
+        match self.ceiling {
+            Some(c) => match overflow(sky, c) {
+                Some(o) => return Err(o),
+                None => {},
+            },
+            None => {},
+        }
+
+        self.skyline.merge(sky);
+        self.occupied = self.occupied.union(&sky.occupied());
+        Ok(())
+    }
+}
+
+// The height of `b` at `x`, which can be one of its infinite ends.
+fn height_at_end(b: &Building, x: f64) -> f64 {
+    if x.is_finite() {
+        b.y(x)
+    } else if b.m == 0.0 {
+        b.b
+    } else {
+        b.m * x
+    }
+}
+
+// Finds where `sky` goes above `ceiling`. Since everything already in an
+// accumulator is below the ceiling, this is the only thing we need to check
+// before merging. Buildings that go on forever count too, so `x1` or `x2`
+// can be infinite.
+fn overflow<T: Direction>(sky: &Skyline<T>, ceiling: f64) -> Option<Overflow> {
+    let mut x1: f64 = Float::infinity();
+    let mut x2: f64 = Float::neg_infinity();
+    let mut worst: f64 = Float::neg_infinity();
+
+    let mut start: f64 = Float::neg_infinity();
+    for b in sky.buildings.iter() {
+        let b_start = start;
+        start = b.end;
+        if b.b == Float::neg_infinity() {
+            continue;
+        }
+
+        let y0 = height_at_end(b, b_start);
+        let y1 = height_at_end(b, b.end);
+        if y0 <= ceiling && y1 <= ceiling {
+            continue;
+        }
+
+        // The part of the building above the ceiling.
+        let cross = if b.m == 0.0 { b_start } else { (ceiling - b.b) / b.m };
+        let (lo, hi) = if b.m > 0.0 {
+            (cross.max(b_start), b.end)
+        } else if b.m < 0.0 {
+            (b_start, cross.min(b.end))
+        } else {
+            (b_start, b.end)
+        };
+
+        x1 = x1.min(lo);
+        x2 = x2.max(hi);
+        worst = worst.max(y0).max(y1);
+    }
+
+    if worst > ceiling {
+        let mult = Direction::direction_multiplier(None::<T>);
+        Some(Overflow { x1: x1, x2: x2, height: worst * mult })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Building, Interval, Skyline, Sweep, Up, Down};
+    use super::SkylineAccumulator;
+
+    #[test]
+    fn ceiling_rejects_overflow() {
+        let mut acc = SkylineAccumulator::<Up>::with_ceiling(2.0);
+        assert!(acc.add(&Skyline::single(0.0, 1.0, 4.0, 1.0)).is_ok());
+
+        let o = acc.add(&Skyline::single(0.0, 0.0, 4.0, 4.0)).unwrap_err();
+        assert!(o.x1.approx_eq(2.0) && o.x2.approx_eq(4.0), "{}", o);
+        assert!(o.height.approx_eq(4.0));
+
+        // The rejected skyline didn't change anything.
+        let probe = Skyline::<Down>::single(3.0, 0.0, 4.0, 0.0);
+        assert!(acc.skyline().overlap(&probe).approx_eq(1.0));
+    }
+
+    #[test]
+    fn ceiling_down() {
+        let mut acc = SkylineAccumulator::<Down>::with_ceiling(-1.0);
+        assert_eq!(acc.ceiling(), Some(-1.0));
+        assert!(acc.add(&Skyline::single(0.0, 0.0, 1.0, 0.0)).is_ok());
+        let o = acc.add(&Skyline::single(5.0, -2.0, 6.0, -2.0)).unwrap_err();
+        assert!(o.height.approx_eq(-2.0));
+    }
+
+    #[test]
+    fn tracks_occupancy() {
+        let mut acc = SkylineAccumulator::<Up>::with_ceiling(2.0);
+        acc.add(&Skyline::single(0.0, 1.0, 1.0, 1.0)).unwrap();
+        acc.add(&Skyline::single(3.0, -5.0, 4.0, -5.0)).unwrap();
+        assert!(acc.add(&Skyline::single(1.0, 3.0, 3.0, 3.0)).is_err());
+
+        let occ: Vec<Interval> = acc.occupied().iter().map(|iv| *iv).collect();
+        assert_eq!(occ, vec!(Interval::new(0.0, 1.0), Interval::new(3.0, 4.0)));
+    }
+
+    #[test]
+    fn ceiling_unbounded() {
+        let mut acc = SkylineAccumulator::<Up>::with_ceiling(2.0);
+        let tail = Skyline::single(0.0, 1.0, 1.0, 3.0).running_max(Sweep::LeftToRight);
+        let o = acc.add(&tail).unwrap_err();
+        assert!(o.x1.approx_eq(0.5), "{}", o);
+        assert_eq!(o.x2, Float::infinity());
+        assert!(o.height.approx_eq(3.0));
+
+        let ramp = Skyline::from_buildings(
+            vec!(Building { m: -1.0, b: 0.0, end: 0.0 }, Building::empty(Float::infinity())));
+        let o = acc.add(&ramp).unwrap_err();
+        assert_eq!(o.x1, Float::neg_infinity());
+        assert!(o.x2.approx_eq(-2.0), "{}", o);
+        assert_eq!(o.height, Float::infinity());
+        assert!(acc.skyline().is_empty());
+    }
+}
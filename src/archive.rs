@@ -0,0 +1,225 @@
+use serialize::json;
+use serialize::json::Json;
+use serialize::Decodable;
+use serial::{to_skyline, SkylineRepr};
+use {Dir, Direction, Down, Left, Right, Shape, Skyline, Up};
+
+// The version that `EnvelopeFile::to_json` writes.
+//
+// Version 1 is a single skyline, exactly as its `Encodable` impl writes it.
+// Version 2 is the container below. Fields can be added to it without
+// changing the version, since readers ignore fields they don't know about;
+// anything that older readers would misunderstand needs a new version, and
+// a migration in `from_json`.
+pub const FORMAT_VERSION: u64 = 2;
+
+// The name that a version 1 skyline gets when it's migrated.
+pub const V1_SKYLINE_NAME: &'static str = "skyline";
+
+#[deriving(Encodable, Decodable)]
+struct FileRepr {
+    version: u64,
+    metadata: Vec<(String, String)>,
+    skylines: Vec<(String, SkylineRepr)>,
+    shapes: Vec<(String, Vec<(f64, f64)>)>
+}
+
+// A bundle of named skylines and shapes, along with free-form metadata
+// (which tool wrote it, what units it's in, and so on), for saving layout
+// state or passing it between programs. Names are unique within the
+// skylines and within the shapes; inserting under an existing name
+// replaces the old entry. Everything is kept in insertion order, so the
+// same file always encodes the same way.
+//
+// Only the outline of a shape is stored, not its history.
+#[deriving(Clone, Show)]
+pub struct EnvelopeFile {
+    metadata: Vec<(String, String)>,
+    skylines: Vec<(String, SkylineRepr)>,
+    shapes: Vec<(String, Vec<(f64, f64)>)>
+}
+
+// Replaces the value for `key`, or adds it at the end.
+fn insert<V>(list: &mut Vec<(String, V)>, key: &str, value: V) {
+    match list.iter().position(|&(ref k, _)| k.as_slice() == key) {
+        Some(i) => list[i] = (key.to_string(), value),
+        None => list.push((key.to_string(), value)),
+    }
+}
+
+fn find<'a, V>(list: &'a [(String, V)], key: &str) -> Option<&'a V> {
+    list.iter().find(|&&(ref k, _)| k.as_slice() == key).map(|&(_, ref v)| v)
+}
+
+fn decode<T: Decodable<json::Decoder, json::DecoderError>>(doc: Json) -> Result<T, String> {
+    let mut d = json::Decoder::new(doc);
+    Decodable::decode(&mut d).map_err(|e| format!("{}", e))
+}
+
+// Checks a stored skyline against the same rules as decoding one directly.
+fn check(repr: &SkylineRepr) -> Result<(), String> {
+    let r = repr.clone();
+    match repr.dir {
+        Dir::Up => to_skyline::<Up>(r).map(|_| ()),
+        Dir::Down => to_skyline::<Down>(r).map(|_| ()),
+        Dir::Left => to_skyline::<Left>(r).map(|_| ()),
+        Dir::Right => to_skyline::<Right>(r).map(|_| ()),
+    }
+}
+
+impl EnvelopeFile {
+    pub fn new() -> EnvelopeFile {
+        EnvelopeFile {
+            metadata: Vec::new(),
+            skylines: Vec::new(),
+            shapes: Vec::new()
+        }
+    }
+
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        insert(&mut self.metadata, key, value.to_string());
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        find(self.metadata.as_slice(), key).map(|v| v.as_slice())
+    }
+
+    pub fn insert_skyline<T: Direction>(&mut self, name: &str, sky: &Skyline<T>) {
+        insert(&mut self.skylines, name, SkylineRepr::new(sky));
+    }
+
+    // The skyline called `name`, or `None` if there isn't one or it faces
+    // some other way.
+    pub fn skyline<T: Direction>(&self, name: &str) -> Option<Skyline<T>> {
+        find(self.skylines.as_slice(), name).and_then(|r| to_skyline(r.clone()).ok())
+    }
+
+    pub fn skyline_names(&self) -> Vec<&str> {
+        self.skylines.iter().map(|&(ref k, _)| k.as_slice()).collect()
+    }
+
+    pub fn insert_shape(&mut self, name: &str, shape: &Shape) {
+        insert(&mut self.shapes, name, shape.points().to_vec());
+    }
+
+    pub fn shape(&self, name: &str) -> Option<Shape> {
+        find(self.shapes.as_slice(), name).map(|p| Shape::from_polygon(p.as_slice()))
+    }
+
+    pub fn shape_names(&self) -> Vec<&str> {
+        self.shapes.iter().map(|&(ref k, _)| k.as_slice()).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        json::encode(&FileRepr {
+            version: FORMAT_VERSION,
+            metadata: self.metadata.clone(),
+            skylines: self.skylines.clone(),
+            shapes: self.shapes.clone()
+        })
+    }
+
+    // Reads any version up to `FORMAT_VERSION`, migrating older ones.
+    // Newer versions are rejected rather than guessed at.
+    pub fn from_json(s: &str) -> Result<EnvelopeFile, String> {
+        let doc = try!(json::from_str(s).map_err(|e| format!("{}", e)));
+        let version = match doc.find("version") {
+            Some(v) => match v.as_u64() {
+                Some(v) => v,
+                None => return Err(format!("invalid format version {}", v)),
+            },
+            None => 1,
+        };
+
+        let file = match version {
+            1 => try!(EnvelopeFile::from_v1(doc)),
+            FORMAT_VERSION => {
+                let repr: FileRepr = try!(decode(doc));
+                EnvelopeFile {
+                    metadata: repr.metadata,
+                    skylines: repr.skylines,
+                    shapes: repr.shapes
+                }
+            },
+            v => return Err(format!("format version {} is newer than this library \
+                                     understands ({})", v, FORMAT_VERSION)),
+        };
+
+        for &(ref name, ref repr) in file.skylines.iter() {
+            try!(check(repr).map_err(|e| format!("skyline {}: {}", name, e)));
+        }
+        for &(ref name, ref points) in file.shapes.iter() {
+            if points.is_empty() {
+                return Err(format!("shape {} has no points", name));
+            }
+        }
+        Ok(file)
+    }
+
+    // A version 1 file is a lone skyline, with no metadata.
+    fn from_v1(doc: Json) -> Result<EnvelopeFile, String> {
+        let repr: SkylineRepr = try!(decode(doc));
+        let mut file = EnvelopeFile::new();
+        insert(&mut file.skylines, V1_SKYLINE_NAME, repr);
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serialize::json;
+    use test_utils::ApproxEq;
+    use {Interval, Rect, Shape, Skyline, Up, Down};
+    use super::{EnvelopeFile, V1_SKYLINE_NAME};
+
+    #[test]
+    fn round_trip() {
+        let mut top = Skyline::<Up>::bounded(0.0, 10.0);
+        top.merge(&Skyline::single(1.0, 2.0, 3.0, 4.0));
+        let bottom = Skyline::<Down>::single(0.0, -1.0, 2.0, -1.0);
+        let square = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+
+        let mut file = EnvelopeFile::new();
+        file.set_metadata("units", "pt");
+        file.insert_skyline("top", &Skyline::<Up>::empty());
+        file.insert_skyline("bottom", &bottom);
+        file.insert_skyline("top", &top);
+        file.insert_shape("square", &square);
+
+        let back = EnvelopeFile::from_json(file.to_json().as_slice()).unwrap();
+        assert_eq!(back.metadata("units"), Some("pt"));
+        assert_eq!(back.metadata("author"), None);
+        assert_eq!(back.skyline_names(), vec!["top", "bottom"]);
+        assert_eq!(back.shape_names(), vec!["square"]);
+
+        let t: Skyline<Up> = back.skyline("top").unwrap();
+        assert!(t.approx_eq(&top));
+        assert_eq!(t.domain(), Interval::new(0.0, 10.0));
+        assert!(back.skyline::<Down>("bottom").unwrap().approx_eq(&bottom));
+        assert!(back.skyline::<Down>("top").is_none());
+        assert_eq!(back.shape("square").unwrap().points(), square.points());
+    }
+
+    #[test]
+    fn migrates_v1() {
+        let sky = Skyline::<Down>::single(1.0, 2.0, 3.0, 4.0);
+        let file = EnvelopeFile::from_json(json::encode(&sky).as_slice()).unwrap();
+        assert_eq!(file.skyline_names(), vec![V1_SKYLINE_NAME]);
+        assert!(file.skyline::<Down>(V1_SKYLINE_NAME).unwrap().approx_eq(&sky));
+        assert!(file.shape_names().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let future = r#"{"version":3,"metadata":[],"skylines":[],"shapes":[]}"#;
+        assert!(EnvelopeFile::from_json(future).is_err());
+
+        let unsorted = r#"{"version":2,"metadata":[],"shapes":[],
+                           "skylines":[["a",{"dir":"Up","domain":[null,null],
+                               "buildings":[{"end":2.0,"line":null},
+                                            {"end":1.0,"line":[0.0,1.0]},
+                                            {"end":null,"line":null}]}]]}"#;
+        assert!(EnvelopeFile::from_json(unsorted).is_err());
+        assert!(EnvelopeFile::from_json("[1, 2]").is_err());
+    }
+}
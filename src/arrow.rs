@@ -0,0 +1,114 @@
+// Moving skylines in and out of columnar buffers, in the layout of Apache
+// Arrow's `Float64Array`: one contiguous buffer of doubles per field of the
+// buildings. Pipelines that hold millions of envelope pieces can hand over
+// three slices instead of serializing every building.
+//
+// The columns describe the buildings from left to right, in world
+// coordinates: building `i` is the line y = slopes[i] * x + intercepts[i] on
+// (ends[i - 1], ends[i]]. Empty buildings have an intercept of negative
+// infinity (for an upward or rightward skyline; positive infinity for the
+// others), and the last end is positive infinity. The domain isn't part of
+// the columns, so skylines come back unbounded.
+
+use {Building, Direction, InvariantError, Skyline};
+
+// Borrowed columns, for example the value buffers of three Arrow arrays.
+#[deriving(Clone, Show)]
+pub struct Columns<'a> {
+    pub ends: &'a [f64],
+    pub slopes: &'a [f64],
+    pub intercepts: &'a [f64]
+}
+
+// Owned columns, as produced by `Skyline::to_columns`.
+#[deriving(Clone, PartialEq, Show)]
+pub struct ColumnBuffers {
+    pub ends: Vec<f64>,
+    pub slopes: Vec<f64>,
+    pub intercepts: Vec<f64>
+}
+
+impl ColumnBuffers {
+    pub fn len(&self) -> uint {
+        self.ends.len()
+    }
+
+    pub fn columns<'a>(&'a self) -> Columns<'a> {
+        Columns {
+            ends: self.ends.as_slice(),
+            slopes: self.slopes.as_slice(),
+            intercepts: self.intercepts.as_slice()
+        }
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    // Builds a skyline from columns, checking the result with `validate`.
+    // This makes one pass over the columns and one allocation. Panics if
+    // the columns have different lengths.
+    pub fn from_columns(cols: Columns) -> Result<Skyline<T>, InvariantError> {
+        let n = cols.ends.len();
+        assert!(cols.slopes.len() == n && cols.intercepts.len() == n,
+                "column lengths differ: {}, {}, {}", n, cols.slopes.len(), cols.intercepts.len());
+        let mult = Direction::direction_multiplier(None::<T>);
+
+        let mut bldgs = Vec::with_capacity(n);
+        for i in range(0, n) {
+            bldgs.push(Building {
+                m: cols.slopes[i] * mult,
+                b: cols.intercepts[i] * mult,
+                end: cols.ends[i]
+            });
+        }
+
+        let sky = Skyline::from_buildings(bldgs);
+        try!(sky.validate());
+        Ok(sky)
+    }
+
+    pub fn to_columns(&self) -> ColumnBuffers {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let n = self.buildings.len();
+        let mut out = ColumnBuffers {
+            ends: Vec::with_capacity(n),
+            slopes: Vec::with_capacity(n),
+            intercepts: Vec::with_capacity(n)
+        };
+        for b in self.buildings.iter() {
+            out.ends.push(b.end);
+            out.slopes.push(b.m * mult);
+            out.intercepts.push(b.b * mult);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {InvariantError, Skyline, Up, Down};
+    use super::Columns;
+
+    #[test]
+    fn column_round_trip() {
+        let mut sky = Skyline::<Down>::single(0.0, 1.0, 2.0, 3.0);
+        sky.merge(&Skyline::single(3.0, 0.0, 4.0, 0.0));
+        let cols = sky.to_columns();
+        assert_eq!(cols.len(), 5);
+        assert_eq!(cols.slopes[1], 1.0);
+        assert_eq!(cols.intercepts[0], Float::infinity());
+
+        let back = Skyline::<Down>::from_columns(cols.columns()).unwrap();
+        assert!(back.approx_eq_envelope(&sky, 0.0));
+    }
+
+    #[test]
+    fn bad_columns() {
+        let inf = Float::infinity();
+        let cols = Columns {
+            ends: &[2.0, 1.0, inf],
+            slopes: &[0.0, 0.0, 0.0],
+            intercepts: &[1.0, 1.0, -inf]
+        };
+        assert_eq!(Skyline::<Up>::from_columns(cols).unwrap_err(), InvariantError::Unsorted(1));
+    }
+}
@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+use std::iter::range_inclusive;
+use std::mem;
+use {Building, Direction, Flip, Skyline};
+use {frame_point, merge_buildings, overlap_buildings, single_buildings};
+
+// A skyline that is split into pages of a fixed width, for very wide domains
+// that are mostly empty. Each page holds an ordinary list of buildings that
+// is empty outside the page, and pages that would be entirely empty aren't
+// stored at all, so merges and overlaps only have to look at the pages that
+// actually contain something.
+#[deriving(Clone, Show)]
+pub struct ChunkedSkyline<T: Direction> {
+    page_width: f64,
+    pages: Vec<Page>
+}
+
+#[deriving(Clone, Show)]
+struct Page {
+    index: i64,
+    buildings: Vec<Building>
+}
+
+// Splits a list of buildings into pages. All of the non-empty buildings must
+// have finite extent.
+fn chunk(bldgs: &[Building], width: f64) -> Vec<Page> {
+    let mut pages: Vec<Page> = Vec::new();
+    let mut start: f64 = Float::neg_infinity();
+
+    for b in bldgs.iter() {
+        let s = start;
+        start = b.end;
+        if b.b == Float::neg_infinity() {
+            continue;
+        }
+        assert!(s.is_finite() && b.end.is_finite(),
+                "can't split an infinitely wide building into pages");
+
+        let first = (s / width).floor() as i64;
+        let last = first.max((b.end / width).ceil() as i64 - 1);
+        for p in range_inclusive(first, last) {
+            let page_start = p as f64 * width;
+            let piece_start = s.max(page_start);
+            let piece_end = b.end.min(page_start + width);
+
+            let new_page = match pages.last() {
+                Some(page) => page.index != p,
+                None => true,
+            };
+            if new_page {
+                pages.push(Page { index: p, buildings: Vec::new() });
+            }
+
+            let page = pages.last_mut().unwrap();
+            let gap = match page.buildings.last() {
+                Some(prev) => prev.end < piece_start,
+                None => true,
+            };
+            if gap {
+                page.buildings.push(Building::empty(piece_start));
+            }
+            page.buildings.push(b.chop(piece_end));
+        }
+    }
+
+    for page in pages.iter_mut() {
+        page.buildings.push(Building::empty(Float::infinity()));
+    }
+    pages
+}
+
+// The inverse of `chunk`. Where pages meet, the zero-width gap at the start
+// of the second page is dropped, and a building that was split between the
+// pages is put back together.
+fn unchunk(pages: &[Page]) -> Vec<Building> {
+    let mut bldgs: Vec<Building> = Vec::new();
+    for page in pages.iter() {
+        // Skip the infinite building at the end of each page.
+        let n = page.buildings.len();
+        for b in page.buildings.slice_to(n - 1).iter() {
+            let (redundant, same_line) = match bldgs.last() {
+                Some(prev) => (b.b == Float::neg_infinity() && prev.end >= b.end,
+                               prev.m == b.m && prev.b == b.b),
+                None => (false, false),
+            };
+            if redundant {
+                continue;
+            }
+            if same_line {
+                bldgs.last_mut().unwrap().end = b.end;
+            } else {
+                bldgs.push(*b);
+            }
+        }
+    }
+    bldgs.push(Building::empty(Float::infinity()));
+    bldgs
+}
+
+impl<T: Direction> ChunkedSkyline<T> {
+    pub fn empty(page_width: f64) -> ChunkedSkyline<T> {
+        assert!(page_width > 0.0, "page width must be positive, not {}", page_width);
+        ChunkedSkyline {
+            page_width: page_width,
+            pages: Vec::new()
+        }
+    }
+
+    pub fn single(page_width: f64, x1: f64, y1: f64, x2: f64, y2: f64)
+            -> ChunkedSkyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+        let bldgs = single_buildings(mult, u1, v1, u2, v2);
+        ChunkedSkyline::from_buildings(page_width, bldgs.as_slice())
+    }
+
+    pub fn from_skyline(sky: &Skyline<T>, page_width: f64) -> ChunkedSkyline<T> {
+        assert!(!sky.is_bounded(), "ChunkedSkyline doesn't support bounded domains");
+        ChunkedSkyline::from_buildings(page_width, sky.buildings.as_slice())
+    }
+
+    fn from_buildings(page_width: f64, bldgs: &[Building]) -> ChunkedSkyline<T> {
+        assert!(page_width > 0.0, "page width must be positive, not {}", page_width);
+        ChunkedSkyline {
+            page_width: page_width,
+            pages: chunk(bldgs, page_width)
+        }
+    }
+
+    pub fn to_skyline(&self) -> Skyline<T> {
+        Skyline::from_buildings(unchunk(self.pages.as_slice()))
+    }
+
+    pub fn page_width(&self) -> f64 {
+        self.page_width
+    }
+
+    // The number of non-empty pages.
+    pub fn page_count(&self) -> uint {
+        self.pages.len()
+    }
+
+    // Pages touch their neighbors at the boundary between them, so each
+    // page is compared with the other skyline's pages on either side as
+    // well as with the one in the same place.
+    pub fn overlap<S: Flip<T>>(&self, other: &ChunkedSkyline<S>) -> f64 {
+        assert!(self.page_width == other.page_width,
+                "can't overlap skylines with different page widths");
+        let mut dist: f64 = Float::neg_infinity();
+        let mut j = 0u;
+
+        for a in self.pages.iter() {
+            while j < other.pages.len() && other.pages[j].index < a.index - 1 {
+                j += 1;
+            }
+            for b in other.pages.slice_from(j).iter().take_while(|b| b.index <= a.index + 1) {
+                dist = dist.max(overlap_buildings(a.buildings.as_slice(),
+                                                  b.buildings.as_slice()));
+            }
+        }
+
+        dist
+    }
+
+    pub fn merge(&mut self, other: &ChunkedSkyline<T>) {
+        assert!(self.page_width == other.page_width,
+                "can't merge skylines with different page widths");
+        let mine = mem::replace(&mut self.pages, Vec::new());
+        let mut pages = Vec::with_capacity(mine.len() + other.pages.len());
+        let mut mine = mine.into_iter().peekable();
+        let mut theirs = other.pages.iter().peekable();
+
+        loop {
+            let ord = match (mine.peek(), theirs.peek()) {
+                (Some(a), Some(b)) => a.index.cmp(&b.index),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match ord {
+                Ordering::Less => pages.push(mine.next().unwrap()),
+                Ordering::Greater => pages.push(theirs.next().unwrap().clone()),
+                Ordering::Equal => {
+                    let a = mine.next().unwrap();
+                    let b = theirs.next().unwrap();
+                    let mut out = Vec::with_capacity(a.buildings.len() + b.buildings.len());
+                    merge_buildings(a.buildings.as_slice(), b.buildings.as_slice(), &mut out);
+                    pages.push(Page { index: a.index, buildings: out });
+                },
+            }
+        }
+
+        self.pages = pages;
+    }
+
+    pub fn slide(&mut self, x: f64) {
+        let shift = x / self.page_width;
+        if shift == shift.floor() {
+            // We're moving by a whole number of pages, so the page
+            // boundaries don't change.
+            for page in self.pages.iter_mut() {
+                page.index += shift as i64;
+                for b in page.buildings.iter_mut() {
+                    b.slide(x);
+                }
+            }
+        } else {
+            let mut bldgs = unchunk(self.pages.as_slice());
+            for b in bldgs.iter_mut() {
+                b.slide(x);
+            }
+            self.pages = chunk(bldgs.as_slice(), self.page_width);
+        }
+    }
+
+    pub fn bump(&mut self, y: f64) {
+        let y = y * Direction::direction_multiplier(None::<T>);
+        for page in self.pages.iter_mut() {
+            for b in page.buildings.iter_mut() {
+                b.b += y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Right, Skyline, Up, Down};
+    use super::ChunkedSkyline;
+
+    fn sparse() -> Skyline<Up> {
+        Skyline::from_rects(&[Rect::new(0.0, 0.0, 10.0, 1.0),
+                              Rect::new(995.0, 0.0, 1010.0, 2.0),
+                              Rect::new(1.0e7, 0.0, 1.0e7 + 5.0, 3.0)])
+    }
+
+    #[test]
+    fn chunked_skips_empty_pages() {
+        let sky = sparse();
+        let chunked = ChunkedSkyline::from_skyline(&sky, 1000.0);
+        assert_eq!(chunked.page_count(), 3);
+
+        let mut probe = Skyline::<Down>::single(1000.0, 0.0, 1001.0, 0.0);
+        probe.merge(&Skyline::single(1.0e7 - 1.0, 0.0, 1.0e7 + 1.0, 0.0));
+        let chunked_probe = ChunkedSkyline::from_skyline(&probe, 1000.0);
+        let d = chunked.overlap(&chunked_probe);
+        assert!(d.approx_eq(sky.overlap(&probe)), "d = {}", d);
+        assert!(d.approx_eq(3.0), "d = {}", d);
+    }
+
+    #[test]
+    fn chunked_merge_matches_skyline() {
+        let mut sky = sparse();
+        let mut chunked = ChunkedSkyline::from_skyline(&sky, 1000.0);
+        let other = Skyline::<Up>::single(500.0, 5.0, 1500.0, 5.0);
+        sky.merge(&other);
+        chunked.merge(&ChunkedSkyline::from_skyline(&other, 1000.0));
+        assert_eq!(chunked.page_count(), 3);
+
+        for &x in [0.0, 700.0, 1005.0, 2000.0, 1.0e7].iter() {
+            let probe = Skyline::<Down>::single(x, 0.0, x + 1.0, 0.0);
+            let d1 = sky.overlap(&probe);
+            let d2 = chunked.to_skyline().overlap(&probe);
+            assert!(d1.approx_eq(d2), "at {}: {} != {}", x, d1, d2);
+        }
+    }
+
+    #[test]
+    fn chunked_page_boundaries() {
+        let sky = Skyline::<Up>::single(0.0, 5.0, 1000.0, 5.0);
+        let probe = Skyline::<Down>::single(1000.0, 0.0, 1001.0, 0.0);
+        let chunked = ChunkedSkyline::from_skyline(&sky, 1000.0);
+        let chunked_probe = ChunkedSkyline::from_skyline(&probe, 1000.0);
+        assert!(sky.overlap(&probe).approx_eq(5.0));
+        assert!(chunked.overlap(&chunked_probe).approx_eq(5.0));
+        assert!(chunked_probe.overlap(&chunked).approx_eq(5.0));
+
+        // A building that crosses page boundaries comes back in one piece.
+        let wide = Skyline::<Up>::single(500.0, 1.0, 2500.0, 3.0);
+        let back = ChunkedSkyline::from_skyline(&wide, 1000.0).to_skyline();
+        assert!(back.approx_eq(&wide));
+    }
+
+    #[test]
+    fn chunked_slide() {
+        let mut chunked = ChunkedSkyline::<Up>::single(100.0, 10.0, 1.0, 20.0, 1.0);
+        chunked.slide(85.0);
+        assert_eq!(chunked.page_count(), 2);
+        chunked.slide(-100.0);
+        assert_eq!(chunked.page_count(), 2);
+        chunked.bump(1.0);
+
+        let probe = Skyline::<Down>::single(4.0, 0.0, 5.0, 0.0);
+        let d = chunked.overlap(&ChunkedSkyline::from_skyline(&probe, 100.0));
+        assert!(d.approx_eq(2.0), "d = {}", d);
+    }
+
+    #[test]
+    fn chunked_horizontal_single() {
+        let chunked = ChunkedSkyline::<Right>::single(10.0, 1.0, 2.0, 3.0, 25.0);
+        let sky = Skyline::<Right>::single(1.0, 2.0, 3.0, 25.0);
+        assert_eq!(chunked.page_count(), 3);
+        assert!(chunked.to_skyline().approx_eq(&sky));
+    }
+}
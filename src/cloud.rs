@@ -0,0 +1,151 @@
+use {Down, Rect, Shape, Skyline, SkylineConfig, Up};
+
+// Greedy placement for word clouds and tag clouds: each shape is put as
+// close to the origin as it can get while touching the outside of the
+// shapes that are already there.
+//
+// We keep the envelope of the placed shapes on all four sides. A new shape
+// is dropped onto each side in turn, anywhere along the extent of what's
+// already there, and the place where its center ends up closest to the
+// origin wins. Since every candidate lies outside one of the envelopes,
+// shapes never overlap (but the cloud has no holes, either).
+pub struct WordCloud {
+    padding: f64,
+    bounds: Option<Rect>,
+    top: Skyline<Up>,
+    bottom: Skyline<Down>,
+    // The left and right sides are stored with x and y swapped, so that
+    // they're functions of y.
+    right: Skyline<Up>,
+    left: Skyline<Down>
+}
+
+fn swapped(shape: &Shape) -> Shape {
+    let points: Vec<(f64, f64)> = shape.points().iter().map(|&(x, y)| (y, x)).collect();
+    Shape::from_polygon(points.as_slice())
+}
+
+fn center(r: &Rect) -> (f64, f64) {
+    ((r.x0 + r.x1) / 2.0, (r.y0 + r.y1) / 2.0)
+}
+
+impl WordCloud {
+    // Neighboring shapes will be at least `padding` apart, measured
+    // vertically or horizontally depending on which side they were attached
+    // to.
+    pub fn new(padding: f64) -> WordCloud {
+        WordCloud::with_config(padding, SkylineConfig::new())
+    }
+
+    // The envelopes of the cloud all have this configuration.
+    pub fn with_config(padding: f64, config: SkylineConfig) -> WordCloud {
+        WordCloud {
+            padding: padding,
+            bounds: None,
+            top: Skyline::with_config(config.clone()),
+            bottom: Skyline::with_config(config.clone()),
+            right: Skyline::with_config(config.clone()),
+            left: Skyline::with_config(config)
+        }
+    }
+
+    // Places a shape, returning how far it was moved.
+    pub fn place(&mut self, shape: &Shape) -> (f64, f64) {
+        let b = shape.bounds();
+        let (cx, cy) = center(&b);
+        let (dx, dy) = match self.bounds {
+            None => (-cx, -cy),
+            Some(r) => {
+                let pad = self.padding;
+                let flipped = swapped(shape);
+                // The ranges of offsets that keep the center within the
+                // cloud's extent.
+                let xs = (r.x0 - cx, r.x1 - cx);
+                let ys = (r.y0 - cy, r.y1 - cy);
+
+                let p = self.top.fit(&shape.skyline::<Down>(), xs);
+                let mut candidates = vec![(p.dx, p.dy + pad)];
+                let p = self.bottom.fit(&shape.skyline::<Up>(), xs);
+                candidates.push((p.dx, p.dy - pad));
+                let p = self.right.fit(&flipped.skyline::<Down>(), ys);
+                candidates.push((p.dy + pad, p.dx));
+                let p = self.left.fit(&flipped.skyline::<Up>(), ys);
+                candidates.push((p.dy - pad, p.dx));
+
+                let dist = |&(dx, dy): &(f64, f64)| (cx + dx).hypot(cy + dy);
+                let mut best = (0.0, 0.0);
+                let mut best_dist: f64 = Float::infinity();
+                for c in candidates.iter() {
+                    // Where nothing stops the shape, the offsets are
+                    // infinite.
+                    let d = dist(c);
+                    if d < best_dist {
+                        best = *c;
+                        best_dist = d;
+                    }
+                }
+                assert!(best_dist.is_finite(), "no room for the shape");
+                best
+            },
+        };
+
+        let moved = shape.translate(dx, dy);
+        let flipped = swapped(&moved);
+        self.top.merge(&moved.skyline());
+        self.bottom.merge(&moved.skyline());
+        self.right.merge(&flipped.skyline());
+        self.left.merge(&flipped.skyline());
+        let mb = moved.bounds();
+        self.bounds = Some(match self.bounds {
+            Some(r) => r.union(&mb),
+            None => mb,
+        });
+        (dx, dy)
+    }
+
+    // The smallest rectangle containing everything that was placed.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+}
+
+// Places the shapes one after another, in order (so put the important ones
+// first), and returns how far each one was moved.
+pub fn layout_cloud(shapes: &[Shape], padding: f64) -> Vec<(f64, f64)> {
+    let mut cloud = WordCloud::new(padding);
+    shapes.iter().map(|s| cloud.place(s)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Shape};
+    use super::layout_cloud;
+
+    #[test]
+    fn cloud_doesnt_overlap() {
+        let shapes = vec![Shape::from_rect(&Rect::new(0.0, 0.0, 6.0, 2.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 4.0, 1.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 3.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 3.0, 1.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 2.0))];
+        let offsets = layout_cloud(shapes.as_slice(), 0.5);
+
+        let placed: Vec<Rect> = shapes.iter().zip(offsets.iter())
+            .map(|(s, &(dx, dy))| s.bounds().translate(dx, dy))
+            .collect();
+        assert!(placed[0].x0.approx_eq(-3.0) && placed[0].y0.approx_eq(-1.0));
+        // The second shape goes right above or below the first.
+        assert!(placed[1].y0.approx_eq(1.5) || placed[1].y1.approx_eq(-1.5), "{}", placed[1]);
+
+        for i in range(0, placed.len()) {
+            for j in range(0, i) {
+                match placed[i].intersection(&placed[j]) {
+                    Some(r) => assert!(r.width() <= 0.0 || r.height() <= 0.0,
+                                       "{} and {} overlap", placed[i], placed[j]),
+                    None => {},
+                }
+            }
+        }
+    }
+}
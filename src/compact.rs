@@ -0,0 +1,57 @@
+// A compact text format for skylines, for logging over slow links (like
+// RTT on an embedded target) where `{}` of a whole skyline is too verbose.
+//
+// A skyline is written as its direction followed by its non-empty segments
+// in world coordinates, e.g. `Up[0,1>2,1 3,0>4,2]` for a segment from
+// (0, 1) to (2, 1) and another from (3, 0) to (4, 2). Formatting goes
+// straight to the formatter without allocating, so it only needs
+// `core::fmt`.
+
+use std::fmt;
+use {Direction, Skyline};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+pub struct Compact<'a, T: 'a> {
+    sky: &'a Skyline<T>
+}
+
+impl<'a, T: Direction> fmt::Show for Compact<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}[", self.sky.dir()));
+        let mut first = true;
+        for s in self.sky.segments() {
+            if !first {
+                try!(write!(f, " "));
+            }
+            first = false;
+            try!(write!(f, "{},{}>{},{}", s.x0, s.y0, s.x1, s.y1));
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    // Something to pass to `write!` to log this skyline in the compact
+    // format.
+    pub fn compact<'a>(&'a self) -> Compact<'a, T> {
+        Compact { sky: self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Skyline, Up, Down};
+
+    #[test]
+    fn compact_format() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        sky.merge(&Skyline::single(3.0, 0.0, 4.0, 2.0));
+        let s = format!("{}", sky.compact());
+        assert!(s.as_slice().starts_with("Up["));
+        assert_eq!(s.as_slice().matches_indices(">").count(), 2);
+
+        let empty = Skyline::<Down>::empty();
+        assert_eq!(format!("{}", empty.compact()).as_slice(), "Down[]");
+    }
+}
@@ -0,0 +1,199 @@
+use {Direction, Skyline, MAX_SLOPE};
+use {frame_point, single_buildings_capped};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// Settings that a skyline carries around with it, and applies to itself
+// whenever it changes. Start from `new` or one of the presets, and adjust it
+// with the `with_` methods. Skylines built from other skylines inherit the
+// configuration.
+#[deriving(Clone, PartialEq, Show)]
+pub struct SkylineConfig {
+    // If set, breakpoints are rounded to multiples of this, both when the
+    // configuration is applied and after every merge. That loses a little
+    // accuracy, but it means that shapes that are nearly the same end up
+    // with exactly the same buildings, and it keeps slivers from piling up.
+    pub snap_grid: Option<f64>,
+    // Whether heights are rounded to the grid too.
+    pub snap_heights: bool,
+    // If set, the skyline is simplified with this tolerance (see
+    // `Skyline::simplify`) whenever the configuration is applied. This
+    // keeps the number of buildings down in long packing loops.
+    pub simplify_tolerance: Option<f64>,
+    // Segments steeper than this are made less steep (and raised, so that
+    // they stay above their end points). Nearly vertical segments make the
+    // arithmetic unstable, so a larger cap is more accurate but less
+    // robust. This only applies to constructors that take a configuration
+    // and to operations on a skyline with this configuration.
+    pub max_slope: f64
+}
+
+impl SkylineConfig {
+    // No snapping or simplification: every breakpoint stays where it is.
+    pub fn new() -> SkylineConfig {
+        SkylineConfig {
+            snap_grid: None,
+            snap_heights: false,
+            simplify_tolerance: None,
+            max_slope: MAX_SLOPE
+        }
+    }
+
+    pub fn snapped(grid: f64) -> SkylineConfig {
+        SkylineConfig::new().with_grid(grid)
+    }
+
+    // Snaps breakpoints to a grid of 1e-6 and simplifies with the same
+    // tolerance, so that merges don't accumulate slivers. That's far below
+    // anything visible if coordinates are around 1 (see `CoordinateSystem`).
+    pub fn fast() -> SkylineConfig {
+        SkylineConfig::new().with_grid(1e-6).with_simplification(1e-6)
+    }
+
+    // For skylines measured in units with `scale` pixels each: breakpoints
+    // and heights are both snapped to the pixel grid.
+    pub fn pixel_grid(scale: f64) -> SkylineConfig {
+        assert!(scale > 0.0, "the scale must be positive ({})", scale);
+        SkylineConfig::new().with_grid(1.0 / scale).with_snapped_heights(true)
+    }
+
+    pub fn with_grid(self, grid: f64) -> SkylineConfig {
+        assert!(grid > 0.0, "the grid size must be positive ({})", grid);
+        SkylineConfig { snap_grid: Some(grid), ..self }
+    }
+
+    pub fn with_snapped_heights(self, snap: bool) -> SkylineConfig {
+        SkylineConfig { snap_heights: snap, ..self }
+    }
+
+    pub fn with_simplification(self, tolerance: f64) -> SkylineConfig {
+        assert!(tolerance >= 0.0, "negative tolerance ({})", tolerance);
+        SkylineConfig { simplify_tolerance: Some(tolerance), ..self }
+    }
+
+    pub fn with_max_slope(self, max_slope: f64) -> SkylineConfig {
+        assert!(max_slope > 0.0, "the maximum slope must be positive ({})", max_slope);
+        SkylineConfig { max_slope: max_slope, ..self }
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    // An empty skyline with the given configuration.
+    pub fn with_config(config: SkylineConfig) -> Skyline<T> {
+        let mut sky = Skyline::empty();
+        sky.config = config;
+        sky
+    }
+
+    // Like `single`, but with a configuration (whose slope cap is used for
+    // the segment).
+    pub fn single_with_config(config: SkylineConfig,
+                              x1: f64, y1: f64, x2: f64, y2: f64) -> Skyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+        let bldgs = single_buildings_capped(mult, config.max_slope, u1, v1, u2, v2);
+        let mut sky = Skyline::from_buildings(bldgs);
+        sky.set_config(config);
+        sky
+    }
+
+    // Sets the configuration and applies it, for use right after a
+    // constructor.
+    pub fn configured(mut self, config: SkylineConfig) -> Skyline<T> {
+        self.set_config(config);
+        self
+    }
+
+    pub fn config(&self) -> &SkylineConfig {
+        &self.config
+    }
+
+    // Replaces the configuration, and applies it right away.
+    pub fn set_config(&mut self, config: SkylineConfig) {
+        self.config = config;
+        self.apply_config();
+        self.debug_assert_valid();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Building, Rect, Skyline, Up};
+    use super::SkylineConfig;
+
+    #[test]
+    fn snap_on_merge() {
+        let mut sky = Skyline::<Up>::single(0.1, 1.0, 2.9, 1.0);
+        sky.set_config(SkylineConfig::snapped(1.0));
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 0.0, b: 1.0, end: 3.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+
+        // A sliver narrower than the grid disappears.
+        sky.merge(&Skyline::single(0.95, 2.0, 1.04, 2.0));
+        assert!(sky.approx_eq(&target));
+    }
+
+    #[test]
+    fn snap_heights() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.2, 2.0, 1.9);
+        sky.set_config(SkylineConfig::snapped(0.5).with_snapped_heights(true));
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 1.0, b: 0.0, end: 2.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+    }
+
+    #[test]
+    fn presets() {
+        let px = SkylineConfig::pixel_grid(4.0);
+        assert_eq!(px.snap_grid, Some(0.25));
+        assert!(px.snap_heights);
+
+        let mut sky = Skyline::<Up>::with_config(SkylineConfig::fast());
+        sky.merge(&Skyline::single(0.0, 1.0, 1.0, 1.0));
+        sky.merge(&Skyline::single(1.0, 1.0, 2.0, 1.0000000001));
+        assert_eq!(sky.segments().count(), 1);
+
+        let merged = Skyline::merge_all(vec![sky.clone(), Skyline::single(3.0, 0.0, 4.0, 0.0)]);
+        assert_eq!(merged.config(), sky.config());
+    }
+
+    #[test]
+    fn steep_slopes() {
+        // A slope of 1e5, which the default cap flattens.
+        let default = Skyline::<Up>::single(0.0, 0.0, 1e-4, 10.0);
+        assert!(default.height_at(0.0) > 9.0);
+
+        let config = SkylineConfig::new().with_max_slope(1e6);
+        let steep = Skyline::<Up>::single_with_config(config.clone(), 0.0, 0.0, 1e-4, 10.0);
+        assert!(steep.height_at(0.0).approx_eq(0.0));
+        assert_eq!(steep.validate(), Ok(()));
+
+        let triangle = [(0.0, 0.0), (1e-4, 10.0), (2e-4, 0.0)];
+        assert!(Skyline::<Up>::from_polygon(&triangle).height_at(0.0) > 9.0);
+        let steep = Skyline::<Up>::from_polygon_with_config(config, &triangle);
+        assert!(steep.height_at(0.0).approx_eq(0.0));
+        assert_eq!(steep.validate(), Ok(()));
+    }
+
+    #[test]
+    fn configured_constructors() {
+        let rects = [Rect::new(0.1, 0.0, 1.9, 1.2)];
+        let config = SkylineConfig::snapped(1.0).with_snapped_heights(true);
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 0.0, b: 1.0, end: 2.0 },
+                 Building::empty(Float::infinity())));
+
+        let sky = Skyline::<Up>::from_rects(&rects).configured(config.clone());
+        assert!(sky.approx_eq(&target));
+        assert_eq!(sky.config(), &config);
+        let sky = Skyline::<Up>::try_from_rects(&rects).unwrap().configured(config);
+        assert!(sky.approx_eq(&target));
+    }
+}
@@ -0,0 +1,613 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter::FromIterator;
+use {Building, Dir, Direction, Rect, Skyline, SkylineConfig, SkylineError, MAX_SLOPE};
+use {frame_point, merge_buildings, merge_many, single_buildings, single_buildings_capped};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+#[cfg(feature = "parallel")]
+use std::os;
+#[cfg(feature = "parallel")]
+use std::sync::Future;
+#[cfg(feature = "deterministic-parallel")]
+use merge_round;
+
+// A rectangle that is active during the sweep in `from_rects`. The heap
+// orders them by height, so `Tower`s must never contain NaN.
+#[deriving(PartialEq, PartialOrd)]
+struct Tower {
+    height: f64,
+    end: f64
+}
+
+impl Eq for Tower {}
+
+impl Ord for Tower {
+    fn cmp(&self, other: &Tower) -> Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
+
+// Splits the lists into one batch per CPU, merges each batch in its own task,
+// and then merges the results. The order in which things get merged depends
+// on the number of CPUs, so the result can differ in the last bits from one
+// machine to the next.
+#[cfg(all(feature = "parallel", not(feature = "deterministic-parallel")))]
+fn merge_many_parallel(lists: Vec<Vec<Building>>) -> Vec<Building> {
+    let tasks = os::num_cpus().max(1);
+    if lists.len() < 2 * tasks {
+        return merge_many(lists);
+    }
+
+    let per_task = (lists.len() + tasks - 1) / tasks;
+    let mut batches = Vec::with_capacity(tasks);
+    let mut iter = lists.into_iter();
+    loop {
+        let batch: Vec<Vec<Building>> = iter.by_ref().take(per_task).collect();
+        if batch.is_empty() {
+            break;
+        }
+        batches.push(batch);
+    }
+
+    let futures: Vec<Future<Vec<Building>>> = batches.into_iter()
+        .map(|batch| Future::spawn(proc() merge_many(batch)))
+        .collect();
+    merge_many(futures.into_iter().map(|mut f| f.get()).collect())
+}
+
+// Like `merge_many`, but each round of pairwise merges is split between one
+// task per CPU. Every batch starts at an even position, so the pairs are the
+// same as in `merge_many` and the result is too, down to the last bit, no
+// matter how many CPUs there are.
+#[cfg(feature = "deterministic-parallel")]
+fn merge_many_parallel(mut lists: Vec<Vec<Building>>) -> Vec<Building> {
+    let tasks = os::num_cpus().max(1);
+    // The last few rounds aren't worth spreading out.
+    while lists.len() >= 2 * tasks {
+        let per_task = ((lists.len() + tasks - 1) / tasks + 1) / 2 * 2;
+        let mut batches = Vec::with_capacity(tasks);
+        let mut iter = lists.into_iter();
+        loop {
+            let batch: Vec<Vec<Building>> = iter.by_ref().take(per_task).collect();
+            if batch.is_empty() {
+                break;
+            }
+            batches.push(batch);
+        }
+
+        let futures: Vec<Future<Vec<Vec<Building>>>> = batches.into_iter()
+            .map(|batch| Future::spawn(proc() merge_round(batch)))
+            .collect();
+        lists = Vec::new();
+        for mut f in futures.into_iter() {
+            lists.extend(f.get().into_iter());
+        }
+    }
+    merge_many(lists)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn merge_many_parallel(lists: Vec<Vec<Building>>) -> Vec<Building> {
+    merge_many(lists)
+}
+
+fn check_finite(xs: &[f64]) -> Result<(), SkylineError> {
+    for &x in xs.iter() {
+        if x.is_nan() {
+            return Err(SkylineError::NotANumber);
+        } else if x.is_infinite() {
+            return Err(SkylineError::Infinite(x));
+        }
+    }
+    Ok(())
+}
+
+fn check_width(x1: f64, x2: f64) -> Result<(), SkylineError> {
+    if x1 == x2 { Err(SkylineError::ZeroWidth(x1)) } else { Ok(()) }
+}
+
+impl<T: Direction> Skyline<T> {
+    // Merges a whole collection of skylines at once. This is much faster
+    // than merging them one at a time, since it merges them in pairs, and
+    // then pairs of pairs, and so on. With the "parallel" feature, the work
+    // is spread over all the CPUs. With "deterministic-parallel" as well, it
+    // is split up in a way that gives exactly the same result as merging on
+    // one CPU.
+    //
+    // The result is defined on the intersection of all the domains, and
+    // has the configuration of the first skyline.
+    pub fn merge_all(skylines: Vec<Skyline<T>>) -> Skyline<T> {
+        let mut lo: f64 = Float::neg_infinity();
+        let mut hi: f64 = Float::infinity();
+        let mut config = None;
+        let mut lists = Vec::with_capacity(skylines.len());
+        for sky in skylines.into_iter() {
+            lo = lo.max(sky.domain.lo);
+            hi = hi.min(sky.domain.hi);
+            if config.is_none() {
+                config = Some(sky.config.clone());
+            }
+            lists.push(sky.buildings);
+        }
+
+        let mut ret = Skyline::from_buildings(merge_many_parallel(lists));
+        if lo > Float::neg_infinity() || hi < Float::infinity() {
+            ret.bound(lo, hi);
+        }
+        match config {
+            Some(c) => ret.set_config(c),
+            None => {},
+        }
+        ret
+    }
+
+    // Like `merge_all`, but borrows the skylines instead of taking them.
+    // The first round of pairwise merges reads straight from the inputs, so
+    // they are never copied, and the total work is O(n log k) for k skylines
+    // with n buildings between them.
+    pub fn merge_k(inputs: &[&Skyline<T>]) -> Skyline<T> {
+        if inputs.is_empty() {
+            return Skyline::empty();
+        }
+
+        let mut lo: f64 = Float::neg_infinity();
+        let mut hi: f64 = Float::infinity();
+        let mut lists = Vec::with_capacity((inputs.len() + 1) / 2);
+        for pair in inputs.chunks(2) {
+            lo = lo.max(pair[0].domain.lo);
+            hi = hi.min(pair[0].domain.hi);
+            if pair.len() == 2 {
+                lo = lo.max(pair[1].domain.lo);
+                hi = hi.min(pair[1].domain.hi);
+                let (a, b) = (pair[0].buildings.as_slice(), pair[1].buildings.as_slice());
+                let mut out = Vec::with_capacity(a.len() + b.len());
+                merge_buildings(a, b, &mut out);
+                lists.push(out);
+            } else {
+                lists.push(pair[0].buildings.clone());
+            }
+        }
+
+        let mut ret = Skyline::from_buildings(merge_many(lists));
+        if lo > Float::neg_infinity() || hi < Float::infinity() {
+            ret.bound(lo, hi);
+        }
+        ret.set_config(inputs[0].config.clone());
+        ret
+    }
+
+    // The points describe a simple polygon, in either orientation; the edge
+    // from the last point back to the first is implied.
+    pub fn from_polygon(points: &[(f64, f64)]) -> Skyline<T> {
+        Skyline::from_polygon_capped(MAX_SLOPE, points)
+    }
+
+    // Like `from_polygon`, but with a configuration (whose slope cap is used
+    // for the edges).
+    pub fn from_polygon_with_config(config: SkylineConfig, points: &[(f64, f64)])
+            -> Skyline<T> {
+        let mut sky = Skyline::from_polygon_capped(config.max_slope, points);
+        sky.set_config(config);
+        sky
+    }
+
+    fn from_polygon_capped(max_slope: f64, points: &[(f64, f64)]) -> Skyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let swapped: Vec<(f64, f64)> =
+            points.iter().map(|&(x, y)| frame_point::<T>(x, y)).collect();
+        let points = swapped.as_slice();
+        let n = points.len();
+
+        // Twice the signed area: positive if the points are counter-clockwise.
+        let mut area = 0.0;
+        for i in range(0, n) {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % n];
+            area += x1 * y2 - x2 * y1;
+        }
+        let orientation = if area > 0.0 { 1.0 } else { -1.0 };
+
+        let mut edges = Vec::new();
+        for i in range(0, n) {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % n];
+
+            // In a counter-clockwise polygon, the outward normal of an edge
+            // is (dy, -dx), so the edge faces our way if -dx has the same
+            // sign as the direction multiplier. Vertical edges never
+            // contribute anything that their neighbors don't. If the polygon
+            // is degenerate, we don't know which way is out so we keep
+            // everything.
+            let facing = -(x2 - x1) * orientation * mult;
+            if facing > 0.0 || (area == 0.0 && x1 != x2) {
+                edges.push(single_buildings_capped(mult, max_slope, x1, y1, x2, y2));
+            }
+        }
+
+        Skyline::from_buildings(merge_many(edges))
+    }
+
+    // The skyline of a collection of rectangles: the top edges for an upward
+    // skyline and the bottom edges for a downward one (and the right and
+    // left edges for horizontal ones).
+    //
+    // Since the edges are all flat, we can do better than merging: a sweep
+    // from left to right, keeping the active rectangles in a heap, produces
+    // the envelope in O(n log n) time with one building per change in
+    // height.
+    pub fn from_rects(rects: &[Rect]) -> Skyline<T> {
+        if Direction::dir(None::<T>).is_horizontal() {
+            let swapped: Vec<Rect> = rects.iter()
+                .map(|r| Rect::new(r.y0, r.x0, r.y1, r.x1))
+                .collect();
+            return Skyline::from_rects_in_frame(swapped.as_slice());
+        }
+        Skyline::from_rects_in_frame(rects)
+    }
+
+    // `from_rects`, with the rectangles already in the skyline's frame.
+    fn from_rects_in_frame(rects: &[Rect]) -> Skyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let height = |r: &Rect| if mult > 0.0 { r.y1 } else { -r.y0 };
+
+        // Rectangles with no width are vertical line segments, which the
+        // sweep can't see. We merge those in at the end.
+        let mut sorted = Vec::with_capacity(rects.len());
+        let mut slivers = Vec::new();
+        for r in rects.iter() {
+            assert!(!r.x0.is_nan() && !r.x1.is_nan() && !height(r).is_nan(),
+                    "NaN in rectangle {}", r);
+            if r.x0 < r.x1 {
+                sorted.push(*r);
+            } else {
+                slivers.push(single_buildings(mult, r.x0, r.y0, r.x1, r.y1));
+            }
+        }
+        sorted.sort_by(|a, b| a.x0.partial_cmp(&b.x0).unwrap());
+
+        let mut xs: Vec<f64> = Vec::with_capacity(2 * sorted.len());
+        for r in sorted.iter() {
+            xs.push(r.x0);
+            xs.push(r.x1);
+        }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs.dedup();
+
+        let mut bldgs = Vec::new();
+        let mut active = BinaryHeap::new();
+        let mut cur: f64 = Float::neg_infinity();
+        let mut i = 0u;
+        for &x in xs.iter() {
+            while i < sorted.len() && sorted[i].x0 <= x {
+                active.push(Tower { height: height(&sorted[i]), end: sorted[i].x1 });
+                i += 1;
+            }
+            loop {
+                match active.top() {
+                    Some(t) if t.end <= x => {},
+                    _ => break,
+                }
+                active.pop();
+            }
+
+            let next = match active.top() {
+                Some(t) => t.height,
+                None => Float::neg_infinity(),
+            };
+            if next != cur {
+                bldgs.push(Building { m: 0.0, b: cur, end: x });
+                cur = next;
+            }
+        }
+        bldgs.push(Building::empty(Float::infinity()));
+
+        for sliver in slivers.iter() {
+            let mut out = Vec::with_capacity(bldgs.len() + sliver.len());
+            merge_buildings(bldgs.as_slice(), sliver.as_slice(), &mut out);
+            bldgs = out;
+        }
+
+        Skyline::from_buildings(bldgs)
+    }
+
+    // Builds a skyline from a row of samples, such as a column profile of a
+    // raster mask: sample `i` covers [x0 + i * dx, x0 + (i + 1) * dx] at
+    // height `heights[i]`. NaN samples are empty. Runs of equal samples
+    // become a single building, so this takes linear time and produces no
+    // more buildings than there are changes in height. Use `simplify`
+    // afterwards to smooth out noisy data.
+    pub fn from_samples(x0: f64, dx: f64, heights: &[f64]) -> Skyline<T> {
+        assert!(dx > 0.0, "sample spacing must be positive ({})", dx);
+        let mult = Direction::direction_multiplier(None::<T>);
+
+        let mut bldgs = vec![Building::empty(x0)];
+        for (i, &h) in heights.iter().enumerate() {
+            let end = x0 + (i + 1) as f64 * dx;
+            let b = if h.is_nan() { Float::neg_infinity() } else { h * mult };
+            if bldgs.last().unwrap().b == b {
+                bldgs.last_mut().unwrap().end = end;
+            } else {
+                bldgs.push(Building { m: 0.0, b: b, end: end });
+            }
+        }
+
+        if bldgs.last().unwrap().b == Float::neg_infinity() {
+            bldgs.last_mut().unwrap().end = Float::infinity();
+        } else {
+            bldgs.push(Building::empty(Float::infinity()));
+        }
+        Skyline::from_buildings(bldgs)
+    }
+
+    // Builds the envelope of the opaque part of a bitmap, such as an alpha
+    // mask. The pixels are in rows from top to bottom, and a pixel is opaque
+    // if it's at least `threshold`. The bitmap is placed right side up in
+    // [0, width] x [0, height], with each pixel a unit square, so that the
+    // top row covers [height - 1, height].
+    //
+    // Upward and downward skylines are built column by column, giving the
+    // top and the bottom of the opaque region. Leftward and rightward ones
+    // are built row by row over y, giving its left and right sides.
+    pub fn from_mask(width: uint, height: uint, pixels: &[u8], threshold: u8) -> Skyline<T> {
+        assert!(pixels.len() == width * height,
+                "expected {} pixels, found {}", width * height, pixels.len());
+        let opaque = |col: uint, row: uint| pixels[row * width + col] >= threshold;
+        let nan: f64 = Float::nan();
+
+        let samples: Vec<f64> = match Direction::dir(None::<T>) {
+            Dir::Up => range(0, width).map(|c| {
+                match range(0, height).find(|&r| opaque(c, r)) {
+                    Some(r) => (height - r) as f64,
+                    None => nan,
+                }
+            }).collect(),
+            Dir::Down => range(0, width).map(|c| {
+                match range(0, height).rev().find(|&r| opaque(c, r)) {
+                    Some(r) => (height - r - 1) as f64,
+                    None => nan,
+                }
+            }).collect(),
+            // Sample j covers [j, j + 1] in y, which is row height - 1 - j.
+            Dir::Left => range(0, height).map(|j| {
+                match range(0, width).find(|&c| opaque(c, height - 1 - j)) {
+                    Some(c) => c as f64,
+                    None => nan,
+                }
+            }).collect(),
+            Dir::Right => range(0, height).map(|j| {
+                match range(0, width).rev().find(|&c| opaque(c, height - 1 - j)) {
+                    Some(c) => (c + 1) as f64,
+                    None => nan,
+                }
+            }).collect(),
+        };
+        Skyline::from_samples(0.0, 1.0, samples.as_slice())
+    }
+
+    // Like `single`, but refuses coordinates that aren't finite and
+    // vertical segments.
+    pub fn try_single(x1: f64, y1: f64, x2: f64, y2: f64)
+            -> Result<Skyline<T>, SkylineError> {
+        try!(check_finite(&[x1, y1, x2, y2]));
+        let ((u1, _), (u2, _)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+        try!(check_width(u1, u2));
+        Ok(Skyline::single(x1, y1, x2, y2))
+    }
+
+    // Like `bounded`, but refuses bounds that are NaN or equal. Infinite
+    // bounds are fine, since they just leave that side open.
+    pub fn try_bounded(x1: f64, x2: f64) -> Result<Skyline<T>, SkylineError> {
+        if x1.is_nan() || x2.is_nan() {
+            return Err(SkylineError::NotANumber);
+        }
+        try!(check_width(x1, x2));
+        Ok(Skyline::bounded(x1, x2))
+    }
+
+    // Like `from_polygon`, but refuses points that aren't finite.
+    pub fn try_from_polygon(points: &[(f64, f64)])
+            -> Result<Skyline<T>, SkylineError> {
+        for &(x, y) in points.iter() {
+            try!(check_finite(&[x, y]));
+        }
+        Ok(Skyline::from_polygon(points))
+    }
+
+    // Like `from_rects`, but refuses rectangles that aren't finite.
+    pub fn try_from_rects(rects: &[Rect]) -> Result<Skyline<T>, SkylineError> {
+        for r in rects.iter() {
+            try!(check_finite(&[r.x0, r.y0, r.x1, r.y1]));
+        }
+        Ok(Skyline::from_rects(rects))
+    }
+}
+
+impl<T: Direction> FromIterator<Rect> for Skyline<T> {
+    fn from_iter<I: Iterator<Rect>>(iter: I) -> Skyline<T> {
+        let rects: Vec<Rect> = iter.collect();
+        Skyline::from_rects(rects.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Building, Rect, Skyline, SkylineError, Up, Down, Left, Right};
+
+    #[test]
+    fn polygon_square() {
+        let square = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 1.0)];
+        let top = Skyline::<Up>::from_polygon(&square);
+        let bottom = Skyline::<Down>::from_polygon(&square);
+
+        let floor = Skyline::<Down>::single(0.0, 0.0, 2.0, 0.0);
+        assert!(top.overlap(&floor).approx_eq(1.0));
+        let ceiling = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        assert!(ceiling.overlap(&bottom).approx_eq(1.0));
+    }
+
+    #[test]
+    fn polygon_concave() {
+        // A "U" shape, in clockwise order.
+        let u = [(0.0, 0.0), (0.0, 2.0), (1.0, 2.0), (1.0, 1.0),
+                 (2.0, 1.0), (2.0, 2.0), (3.0, 2.0), (3.0, 0.0)];
+        let top = Skyline::<Up>::from_polygon(&u);
+
+        let notch = Skyline::<Down>::single(1.2, 0.0, 1.8, 0.0);
+        let d = top.overlap(&notch);
+        assert!(d.approx_eq(1.0), "d = {}, should be 1.0", d);
+
+        let wide = Skyline::<Down>::single(0.0, 0.0, 3.0, 0.0);
+        let d = top.overlap(&wide);
+        assert!(d.approx_eq(2.0), "d = {}, should be 2.0", d);
+    }
+
+    #[test]
+    fn rects_matches_repeated_merge() {
+        let rects = [Rect::new(0.0, 0.0, 2.0, 1.0),
+                     Rect::new(1.0, 0.5, 3.0, 3.0),
+                     Rect::new(5.0, -1.0, 6.0, 0.0),
+                     Rect::new(2.5, 0.0, 4.0, 2.0)];
+        let top = Skyline::<Up>::from_rects(&rects);
+        let bottom = Skyline::<Down>::from_rects(&rects);
+
+        let mut folded = Skyline::<Up>::empty();
+        for r in rects.iter() {
+            folded.merge(&Skyline::single(r.x0, r.y1, r.x1, r.y1));
+        }
+
+        for x in range(0u, 14) {
+            let x = x as f64 * 0.5 - 0.25;
+            let probe = Skyline::<Down>::single(x, 0.0, x + 0.5, 0.0);
+            assert!(top.overlap(&probe).approx_eq(folded.overlap(&probe)));
+        }
+
+        let floor = Skyline::<Up>::single(5.0, 0.0, 6.0, 0.0);
+        assert!(floor.overlap(&bottom).approx_eq(1.0));
+    }
+
+    #[test]
+    fn rects_sweep_structure() {
+        let rects = vec![Rect::new(0.0, 0.0, 2.0, 1.0),
+                         Rect::new(1.0, 0.0, 3.0, 2.0),
+                         Rect::new(1.5, 0.0, 2.5, 0.5)];
+        let sky: Skyline<Up> = rects.iter().map(|r| *r).collect();
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 0.0, b: 1.0, end: 1.0 },
+                 Building { m: 0.0, b: 2.0, end: 3.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+    }
+
+    #[test]
+    fn merge_all_matches_fold() {
+        let mut skylines = Vec::new();
+        let mut folded = Skyline::<Up>::empty();
+        for i in range(0u, 50) {
+            let x = (i * 7 % 23) as f64;
+            let y = (i * 5 % 11) as f64;
+            let sky = Skyline::single(x, y, x + 3.0, y + 1.0);
+            folded.merge(&sky);
+            skylines.push(sky);
+        }
+        skylines.push(Skyline::bounded(-5.0, 30.0));
+
+        let merged = Skyline::merge_all(skylines);
+        folded.bound(-5.0, 30.0);
+        assert!(merged.approx_eq_envelope(&folded, 1e-9));
+        assert_eq!(merged.domain(), folded.domain());
+    }
+
+    #[test]
+    fn merge_k_matches_merge_all() {
+        let mut skylines = Vec::new();
+        for i in range(0u, 7) {
+            let x = (i * 3 % 5) as f64;
+            skylines.push(Skyline::<Up>::single(x, i as f64, x + 2.0, 0.0));
+        }
+        skylines.push(Skyline::bounded(-1.0, 8.0));
+
+        let refs: Vec<&Skyline<Up>> = skylines.iter().collect();
+        let merged = Skyline::merge_k(refs.as_slice());
+        let all = Skyline::merge_all(skylines.clone());
+        assert!(merged.approx_eq_envelope(&all, 1e-9));
+        assert_eq!(merged.domain(), all.domain());
+        assert!(Skyline::<Up>::merge_k(&[]).approx_eq_envelope(&Skyline::empty(), 0.0));
+    }
+
+    #[test]
+    fn samples() {
+        let nan: f64 = Float::nan();
+        let sky = Skyline::<Up>::from_samples(1.0, 0.5, &[nan, 2.0, 2.0, 1.0, nan, 3.0]);
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(1.5),
+                 Building { m: 0.0, b: 2.0, end: 2.5 },
+                 Building { m: 0.0, b: 1.0, end: 3.0 },
+                 Building::empty(3.5),
+                 Building { m: 0.0, b: 3.0, end: 4.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+
+        let rects = [Rect::new(1.5, 0.0, 2.5, 2.0), Rect::new(2.5, 0.0, 3.0, 1.0),
+                     Rect::new(3.5, 0.0, 4.0, 3.0)];
+        assert!(sky.approx_eq_envelope(&Skyline::from_rects(&rects), 0.0));
+
+        let down = Skyline::<Down>::from_samples(0.0, 1.0, &[1.0, 1.0]);
+        assert!(down.height_at(0.5).approx_eq(1.0));
+        assert_eq!(down.segments().count(), 1);
+    }
+
+    #[test]
+    fn mask() {
+        // An "L", with a faint pixel that doesn't count.
+        let pixels = [255u8, 0,   0,
+                      255,   0,   10,
+                      255,   255, 255];
+        let up = Skyline::<Up>::from_mask(3, 3, &pixels, 128);
+        assert!(up.approx_eq_envelope(&Skyline::from_samples(0.0, 1.0, &[3.0, 1.0, 1.0]), 0.0));
+
+        let down = Skyline::<Down>::from_mask(3, 3, &pixels, 128);
+        assert!(down.height_at(2.5).approx_eq(0.0));
+
+        let left = Skyline::<Left>::from_mask(3, 3, &pixels, 128);
+        assert!(left.height_at(2.5).approx_eq(0.0));
+        let right = Skyline::<Right>::from_mask(3, 3, &pixels, 128);
+        assert!(right.height_at(0.5).approx_eq(3.0));
+        assert!(right.height_at(2.5).approx_eq(1.0));
+
+        let blank = Skyline::<Up>::from_mask(2, 1, &[0, 0], 1);
+        assert_eq!(blank.segments().count(), 0);
+    }
+
+    #[test]
+    fn rects_with_slivers() {
+        let rects = [Rect::new(0.0, 0.0, 2.0, 1.0), Rect::new(1.0, 0.0, 1.0, 4.0)];
+        let top = Skyline::<Up>::from_rects(&rects);
+        let probe = Skyline::<Down>::single(0.5, 0.0, 1.5, 0.0);
+        assert!(top.overlap(&probe).approx_eq(4.0));
+    }
+
+    #[test]
+    fn fallible_constructors() {
+        let nan: f64 = Float::nan();
+        let inf: f64 = Float::infinity();
+        assert!(Skyline::<Up>::try_single(0.0, 1.0, 2.0, 1.0).is_ok());
+        assert_eq!(Skyline::<Up>::try_single(0.0, nan, 2.0, 1.0).unwrap_err(),
+                   SkylineError::NotANumber);
+        assert_eq!(Skyline::<Up>::try_single(0.0, 1.0, inf, 1.0).unwrap_err(),
+                   SkylineError::Infinite(inf));
+        assert_eq!(Skyline::<Up>::try_single(1.0, 0.0, 1.0, 2.0).unwrap_err(),
+                   SkylineError::ZeroWidth(1.0));
+
+        assert!(Skyline::<Up>::try_bounded(0.0, inf).is_ok());
+        assert_eq!(Skyline::<Up>::try_bounded(3.0, 3.0).unwrap_err(),
+                   SkylineError::ZeroWidth(3.0));
+        assert_eq!(Skyline::<Down>::try_from_polygon(&[(0.0, 0.0), (1.0, nan), (1.0, 1.0)])
+                       .unwrap_err(), SkylineError::NotANumber);
+        assert!(Skyline::<Down>::try_from_rects(&[Rect::new(0.0, 0.0, 1.0, 1.0)]).is_ok());
+    }
+}
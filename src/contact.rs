@@ -0,0 +1,214 @@
+use std::cmp::Ordering;
+use {Building, Direction, Flip, Segment, Skyline};
+use add_heights;
+
+// A place where two skylines come close: `dist` is the overlap (as returned
+// by `Skyline::overlap`) measured at `x` alone.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Contact {
+    pub x: f64,
+    pub dist: f64
+}
+
+// Where `overlap` is attained, and which pieces of the two skylines touch
+// there (in world coordinates). A piece has no width if it's a wall.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Witness {
+    pub x: f64,
+    pub dist: f64,
+    pub ours: Segment,
+    pub theirs: Segment
+}
+
+fn piece(b: &Building, start: f64, mult: f64) -> Segment {
+    Segment {
+        x0: start,
+        y0: b.y(start) * mult,
+        x1: b.end,
+        y1: b.y(b.end) * mult
+    }
+}
+
+// A stretch over which neither skyline has a breakpoint, so that their sum
+// is linear. `v0` and `v1` are the sums at the two ends.
+struct Stretch {
+    end: f64,
+    v0: f64,
+    v1: f64
+}
+
+fn sum(b1: &Building, b2: &Building, x: f64) -> f64 {
+    if x.is_finite() { add_heights(b1.y(x), b2.y(x)) } else { Float::neg_infinity() }
+}
+
+// Splits the line into stretches of positive width. Since the sum can jump
+// at a breakpoint, we also record the largest value attained exactly at the
+// end of each stretch.
+fn stretches(in1: &[Building], in2: &[Building]) -> (Vec<Stretch>, Vec<f64>) {
+    let mut out: Vec<Stretch> = Vec::new();
+    let mut at_end: Vec<f64> = Vec::new();
+    let mut start: f64 = Float::neg_infinity();
+    let mut i = 0u;
+    let mut j = 0u;
+
+    while i < in1.len() && j < in2.len() {
+        let b1 = &in1[i];
+        let b2 = &in2[j];
+        let end = if b1.end < b2.end {
+            i += 1;
+            b1.end
+        } else {
+            j += 1;
+            b2.end
+        };
+
+        let v0 = sum(b1, b2, start);
+        let v1 = sum(b1, b2, end);
+        if start < end || out.is_empty() {
+            out.push(Stretch { end: end, v0: v0, v1: v1 });
+            at_end.push(v1);
+        } else {
+            let last = at_end.last_mut().unwrap();
+            *last = last.max(v0).max(v1);
+        }
+        start = end;
+    }
+
+    (out, at_end)
+}
+
+impl<T: Direction> Skyline<T> {
+    // Up to `k` local maxima of the distance between this skyline and
+    // `other`, largest first. The first one is where `overlap` is attained
+    // (unless the domains get in the way: this function ignores them).
+    // Along a flat stretch, only the right-most point is reported. Maxima
+    // of the same size are reported from left to right.
+    pub fn overlap_topk<S: Flip<T>>(&self, other: &Skyline<S>, k: uint) -> Vec<Contact> {
+        let (ss, at_end) = stretches(self.buildings.as_slice(), other.buildings.as_slice());
+        let mut peaks = Vec::new();
+        for i in range(1, ss.len()) {
+            let left = &ss[i - 1];
+            let right = &ss[i];
+            let x = left.end;
+            let d = left.v1.max(right.v0).max(at_end[i - 1]);
+            if !x.is_finite() || d == Float::neg_infinity() {
+                continue;
+            }
+
+            // Nearby points on the left must be no higher, and nearby points
+            // on the right must be strictly lower.
+            let left_ok = left.v1 < d || left.v0 <= left.v1;
+            let right_ok = right.v0 < d || right.v1 < right.v0;
+            if left_ok && right_ok {
+                peaks.push(Contact { x: x, dist: d });
+            }
+        }
+
+        peaks.sort_by(|a, b| b.dist.partial_cmp(&a.dist).unwrap_or(Ordering::Equal));
+        peaks.truncate(k);
+        peaks
+    }
+
+    // Like `overlap`, but also says where the overlap happens: after
+    // bumping `other` by the overlap, this is where the two skylines touch.
+    // If there's more than one such place, this is the left-most one. Like
+    // `overlap_topk`, this ignores the domains. Returns `None` if the
+    // skylines are never non-empty in the same place.
+    pub fn overlap_witness<S: Flip<T>>(&self, other: &Skyline<S>) -> Option<Witness> {
+        let in1 = self.buildings.as_slice();
+        let in2 = other.buildings.as_slice();
+        let mut best: Option<(f64, f64, uint, f64, uint, f64)> = None;
+        let mut start: f64 = Float::neg_infinity();
+        let mut start1: f64 = Float::neg_infinity();
+        let mut start2: f64 = Float::neg_infinity();
+        let mut i = 0u;
+        let mut j = 0u;
+
+        while i < in1.len() && j < in2.len() {
+            let b1 = &in1[i];
+            let b2 = &in2[j];
+            for &x in [start, b1.end.min(b2.end)].iter() {
+                let d = sum(b1, b2, x);
+                let better = match best {
+                    Some((best_d, _, _, _, _, _)) => d > best_d,
+                    None => d > Float::neg_infinity(),
+                };
+                if better {
+                    best = Some((d, x, i, start1, j, start2));
+                }
+            }
+
+            if b1.end < b2.end {
+                start = b1.end;
+                start1 = b1.end;
+                i += 1;
+            } else {
+                start = b2.end;
+                start2 = b2.end;
+                j += 1;
+            }
+        }
+
+        let mult = Direction::direction_multiplier(None::<T>);
+        best.map(|(d, x, i, start1, j, start2)| Witness {
+            x: x,
+            dist: d,
+            ours: piece(&in1[i], start1, mult),
+            theirs: piece(&in2[j], start2, -mult)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Segment, Skyline, Up, Down};
+
+    #[test]
+    fn topk_finds_peaks() {
+        let scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 1.0, 3.0),
+                                                Rect::new(2.0, 0.0, 3.0, 1.0),
+                                                Rect::new(4.0, 0.0, 5.0, 2.0)]);
+        let floor = Skyline::<Down>::single(-10.0, 0.0, 10.0, 0.0);
+
+        let contacts = scene.overlap_topk(&floor, 5);
+        assert_eq!(contacts.len(), 3);
+        let expected = [(1.0, 3.0), (5.0, 2.0), (3.0, 1.0)];
+        for (c, &(x, d)) in contacts.iter().zip(expected.iter()) {
+            assert!(c.x.approx_eq(x) && c.dist.approx_eq(d), "{}", c);
+        }
+        assert!(contacts[0].dist.approx_eq(scene.overlap(&floor)));
+
+        assert_eq!(scene.overlap_topk(&floor, 1).len(), 1);
+    }
+
+    #[test]
+    fn topk_sloped() {
+        // Two peaks of a "W", touching a flat ceiling.
+        let mut scene = Skyline::<Up>::single(0.0, 2.0, 1.0, 0.0);
+        scene.merge(&Skyline::single(1.0, 0.0, 2.0, 1.0));
+        scene.merge(&Skyline::single(2.0, 1.0, 3.0, 0.0));
+        let ceiling = Skyline::<Down>::single(-1.0, 0.0, 4.0, 0.0);
+
+        let contacts = scene.overlap_topk(&ceiling, 5);
+        assert_eq!(contacts.len(), 2);
+        assert!(contacts[0].x.approx_eq(0.0) && contacts[0].dist.approx_eq(2.0));
+        assert!(contacts[1].x.approx_eq(2.0) && contacts[1].dist.approx_eq(1.0));
+    }
+
+    #[test]
+    fn witness() {
+        let scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 1.0, 3.0),
+                                                Rect::new(2.0, 0.0, 3.0, 1.0)]);
+        let item = Skyline::<Down>::single(0.5, 2.0, 2.5, 1.0);
+
+        let w = scene.overlap_witness(&item).unwrap();
+        assert!(w.dist.approx_eq(scene.overlap(&item)));
+        assert!(w.x.approx_eq(1.0), "x = {}", w.x);
+        assert_eq!(w.ours, Segment { x0: 0.0, y0: 3.0, x1: 1.0, y1: 3.0 });
+        assert!(w.theirs.x0.approx_eq(0.5) && w.theirs.x1.approx_eq(2.5), "{}", w.theirs);
+
+        let far = Skyline::<Down>::single(5.0, 0.0, 6.0, 0.0);
+        assert!(scene.overlap_witness(&far).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use {Building, Direction, Skyline};
+use {empty_buildings, single_buildings, merge_buildings, overlap_buildings};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// A run-time version of the `Direction` marker types, for code that wants
+// to choose (or match on) a direction without threading a type parameter.
+#[cfg_attr(feature = "serialize", deriving(Encodable, Decodable))]
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub enum Dir {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl Dir {
+    pub fn multiplier(&self) -> f64 {
+        match *self {
+            Dir::Up | Dir::Right => 1.0,
+            Dir::Down | Dir::Left => -1.0,
+        }
+    }
+
+    // Leftward and rightward skylines are functions of y.
+    pub fn is_horizontal(&self) -> bool {
+        *self == Dir::Left || *self == Dir::Right
+    }
+
+    pub fn flip(&self) -> Dir {
+        match *self {
+            Dir::Up => Dir::Down,
+            Dir::Down => Dir::Up,
+            Dir::Left => Dir::Right,
+            Dir::Right => Dir::Left,
+        }
+    }
+}
+
+// The same thing as a `Skyline`, except that the direction is checked when
+// the skyline is used instead of when it is compiled. Merging skylines with
+// different directions (or overlapping skylines whose directions aren't
+// flips of each other) panics.
+#[deriving(Clone, Show)]
+pub struct DirSkyline {
+    dir: Dir,
+    buildings: Vec<Building>
+}
+
+impl DirSkyline {
+    pub fn empty(dir: Dir) -> DirSkyline {
+        DirSkyline {
+            dir: dir,
+            buildings: empty_buildings()
+        }
+    }
+
+    // As for `Skyline::single`, the points of horizontal skylines are
+    // swapped.
+    pub fn single(dir: Dir, x1: f64, y1: f64, x2: f64, y2: f64) -> DirSkyline {
+        let bldgs = if dir.is_horizontal() {
+            single_buildings(dir.multiplier(), y1, x1, y2, x2)
+        } else {
+            single_buildings(dir.multiplier(), x1, y1, x2, y2)
+        };
+        DirSkyline {
+            dir: dir,
+            buildings: bldgs
+        }
+    }
+
+    pub fn from_skyline<T: Direction>(sky: &Skyline<T>) -> DirSkyline {
+        assert!(!sky.is_bounded(), "DirSkyline doesn't support bounded domains");
+        DirSkyline {
+            dir: sky.dir(),
+            buildings: sky.buildings.clone()
+        }
+    }
+
+    // Returns `None` if `T` doesn't match the direction of this skyline.
+    pub fn to_skyline<T: Direction>(&self) -> Option<Skyline<T>> {
+        if Direction::dir(None::<T>) == self.dir {
+            Some(Skyline::from_buildings(self.buildings.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn dir(&self) -> Dir {
+        self.dir
+    }
+
+    pub fn overlap(&self, other: &DirSkyline) -> f64 {
+        assert!(self.dir.flip() == other.dir,
+                "can't overlap {} skyline with {} skyline", self.dir, other.dir);
+        overlap_buildings(self.buildings.as_slice(), other.buildings.as_slice())
+    }
+
+    pub fn merge(&mut self, other: &DirSkyline) {
+        assert!(self.dir == other.dir,
+                "can't merge {} skyline with {} skyline", self.dir, other.dir);
+        let mut new_bldgs = Vec::new();
+        merge_buildings(self.buildings.as_slice(),
+                        other.buildings.as_slice(),
+                        &mut new_bldgs);
+
+        self.buildings = new_bldgs;
+    }
+
+    pub fn slide(&mut self, x: f64) {
+        for b in self.buildings.iter_mut() {
+            b.slide(x)
+        }
+    }
+
+    pub fn bump(&mut self, y: f64) {
+        let y = y * self.dir.multiplier();
+        for b in self.buildings.iter_mut() {
+            b.b += y
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Skyline, Up, Down};
+    use super::{Dir, DirSkyline};
+
+    #[test]
+    fn dir_skyline_matches_typed() {
+        let up = DirSkyline::single(Dir::Up, -1.0, 3.0, 1.0, 3.0);
+        let down = DirSkyline::single(Dir::Down, -1.0, 2.0, 1.0, 2.0);
+        let d = up.overlap(&down);
+        assert!(d.approx_eq(1.0), "d = {}, should be 1.0", d);
+
+        let typed = Skyline::<Up>::single(-1.0, 3.0, 1.0, 3.0);
+        let converted = DirSkyline::from_skyline(&typed);
+        assert_eq!(converted.dir(), Dir::Up);
+        assert!(converted.to_skyline::<Down>().is_none());
+        let back = converted.to_skyline::<Up>().unwrap();
+        assert!(back.overlap(&down.to_skyline::<Down>().unwrap()).approx_eq(1.0));
+    }
+
+    #[test]
+    #[should_fail]
+    fn dir_skyline_merge_mismatch() {
+        let mut up = DirSkyline::single(Dir::Up, -1.0, 3.0, 1.0, 3.0);
+        let down = DirSkyline::single(Dir::Down, -1.0, 2.0, 1.0, 2.0);
+        up.merge(&down);
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// Returned by the `try_` constructors when their input can't describe a
+// skyline.
+#[deriving(Clone, PartialEq, Show)]
+pub enum SkylineError {
+    // One of the coordinates was NaN.
+    NotANumber,
+    // One of the coordinates was infinite.
+    Infinite(f64),
+    // A segment or interval with no width, starting and ending at this x
+    // coordinate.
+    ZeroWidth(f64)
+}
+
+// Why `Skyline::try_overlap` couldn't give a finite answer.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Blocked {
+    // A solid part of one skyline (see `Skyline::solid`) is over a
+    // non-empty part of the other, starting at this x coordinate.
+    Solid(f64),
+    // One of the skylines has buildings outside the other's domain.
+    OutsideDomain
+}
@@ -0,0 +1,155 @@
+// A flat C interface to skylines, for driving the library from other
+// languages (a C++ engine, or a JavaScript canvas through a C-to-wasm
+// toolchain). Skylines are opaque pointers to `DirSkyline`s, so the
+// direction is an argument instead of a type parameter. As with `CScorer`,
+// coordinates are always doubles.
+//
+// None of these functions panic on bad input, since unwinding into C is
+// undefined: invalid directions give a null pointer, and mismatched
+// directions or null arguments give an error code (or NaN, for overlaps, or
+// nothing at all, for `skyline_slide` and `skyline_bump`). Every pointer that
+// `skyline_new` or `skyline_single` returns must be passed to `skyline_free`
+// exactly once.
+
+use std::mem;
+use libc::{c_double, c_int};
+use {Dir, DirSkyline};
+
+pub const SKYLINE_UP: c_int = 0;
+pub const SKYLINE_DOWN: c_int = 1;
+pub const SKYLINE_LEFT: c_int = 2;
+pub const SKYLINE_RIGHT: c_int = 3;
+
+// Returned by `skyline_merge` if the directions don't match.
+pub const SKYLINE_MISMATCH: c_int = -1;
+// Returned by `skyline_merge` if either argument is null.
+pub const SKYLINE_NULL: c_int = -2;
+
+fn dir_from_c(dir: c_int) -> Option<Dir> {
+    match dir {
+        SKYLINE_UP => Some(Dir::Up),
+        SKYLINE_DOWN => Some(Dir::Down),
+        SKYLINE_LEFT => Some(Dir::Left),
+        SKYLINE_RIGHT => Some(Dir::Right),
+        _ => None,
+    }
+}
+
+fn into_raw(sky: DirSkyline) -> *mut DirSkyline {
+    unsafe { mem::transmute(box sky) }
+}
+
+// An empty skyline, or null if `dir` isn't one of the direction constants.
+#[no_mangle]
+pub extern "C" fn skyline_new(dir: c_int) -> *mut DirSkyline {
+    match dir_from_c(dir) {
+        Some(d) => into_raw(DirSkyline::empty(d)),
+        None => 0 as *mut DirSkyline,
+    }
+}
+
+// As for `Skyline::single`.
+#[no_mangle]
+pub extern "C" fn skyline_single(dir: c_int, x1: c_double, y1: c_double,
+                                 x2: c_double, y2: c_double) -> *mut DirSkyline {
+    match dir_from_c(dir) {
+        Some(d) => into_raw(DirSkyline::single(d, x1, y1, x2, y2)),
+        None => 0 as *mut DirSkyline,
+    }
+}
+
+// Does nothing if `sky` is null.
+#[no_mangle]
+pub unsafe extern "C" fn skyline_free(sky: *mut DirSkyline) {
+    if !sky.is_null() {
+        let _: Box<DirSkyline> = mem::transmute(sky);
+    }
+}
+
+// Merges `other` into `sky`. Returns zero, `SKYLINE_NULL` if either is null,
+// or `SKYLINE_MISMATCH` (leaving `sky` alone) if their directions differ.
+#[no_mangle]
+pub unsafe extern "C" fn skyline_merge(sky: *mut DirSkyline, other: *const DirSkyline) -> c_int {
+    if sky.is_null() || other.is_null() {
+        return SKYLINE_NULL;
+    }
+    let (sky, other) = (&mut *sky, &*other);
+    if sky.dir() != other.dir() {
+        return SKYLINE_MISMATCH;
+    }
+    sky.merge(other);
+    0
+}
+
+// As for `Skyline::overlap`, or NaN if `b` doesn't face `a` or either is null.
+#[no_mangle]
+pub unsafe extern "C" fn skyline_overlap(a: *const DirSkyline, b: *const DirSkyline)
+        -> c_double {
+    if a.is_null() || b.is_null() {
+        return Float::nan();
+    }
+    let (a, b) = (&*a, &*b);
+    if a.dir().flip() != b.dir() {
+        return Float::nan();
+    }
+    a.overlap(b) as c_double
+}
+
+// Does nothing if `sky` is null.
+#[no_mangle]
+pub unsafe extern "C" fn skyline_slide(sky: *mut DirSkyline, x: c_double) {
+    if !sky.is_null() {
+        (*sky).slide(x);
+    }
+}
+
+// Does nothing if `sky` is null.
+#[no_mangle]
+pub unsafe extern "C" fn skyline_bump(sky: *mut DirSkyline, y: c_double) {
+    if !sky.is_null() {
+        (*sky).bump(y);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use super::{skyline_bump, skyline_free, skyline_merge, skyline_new, skyline_overlap};
+    use super::{skyline_single, skyline_slide, SKYLINE_DOWN, SKYLINE_MISMATCH, SKYLINE_UP};
+    use super::SKYLINE_NULL;
+
+    #[test]
+    fn round_trip() {
+        unsafe {
+            let up = skyline_single(SKYLINE_UP, -1.0, 3.0, 1.0, 3.0);
+            let down = skyline_single(SKYLINE_DOWN, -1.0, 2.0, 1.0, 2.0);
+            let more = skyline_single(SKYLINE_UP, 0.0, 4.0, 2.0, 4.0);
+            assert!(skyline_overlap(up as *const _, down as *const _).approx_eq(1.0));
+
+            assert_eq!(skyline_merge(up, more as *const _), 0);
+            assert!(skyline_overlap(up as *const _, down as *const _).approx_eq(2.0));
+            skyline_bump(down, -1.0);
+            assert!(skyline_overlap(up as *const _, down as *const _).approx_eq(3.0));
+            skyline_slide(down, 5.0);
+            assert_eq!(skyline_overlap(up as *const _, down as *const _),
+                       Float::neg_infinity());
+
+            assert_eq!(skyline_merge(up, down as *const _), SKYLINE_MISMATCH);
+            assert!(skyline_overlap(up as *const _, more as *const _).is_nan());
+            assert!(skyline_new(7).is_null());
+
+            let null = 0 as *mut _;
+            assert_eq!(skyline_merge(null, up as *const _), SKYLINE_NULL);
+            assert_eq!(skyline_merge(up, null as *const _), SKYLINE_NULL);
+            assert!(skyline_overlap(up as *const _, null as *const _).is_nan());
+            assert!(skyline_overlap(null as *const _, down as *const _).is_nan());
+            skyline_slide(null, 1.0);
+            skyline_bump(null, 1.0);
+
+            skyline_free(up);
+            skyline_free(down);
+            skyline_free(more);
+            skyline_free(0 as *mut _);
+        }
+    }
+}
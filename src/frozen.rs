@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use {Direction, Skyline};
+
+// A skyline with shared, reference-counted storage. Cloning one is cheap, so
+// a large set of obstacles can be handed to many threads without copying
+// it. Reading goes through `Deref`; the first mutation of a shared copy
+// (through `to_mut`) makes a private copy first.
+pub struct FrozenSkyline<T: Direction> {
+    inner: Arc<Skyline<T>>
+}
+
+impl<T: Direction + Clone + Send + Sync> FrozenSkyline<T> {
+    pub fn new(sky: Skyline<T>) -> FrozenSkyline<T> {
+        FrozenSkyline {
+            inner: Arc::new(sky)
+        }
+    }
+
+    // Returns a mutable reference to the skyline, copying it first if anyone
+    // else can see it.
+    pub fn to_mut(&mut self) -> &mut Skyline<T> {
+        self.inner.make_unique()
+    }
+
+    pub fn thaw(&self) -> Skyline<T> {
+        (*self.inner).clone()
+    }
+}
+
+impl<T: Direction + Send + Sync> Clone for FrozenSkyline<T> {
+    fn clone(&self) -> FrozenSkyline<T> {
+        FrozenSkyline {
+            inner: self.inner.clone()
+        }
+    }
+}
+
+impl<T: Direction + Send + Sync> Deref<Skyline<T>> for FrozenSkyline<T> {
+    fn deref<'a>(&'a self) -> &'a Skyline<T> {
+        &*self.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Skyline, Up, Down};
+    use super::FrozenSkyline;
+
+    #[test]
+    fn frozen_copy_on_write() {
+        let frozen = FrozenSkyline::new(Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0));
+        let mut copy = frozen.clone();
+        copy.to_mut().bump(1.0);
+
+        let probe = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.0);
+        assert!(frozen.overlap(&probe).approx_eq(1.0));
+        assert!(copy.overlap(&probe).approx_eq(2.0));
+        assert!(copy.thaw().overlap(&probe).approx_eq(2.0));
+    }
+}
@@ -0,0 +1,242 @@
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// A closed interval of the real line. The constructor sorts the endpoints,
+// but intervals built directly (or returned from `Skyline::domain` after
+// bounding to disjoint ranges) may have `lo > hi`, in which case they are
+// empty.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64
+}
+
+impl Interval {
+    pub fn new(a: f64, b: f64) -> Interval {
+        Interval {
+            lo: a.min(b),
+            hi: a.max(b)
+        }
+    }
+
+    // The whole real line.
+    pub fn unbounded() -> Interval {
+        Interval {
+            lo: Float::neg_infinity(),
+            hi: Float::infinity()
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        (self.hi - self.lo).max(0.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo > self.hi
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.lo > Float::neg_infinity() || self.hi < Float::infinity()
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    // Returns `None` if the intervals don't meet.
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let out = Interval {
+            lo: self.lo.max(other.lo),
+            hi: self.hi.min(other.hi)
+        };
+        if out.is_empty() { None } else { Some(out) }
+    }
+
+    // The smallest interval containing both.
+    pub fn union(&self, other: &Interval) -> Interval {
+        if self.is_empty() {
+            *other
+        } else if other.is_empty() {
+            *self
+        } else {
+            Interval {
+                lo: self.lo.min(other.lo),
+                hi: self.hi.max(other.hi)
+            }
+        }
+    }
+
+    pub fn inflate(&self, r: f64) -> Interval {
+        Interval {
+            lo: self.lo - r,
+            hi: self.hi + r
+        }
+    }
+
+    pub fn translate(&self, d: f64) -> Interval {
+        Interval {
+            lo: self.lo + d,
+            hi: self.hi + d
+        }
+    }
+}
+
+// An axis-aligned rectangle. The constructor sorts the coordinates, so
+// `x0 <= x1` and `y0 <= y1` always.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Rect {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64
+}
+
+impl Rect {
+    pub fn new(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect {
+        Rect {
+            x0: x0.min(x1),
+            y0: y0.min(y1),
+            x1: x0.max(x1),
+            y1: y0.max(y1)
+        }
+    }
+
+    pub fn from_intervals(x: Interval, y: Interval) -> Rect {
+        Rect::new(x.lo, y.lo, x.hi, y.hi)
+    }
+
+    pub fn x_interval(&self) -> Interval {
+        Interval { lo: self.x0, hi: self.x1 }
+    }
+
+    pub fn y_interval(&self) -> Interval {
+        Interval { lo: self.y0, hi: self.y1 }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y1 - self.y0
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.x_interval().contains(x) && self.y_interval().contains(y)
+    }
+
+    // Returns `None` if the rectangles don't meet. Rectangles that only
+    // share an edge meet in a rectangle with no width or height.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        match (self.x_interval().intersection(&other.x_interval()),
+               self.y_interval().intersection(&other.y_interval())) {
+            (Some(x), Some(y)) => Some(Rect::from_intervals(x, y)),
+            _ => None,
+        }
+    }
+
+    // The smallest rectangle containing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::from_intervals(self.x_interval().union(&other.x_interval()),
+                             self.y_interval().union(&other.y_interval()))
+    }
+
+    // Grows the rectangle by `r` on every side (or shrinks it, if `r` is
+    // negative).
+    pub fn inflate(&self, r: f64) -> Rect {
+        Rect {
+            x0: self.x0 - r,
+            y0: self.y0 - r,
+            x1: self.x1 + r,
+            y1: self.y1 + r
+        }
+    }
+
+    pub fn translate(&self, dx: f64, dy: f64) -> Rect {
+        Rect {
+            x0: self.x0 + dx,
+            y0: self.y0 + dy,
+            x1: self.x1 + dx,
+            y1: self.y1 + dy
+        }
+    }
+}
+
+// An affine map of the plane, taking (x, y) to
+// (xx * x + xy * y + x0, yx * x + yy * y + y0).
+#[deriving(Clone, PartialEq, Show)]
+pub struct Affine {
+    pub xx: f64,
+    pub xy: f64,
+    pub yx: f64,
+    pub yy: f64,
+    pub x0: f64,
+    pub y0: f64
+}
+
+impl Affine {
+    pub fn identity() -> Affine {
+        Affine::scaling(1.0, 1.0)
+    }
+
+    pub fn scaling(sx: f64, sy: f64) -> Affine {
+        Affine { xx: sx, xy: 0.0, yx: 0.0, yy: sy, x0: 0.0, y0: 0.0 }
+    }
+
+    pub fn translation(dx: f64, dy: f64) -> Affine {
+        Affine { xx: 1.0, xy: 0.0, yx: 0.0, yy: 1.0, x0: dx, y0: dy }
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.xx * x + self.xy * y + self.x0, self.yx * x + self.yy * y + self.y0)
+    }
+
+    // The map that applies `self` and then `other`.
+    pub fn then(&self, other: &Affine) -> Affine {
+        let (x0, y0) = other.apply(self.x0, self.y0);
+        Affine {
+            xx: other.xx * self.xx + other.xy * self.yx,
+            xy: other.xx * self.xy + other.xy * self.yy,
+            yx: other.yx * self.xx + other.yy * self.yx,
+            yy: other.yx * self.xy + other.yy * self.yy,
+            x0: x0,
+            y0: y0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Affine, Interval, Rect};
+
+    #[test]
+    fn interval_ops() {
+        let a = Interval::new(2.0, 0.0);
+        let b = Interval::new(1.0, 3.0);
+        assert_eq!(a, Interval { lo: 0.0, hi: 2.0 });
+        assert_eq!(a.intersection(&b), Some(Interval::new(1.0, 2.0)));
+        assert_eq!(a.union(&b), Interval::new(0.0, 3.0));
+        assert_eq!(a.intersection(&Interval::new(5.0, 6.0)), None);
+        assert_eq!(a.inflate(1.0), Interval::new(-1.0, 3.0));
+        assert!(a.contains(2.0) && !a.contains(2.5));
+        assert!(!a.is_empty() && !Interval::unbounded().is_bounded());
+    }
+
+    #[test]
+    fn rect_ops() {
+        let a = Rect::new(0.0, 0.0, 2.0, 2.0);
+        let b = Rect::new(1.0, 1.0, 3.0, 4.0);
+        assert_eq!(a.intersection(&b), Some(Rect::new(1.0, 1.0, 2.0, 2.0)));
+        assert_eq!(a.union(&b), Rect::new(0.0, 0.0, 3.0, 4.0));
+        assert_eq!(a.intersection(&a.translate(5.0, 0.0)), None);
+        assert_eq!(a.inflate(0.5), Rect::new(-0.5, -0.5, 2.5, 2.5));
+        assert!(b.contains(3.0, 4.0) && !b.contains(0.5, 2.0));
+    }
+
+    #[test]
+    fn affine_composition() {
+        let a = Affine::scaling(2.0, 3.0).then(&Affine::translation(1.0, -1.0));
+        assert_eq!(a.apply(1.0, 1.0), (3.0, 2.0));
+        assert_eq!(Affine::identity().then(&a), a);
+    }
+}
@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::collections::TreeMap;
+use {Building, Direction, Flip, Skyline};
+use {frame_point, merge_buildings, overlap_buildings, single_buildings, support};
+
+// A skyline stored in a balanced tree instead of a vector, for packing loops
+// that merge many small items into one big skyline. Merging an item only
+// touches the buildings under it, so it takes O(k log n) time where k is
+// the number of buildings in the item's x-range, instead of the O(n) that
+// `Skyline::merge` needs to rebuild the whole list. Overlaps against small
+// items are cheap in the same way.
+#[deriving(Clone, Show)]
+pub struct IncrementalSkyline<T: Direction> {
+    tree: TreeMap<Key, Building>
+}
+
+// Buildings are keyed by where they end, and then by where they start so
+// that a zero-width building sorts after the building that ends at the same
+// place. Keys must never contain NaN.
+#[deriving(Clone, PartialEq, PartialOrd, Show)]
+struct Key {
+    end: f64,
+    start: f64
+}
+
+impl Eq for Key {}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
+
+impl<T: Direction> IncrementalSkyline<T> {
+    pub fn new() -> IncrementalSkyline<T> {
+        let mut tree = TreeMap::new();
+        tree.insert(Key { end: Float::infinity(), start: Float::neg_infinity() },
+                    Building::empty(Float::infinity()));
+        IncrementalSkyline { tree: tree }
+    }
+
+    pub fn from_skyline(sky: &Skyline<T>) -> IncrementalSkyline<T> {
+        assert!(!sky.is_bounded(), "IncrementalSkyline doesn't support bounded domains");
+        let mut ret = IncrementalSkyline { tree: TreeMap::new() };
+        let mut start: f64 = Float::neg_infinity();
+        for b in sky.buildings.iter() {
+            ret.put(Key { end: b.end, start: start }, *b);
+            start = b.end;
+        }
+        ret
+    }
+
+    pub fn to_skyline(&self) -> Skyline<T> {
+        Skyline::from_buildings(self.tree.values().map(|b| *b).collect())
+    }
+
+    // The number of buildings.
+    pub fn len(&self) -> uint {
+        self.tree.len()
+    }
+
+    // Inserts a building, unless there's already a zero-width building in
+    // the same place that is at least as high.
+    fn put(&mut self, key: Key, b: Building) {
+        let keep_old = match self.tree.get(&key) {
+            Some(old) => key.start == key.end && old.y(key.end) >= b.y(key.end),
+            None => false,
+        };
+        if !keep_old {
+            self.tree.insert(key, b);
+        }
+    }
+
+    // The buildings from the one containing `x1` to the one containing
+    // `x2`. If `open` is true, buildings that end exactly at `x1` are left
+    // out.
+    fn window(&self, x1: f64, x2: f64, open: bool) -> Vec<(Key, Building)> {
+        let first = Key {
+            end: x1,
+            start: if open { Float::infinity() } else { Float::neg_infinity() }
+        };
+        let mut out = Vec::new();
+        for (k, b) in self.tree.lower_bound(&first) {
+            out.push((*k, *b));
+            if k.end > x2 || (open && k.end >= x2) {
+                break;
+            }
+        }
+        out
+    }
+
+    pub fn insert_segment(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+        self.merge_buildings(single_buildings(mult, u1, v1, u2, v2).as_slice());
+    }
+
+    // Merges `other` into this skyline, only touching the buildings below
+    // it.
+    pub fn merge_local(&mut self, other: &Skyline<T>) {
+        assert!(!other.is_bounded(), "IncrementalSkyline doesn't support bounded domains");
+        self.merge_buildings(other.buildings.as_slice());
+    }
+
+    fn merge_buildings(&mut self, other: &[Building]) {
+        let (lo, hi) = match support(other) {
+            Some(s) => s,
+            None => return,
+        };
+
+        // Everything that ends after `lo`, up to the first building that
+        // reaches `hi`. Whatever ends at or before `lo` doesn't change.
+        let affected = self.window(lo, hi, true);
+        let (first_key, first) = affected[0];
+        let (last_key, _) = affected[affected.len() - 1];
+        let last_end = last_key.end;
+
+        let mut local = vec![Building::empty(lo)];
+        local.extend(affected.iter().map(|&(_, b)| b));
+        if last_end < Float::infinity() {
+            local.push(Building::empty(Float::infinity()));
+        }
+        let mut merged = Vec::with_capacity(local.len() + other.len());
+        merge_buildings(local.as_slice(), other, &mut merged);
+
+        for &(k, _) in affected.iter() {
+            self.tree.remove(&k);
+        }
+
+        // The first building used to reach back past `lo`.
+        if first_key.start < lo {
+            self.put(Key { end: lo, start: first_key.start }, first.chop(lo));
+        }
+
+        let mut start: f64 = Float::neg_infinity();
+        for b in merged.iter() {
+            let s = start;
+            start = b.end;
+            if b.end < lo || (b.end == lo && s < lo) {
+                continue;
+            }
+            let s = s.max(lo);
+            let end = b.end.min(last_end);
+            self.put(Key { end: end, start: s }, b.chop(end));
+            if b.end >= last_end {
+                break;
+            }
+        }
+    }
+
+    pub fn overlap<S: Flip<T>>(&self, other: &Skyline<S>) -> f64 {
+        let (lo, hi) = match support(other.buildings.as_slice()) {
+            Some(s) => s,
+            None => return Float::neg_infinity(),
+        };
+
+        let window = self.window(lo, hi, false);
+        let (first_key, _) = window[0];
+        let mut local = Vec::with_capacity(window.len() + 2);
+        if first_key.start > Float::neg_infinity() {
+            local.push(Building::empty(first_key.start));
+        }
+        local.extend(window.iter().map(|&(_, b)| b));
+        if local[local.len() - 1].end < Float::infinity() {
+            local.push(Building::empty(Float::infinity()));
+        }
+        overlap_buildings(local.as_slice(), other.buildings.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Right, Skyline, Up, Down};
+    use super::IncrementalSkyline;
+
+    #[test]
+    fn incremental_matches_merge() {
+        let segs = [(0.0, 1.0, 4.0, 1.0), (2.0, 3.0, 3.0, 0.0), (5.0, 2.0, 6.0, 2.0),
+                    (1.0, 0.0, 5.5, 4.0), (3.0, 5.0, 3.0, 5.0), (-1.0, 2.0, 0.0, 2.0)];
+        let mut inc = IncrementalSkyline::<Up>::new();
+        let mut sky = Skyline::<Up>::empty();
+        for &(x1, y1, x2, y2) in segs.iter() {
+            inc.insert_segment(x1, y1, x2, y2);
+            sky.merge(&Skyline::single(x1, y1, x2, y2));
+            assert!(inc.to_skyline().approx_eq_envelope(&sky, 1e-9));
+        }
+
+        for i in range(0u, 16) {
+            let x = i as f64 * 0.5 - 1.25;
+            let probe = Skyline::<Down>::single(x, 0.0, x + 0.5, 0.0);
+            assert!(inc.overlap(&probe).approx_eq(sky.overlap(&probe)),
+                    "at {}: {} != {}", x, inc.overlap(&probe), sky.overlap(&probe));
+        }
+    }
+
+    #[test]
+    fn incremental_round_trip() {
+        let sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 1.0, 1.0),
+                                              Rect::new(2.0, 0.0, 3.0, 2.0)]);
+        let mut inc = IncrementalSkyline::from_skyline(&sky);
+        assert!(inc.to_skyline().approx_eq(&sky));
+
+        // Merging something far away leaves the other buildings alone.
+        let n = inc.len();
+        inc.merge_local(&Skyline::single(10.0, 1.0, 11.0, 1.0));
+        assert_eq!(inc.len(), n + 2);
+    }
+
+    #[test]
+    fn incremental_horizontal_segments() {
+        let mut inc = IncrementalSkyline::<Right>::new();
+        let mut sky = Skyline::<Right>::empty();
+        for &(x1, y1, x2, y2) in [(1.0, 0.0, 3.0, 2.0), (4.0, 1.0, 2.0, 5.0)].iter() {
+            inc.insert_segment(x1, y1, x2, y2);
+            sky.merge(&Skyline::single(x1, y1, x2, y2));
+        }
+        assert!(inc.to_skyline().approx_eq_envelope(&sky, 1e-9));
+        assert!(inc.to_skyline().height_at(1.5).approx_eq(3.75));
+    }
+}
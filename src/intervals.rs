@@ -0,0 +1,220 @@
+use std::slice::Items;
+use {Direction, Interval, Skyline};
+
+// A union of disjoint intervals of the real line, for keeping track of which
+// parts of the x axis are in use without caring about heights.
+//
+// Only the parts with positive length count: intervals that shrink to a
+// point (for example, when subtracting [1, 2] from [0, 2]) are dropped, and
+// intervals that touch are joined.
+#[deriving(Clone, PartialEq, Show)]
+pub struct IntervalSet {
+    // Sorted, non-empty and separated by gaps of positive length.
+    intervals: Vec<Interval>
+}
+
+// Sorts the intervals and joins the ones that overlap or touch.
+fn normalize(mut ivs: Vec<Interval>) -> Vec<Interval> {
+    ivs.retain(|iv| iv.lo < iv.hi);
+    ivs.sort_by(|a, b| a.lo.partial_cmp(&b.lo).unwrap());
+
+    let mut out: Vec<Interval> = Vec::with_capacity(ivs.len());
+    for iv in ivs.into_iter() {
+        let joins = match out.last() {
+            Some(last) => iv.lo <= last.hi,
+            None => false,
+        };
+        if joins {
+            let last = out.last_mut().unwrap();
+            last.hi = last.hi.max(iv.hi);
+        } else {
+            out.push(iv);
+        }
+    }
+    out
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet { intervals: Vec::new() }
+    }
+
+    pub fn from_intervals(ivs: &[Interval]) -> IntervalSet {
+        IntervalSet { intervals: normalize(ivs.to_vec()) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // The total length of the set.
+    pub fn length(&self) -> f64 {
+        self.intervals.iter().fold(0.0, |acc, iv| acc + iv.length())
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.intervals.iter().any(|iv| iv.contains(x))
+    }
+
+    // The intervals, from left to right.
+    pub fn iter(&self) -> Items<Interval> {
+        self.intervals.iter()
+    }
+
+    pub fn insert(&mut self, iv: Interval) {
+        let mut ivs = self.intervals.clone();
+        ivs.push(iv);
+        self.intervals = normalize(ivs);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ivs = self.intervals.clone();
+        ivs.push_all(other.intervals.as_slice());
+        IntervalSet { intervals: normalize(ivs) }
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let a = self.intervals.as_slice();
+        let b = other.intervals.as_slice();
+        let mut out = Vec::new();
+        let (mut i, mut j) = (0u, 0u);
+        while i < a.len() && j < b.len() {
+            let lo = a[i].lo.max(b[j].lo);
+            let hi = a[i].hi.min(b[j].hi);
+            if lo < hi {
+                out.push(Interval { lo: lo, hi: hi });
+            }
+            // Whichever interval ends first can't meet anything else.
+            if a[i].hi < b[j].hi { i += 1; } else { j += 1; }
+        }
+        IntervalSet { intervals: out }
+    }
+
+    // Everything on the real line that isn't in this set.
+    pub fn complement(&self) -> IntervalSet {
+        let mut out = Vec::new();
+        let mut prev: f64 = Float::neg_infinity();
+        for iv in self.intervals.iter() {
+            if iv.lo > prev {
+                out.push(Interval { lo: prev, hi: iv.lo });
+            }
+            prev = iv.hi;
+        }
+        if prev < Float::infinity() {
+            out.push(Interval { lo: prev, hi: Float::infinity() });
+        }
+        IntervalSet { intervals: out }
+    }
+
+    // The part of this set that isn't in `other`.
+    pub fn subtract(&self, other: &IntervalSet) -> IntervalSet {
+        self.intersection(&other.complement())
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    // The parts of the x axis where the skyline is strictly higher than `y`
+    // (in world coordinates, whatever the skyline's direction). Empty parts
+    // of a downward or leftward skyline are infinitely high, so they count;
+    // vertical walls don't.
+    pub fn where_above(&self, y: f64) -> IntervalSet {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let level = y * mult;
+        let mut ivs = Vec::new();
+        let mut start: f64 = Float::neg_infinity();
+
+        for b in self.buildings.iter() {
+            let (lo, hi) = (start, b.end);
+            start = b.end;
+            if lo >= hi {
+                continue;
+            }
+
+            // In internal coordinates, we're looking for where
+            // mult * (b.y(x) - level) > 0.
+            if b.b == Float::neg_infinity() || b.m == 0.0 {
+                if mult * (b.b - level) > 0.0 {
+                    ivs.push(Interval { lo: lo, hi: hi });
+                }
+            } else {
+                let root = (level - b.b) / b.m;
+                let (a, z) = if mult * b.m > 0.0 {
+                    (root.max(lo), hi)
+                } else {
+                    (lo, root.min(hi))
+                };
+                ivs.push(Interval { lo: a, hi: z });
+            }
+        }
+        IntervalSet { intervals: normalize(ivs) }
+    }
+
+    // The parts of the x axis that have a building on them (including the
+    // ones that are below the ground).
+    pub fn occupied(&self) -> IntervalSet {
+        let mut ivs = Vec::new();
+        let mut start: f64 = Float::neg_infinity();
+        for b in self.buildings.iter() {
+            if b.b > Float::neg_infinity() {
+                ivs.push(Interval { lo: start, hi: b.end });
+            }
+            start = b.end;
+        }
+        IntervalSet { intervals: normalize(ivs) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Interval, Rect, Skyline, Up, Down};
+    use super::IntervalSet;
+
+    fn set(ivs: &[(f64, f64)]) -> IntervalSet {
+        let ivs: Vec<Interval> = ivs.iter().map(|&(a, b)| Interval::new(a, b)).collect();
+        IntervalSet::from_intervals(ivs.as_slice())
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[(0.0, 2.0), (1.0, 3.0), (5.0, 6.0)]);
+        assert_eq!(a.iter().count(), 2);
+        assert_eq!(a.length(), 4.0);
+
+        let b = set(&[(2.0, 5.5)]);
+        assert_eq!(a.union(&b), set(&[(0.0, 6.0)]));
+        assert_eq!(a.intersection(&b), set(&[(2.0, 3.0), (5.0, 5.5)]));
+        assert_eq!(a.subtract(&b), set(&[(0.0, 2.0), (5.5, 6.0)]));
+        assert!(a.contains(5.0) && !a.contains(4.0));
+
+        let c = a.complement();
+        assert!(c.contains(-100.0) && c.contains(4.0) && !c.contains(1.0));
+        assert_eq!(c.complement(), a);
+        assert!(IntervalSet::new().complement().contains(0.0));
+    }
+
+    #[test]
+    fn skyline_occupancy() {
+        let sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, -1.0, 2.0, -0.5),
+                                              Rect::new(2.0, 0.0, 3.0, 1.0),
+                                              Rect::new(4.0, 0.0, 5.0, 1.0)]);
+        assert_eq!(sky.occupied(), set(&[(0.0, 3.0), (4.0, 5.0)]));
+        assert!(Skyline::<Up>::empty().occupied().is_empty());
+    }
+
+    #[test]
+    fn clearance_above_threshold() {
+        let floor = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 1.0),
+                                                Rect::new(4.0, 0.0, 6.0, 2.0)]);
+        let mut ceiling = Skyline::<Down>::single(0.0, 4.0, 4.0, 4.0);
+        ceiling.merge(&Skyline::single(4.0, 4.0, 8.0, 0.0));
+
+        let gap = floor.gap_profile(&ceiling);
+        assert_eq!(gap.height_at(1.0), 3.0);
+        assert_eq!(gap.height_at(3.0), Float::infinity());
+        assert_eq!(gap.height_at(5.0), 1.0);
+
+        let inf: f64 = Float::infinity();
+        assert_eq!(gap.where_above(1.5), set(&[(-inf, 4.5), (6.0, inf)]));
+        assert_eq!(floor.where_above(1.5), set(&[(4.0, 6.0)]));
+    }
+}
@@ -0,0 +1,80 @@
+use std::rand::Rng;
+use {Building, Direction, Skyline};
+
+// A uniformly random number in [-max, max].
+fn noise<R: Rng>(rng: &mut R, max: f64) -> f64 {
+    if max > 0.0 { rng.gen_range(-max, max) } else { 0.0 }
+}
+
+impl<T: Direction> Skyline<T> {
+    // A randomly perturbed copy of this skyline, for checking that things
+    // built on top of it are stable under small changes: every breakpoint
+    // moves sideways by at most `max_dx`, and the two ends of every building
+    // move up or down by at most `max_dy`.
+    //
+    // The result is always valid. Breakpoints don't pass each other or
+    // leave the domain, empty buildings stay empty, buildings that get
+    // too steep have their slopes capped, and buildings that go on forever
+    // only move up or down.
+    pub fn jitter<R: Rng>(&self, rng: &mut R, max_dx: f64, max_dy: f64) -> Skyline<T> {
+        assert!(max_dx >= 0.0 && max_dy >= 0.0, "negative jitter ({}, {})", max_dx, max_dy);
+        let d = self.domain;
+        let mut out = Vec::with_capacity(self.buildings.len());
+        let mut start: f64 = Float::neg_infinity();
+        let mut new_start: f64 = Float::neg_infinity();
+
+        for b in self.buildings.iter() {
+            let mut end = b.end;
+            if end.is_finite() && d.contains(end) {
+                end = (end + noise(rng, max_dx)).max(d.lo).min(d.hi);
+            }
+            end = end.max(new_start);
+
+            let moved = if b.b == Float::neg_infinity() {
+                Building::empty(end)
+            } else if start.is_finite() && b.end.is_finite() {
+                Building::from_points_capped(new_start, b.y(start) + noise(rng, max_dy),
+                                             end, b.y(b.end) + noise(rng, max_dy),
+                                             self.config.max_slope)
+            } else {
+                Building { m: b.m, b: b.b + noise(rng, max_dy), end: end }
+            };
+            out.push(moved);
+            start = b.end;
+            new_start = end;
+        }
+
+        Skyline {
+            buildings: out,
+            domain: d,
+            config: self.config.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::{SeedableRng, StdRng};
+    use {Rect, Skyline, Up};
+
+    #[test]
+    fn jitter_stays_close() {
+        let mut sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 3.0),
+                                                  Rect::new(2.0, 0.0, 4.0, 1.0)]);
+        sky.merge(&Skyline::single(3.0, 0.0, 6.0, 4.0));
+        sky.bound(-1.0, 7.0);
+
+        let seed: &[uint] = &[1, 2, 3, 4];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        for _ in range(0u, 50) {
+            let j = sky.jitter(&mut rng, 0.1, 0.2);
+            assert_eq!(j.validate(), Ok(()));
+            // Away from the breakpoints (at 0, 2, 3.75 and 6), heights only
+            // move by max_dy plus the slope times max_dx.
+            for &x in [0.5, 1.0, 1.5, 2.5, 3.0, 3.3, 4.5, 5.0, 5.5].iter() {
+                let diff = (j.height_at(x) - sky.height_at(x)).abs();
+                assert!(diff <= 0.2 + 4.0 / 3.0 * 0.1 + 1e-6, "at {}: moved by {}", x, diff);
+            }
+        }
+    }
+}
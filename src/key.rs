@@ -0,0 +1,158 @@
+use std::i64;
+use {Building, Dir, Direction, Skyline};
+
+// A hashable summary of a skyline, for use as a cache key.
+//
+// The key records the envelope rather than the buildings: we drop pieces
+// that vanish when their endpoints are rounded to the grid, coalesce
+// neighboring empty or flat pieces of the same height, and round every
+// remaining breakpoint and endpoint height to the nearest multiple of
+// `grid`. Sloped pieces that were split by a merge are not rejoined, so
+// two skylines with the same envelope can still have different keys.
+//
+// Collisions are intentional: if two skylines have the same key then their
+// pieces correspond one-to-one, with breakpoints and endpoint heights that
+// differ by less than `grid` (`SkylineKey::verify` checks this). The
+// converse doesn't hold, since two nearly identical skylines can round to
+// opposite sides of a grid line. Keys are only comparable if they were made
+// with the same grid.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub struct SkylineKey {
+    dir: Dir,
+    pieces: Vec<Piece<i64>>
+}
+
+// A piece of the envelope, with the heights at its start and end. The start
+// of a piece is the end of the previous one.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+struct Piece<N> {
+    end: N,
+    y0: N,
+    y1: N
+}
+
+// NaNs get a key of their own, but `verify` will reject any pair of
+// skylines that contains them.
+static NAN_KEY: i64 = i64::MIN + 1;
+
+fn quantize(v: f64, grid: f64) -> i64 {
+    if v.is_nan() {
+        NAN_KEY
+    } else if v == Float::infinity() {
+        i64::MAX
+    } else if v == Float::neg_infinity() {
+        i64::MIN
+    } else {
+        (v / grid).round().max(-9.0e18).min(9.0e18) as i64
+    }
+}
+
+// Like `Building::y`, but well-behaved for flat buildings at infinity.
+fn height(b: &Building, x: f64) -> f64 {
+    if x.is_infinite() && b.m == 0.0 { b.b } else { b.y(x) }
+}
+
+// Returns the canonical pieces of `sky`, both unrounded and rounded.
+fn canonical_pieces<T: Direction>(sky: &Skyline<T>, grid: f64)
+        -> (Vec<Piece<f64>>, Vec<Piece<i64>>) {
+    let mut raw: Vec<Piece<f64>> = Vec::new();
+    let mut rounded: Vec<Piece<i64>> = Vec::new();
+    let mut start: f64 = Float::neg_infinity();
+
+    for b in sky.buildings.iter() {
+        let piece = Piece { end: b.end, y0: height(b, start), y1: height(b, b.end) };
+        let q = Piece {
+            end: quantize(piece.end, grid),
+            y0: quantize(piece.y0, grid),
+            y1: quantize(piece.y1, grid)
+        };
+        start = b.end;
+
+        let (qs, coalesce) = match rounded.last() {
+            Some(last) => (last.end,
+                           last.y0 == last.y1 && last.y1 == q.y0 && q.y0 == q.y1),
+            None => (i64::MIN, false),
+        };
+        if q.end == qs {
+            continue;
+        }
+
+        if coalesce {
+            rounded.last_mut().unwrap().end = q.end;
+            let last_raw = raw.last_mut().unwrap();
+            last_raw.end = piece.end;
+            last_raw.y1 = piece.y1;
+        } else {
+            rounded.push(q);
+            raw.push(piece);
+        }
+    }
+
+    (raw, rounded)
+}
+
+fn close(a: f64, b: f64, grid: f64) -> bool {
+    a == b || (a - b).abs() < grid
+}
+
+impl SkylineKey {
+    pub fn new<T: Direction>(sky: &Skyline<T>, grid: f64) -> SkylineKey {
+        assert!(grid > 0.0, "grid must be positive, not {}", grid);
+        let (_, pieces) = canonical_pieces(sky, grid);
+        SkylineKey {
+            dir: sky.dir(),
+            pieces: pieces
+        }
+    }
+
+    // Returns false if `a` and `b` have the same key but aren't within the
+    // tolerance described above (which can only happen if one of them
+    // contains NaNs). Returns true if the keys differ.
+    pub fn verify<T: Direction>(a: &Skyline<T>, b: &Skyline<T>, grid: f64) -> bool {
+        let (raw_a, key_a) = canonical_pieces(a, grid);
+        let (raw_b, key_b) = canonical_pieces(b, grid);
+        if key_a != key_b {
+            return true;
+        }
+
+        raw_a.iter().zip(raw_b.iter()).all(|(p, q)| {
+            close(p.end, q.end, grid) && close(p.y0, q.y0, grid) && close(p.y1, q.y1, grid)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Skyline, Up, Down};
+    use super::SkylineKey;
+
+    #[test]
+    fn key_tolerates_jitter() {
+        let a = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        let b = Skyline::<Up>::single(0.001, 1.002, 2.003, 0.999);
+        assert_eq!(SkylineKey::new(&a, 0.1), SkylineKey::new(&b, 0.1));
+        assert!(SkylineKey::verify(&a, &b, 0.1));
+        assert!(SkylineKey::new(&a, 0.0001) != SkylineKey::new(&b, 0.0001));
+    }
+
+    #[test]
+    fn key_ignores_split_buildings() {
+        let mut a = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        a.merge(&Skyline::single(1.0, 1.0, 2.0, 1.0));
+        let b = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        assert_eq!(SkylineKey::new(&a, 0.01), SkylineKey::new(&b, 0.01));
+    }
+
+    #[test]
+    fn key_distinguishes_directions() {
+        let a = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        let b = Skyline::<Down>::single(0.0, -1.0, 2.0, -1.0);
+        assert!(SkylineKey::new(&a, 0.1) != SkylineKey::new(&b, 0.1));
+    }
+
+    #[test]
+    fn verify_rejects_nan() {
+        let a = Skyline::<Up>::single(0.0, Float::nan(), 2.0, Float::nan());
+        assert!(!SkylineKey::verify(&a, &a, 0.1));
+    }
+}
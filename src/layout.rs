@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use {Direction, Shape, Skyline, SkylineConfig, Up};
+
+// Identifies a shape within a `Layout`.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub struct ShapeId(pub uint);
+
+// Identifies a group of shapes within a `Layout`.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub struct GroupId(pub uint);
+
+#[deriving(Clone, Show)]
+struct Item {
+    // In the group's coordinates.
+    shape: Shape,
+    group: GroupId,
+    z: i32
+}
+
+#[deriving(Clone, Show)]
+struct Group {
+    dx: f64,
+    dy: f64
+}
+
+// A collection of placed shapes. Every shape belongs to a group, and can be
+// moved either on its own or along with the rest of its group (e.g. all of
+// the labels on one layer of a map). Shapes also have a z-order, for
+// drawing.
+#[deriving(Clone, Show)]
+pub struct Layout {
+    items: Vec<Item>,
+    groups: Vec<Group>,
+    // Applied to the envelopes that the layout builds.
+    config: SkylineConfig,
+    // Every shape, in world coordinates, at the time of each snapshot.
+    snapshots: HashMap<String, Vec<Shape>>
+}
+
+// How one snapshot of a layout differs from another, as computed by
+// `Layout::compare`.
+#[deriving(Clone, PartialEq, Show)]
+pub struct LayoutDiff {
+    // The shapes whose bounding boxes changed, with how far the bottom left
+    // corner moved. Shapes that were only in one of the snapshots aren't
+    // included.
+    pub moved: Vec<(ShapeId, f64, f64)>,
+    // The change in the area under the upward envelope of all the shapes,
+    // measured from the bottom of their bounding box. This is the space
+    // that the layout uses up when things are packed from below.
+    pub area_delta: f64,
+    // The change in the fraction of that area that is covered by shapes.
+    pub utilization_delta: f64
+}
+
+// The area inside a simple polygon.
+fn polygon_area(points: &[(f64, f64)]) -> f64 {
+    let n = points.len();
+    let mut twice = 0.0;
+    for i in range(0, n) {
+        let (x1, y1) = points[i];
+        let (x2, y2) = points[(i + 1) % n];
+        twice += x1 * y2 - x2 * y1;
+    }
+    (twice / 2.0).abs()
+}
+
+// The area under the upward envelope of `shapes` (see `LayoutDiff`), and
+// the fraction of it that the shapes cover.
+fn envelope_stats(shapes: &[Shape], config: &SkylineConfig) -> (f64, f64) {
+    if shapes.is_empty() {
+        return (0.0, 0.0);
+    }
+    let bounds = shapes.iter().skip(1).fold(shapes[0].bounds(), |r, s| r.union(&s.bounds()));
+    let mut skylines = vec![Skyline::<Up>::with_config(config.clone())];
+    skylines.extend(shapes.iter().map(|s| s.skyline()));
+    let area = Skyline::merge_all(skylines).area(bounds.x0, bounds.x1, bounds.y0);
+    let covered = shapes.iter().fold(0.0, |a, s| a + polygon_area(s.points()));
+    (area, if area > 0.0 { covered / area } else { 0.0 })
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout {
+            items: Vec::new(),
+            groups: Vec::new(),
+            config: SkylineConfig::new(),
+            snapshots: HashMap::new()
+        }
+    }
+
+    pub fn config(&self) -> &SkylineConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: SkylineConfig) {
+        self.config = config;
+    }
+
+    pub fn add_group(&mut self) -> GroupId {
+        self.groups.push(Group { dx: 0.0, dy: 0.0 });
+        GroupId(self.groups.len() - 1)
+    }
+
+    // Adds a shape to a group. The shape's coordinates are relative to the
+    // group's offset.
+    pub fn add(&mut self, shape: Shape, group: GroupId, z: i32) -> ShapeId {
+        let GroupId(g) = group;
+        assert!(g < self.groups.len(), "no such group {}", group);
+        self.items.push(Item { shape: shape, group: group, z: z });
+        ShapeId(self.items.len() - 1)
+    }
+
+    pub fn len(&self) -> uint {
+        self.items.len()
+    }
+
+    fn item(&self, id: ShapeId) -> &Item {
+        let ShapeId(i) = id;
+        &self.items[i]
+    }
+
+    fn item_mut(&mut self, id: ShapeId) -> &mut Item {
+        let ShapeId(i) = id;
+        &mut self.items[i]
+    }
+
+    fn group(&self, id: GroupId) -> &Group {
+        let GroupId(g) = id;
+        &self.groups[g]
+    }
+
+    // The shape, in world coordinates.
+    pub fn shape(&self, id: ShapeId) -> Shape {
+        let item = self.item(id);
+        let g = self.group(item.group);
+        item.shape.translate(g.dx, g.dy)
+    }
+
+    pub fn group_of(&self, id: ShapeId) -> GroupId {
+        self.item(id).group
+    }
+
+    pub fn z(&self, id: ShapeId) -> i32 {
+        self.item(id).z
+    }
+
+    pub fn set_z(&mut self, id: ShapeId, z: i32) {
+        self.item_mut(id).z = z;
+    }
+
+    pub fn translate_shape(&mut self, id: ShapeId, dx: f64, dy: f64) {
+        let item = self.item_mut(id);
+        item.shape = item.shape.translate(dx, dy);
+    }
+
+    pub fn translate_group(&mut self, id: GroupId, dx: f64, dy: f64) {
+        let GroupId(g) = id;
+        let group = &mut self.groups[g];
+        group.dx += dx;
+        group.dy += dy;
+    }
+
+    pub fn group_offset(&self, id: GroupId) -> (f64, f64) {
+        let g = self.group(id);
+        (g.dx, g.dy)
+    }
+
+    pub fn shapes_in(&self, group: GroupId) -> Vec<ShapeId> {
+        range(0, self.items.len())
+            .filter(|&i| self.items[i].group == group)
+            .map(|i| ShapeId(i))
+            .collect()
+    }
+
+    // All the shapes, from back to front. Shapes with the same z are in the
+    // order they were added.
+    pub fn z_order(&self) -> Vec<ShapeId> {
+        let mut keys: Vec<(i32, uint)> = range(0, self.items.len())
+            .map(|i| (self.items[i].z, i))
+            .collect();
+        keys.sort();
+        keys.into_iter().map(|(_, i)| ShapeId(i)).collect()
+    }
+
+    // The combined skyline of all the shapes in a group, in world
+    // coordinates.
+    pub fn group_envelope<T: Direction>(&self, group: GroupId) -> Skyline<T> {
+        let mut skylines = vec![Skyline::with_config(self.config.clone())];
+        skylines.extend(self.shapes_in(group).into_iter().map(|id| self.shape(id).skyline()));
+        Skyline::merge_all(skylines)
+    }
+
+    // Remembers where every shape is right now, under `name` (replacing any
+    // earlier snapshot with that name).
+    pub fn snapshot(&mut self, name: &str) {
+        let shapes = range(0, self.items.len()).map(|i| self.shape(ShapeId(i))).collect();
+        self.snapshots.insert(name.to_string(), shapes);
+    }
+
+    // How the layout changed between two snapshots, or `None` if there's no
+    // snapshot with one of the names. This is for measuring whether a
+    // change to a placement heuristic actually helps.
+    pub fn compare(&self, a: &str, b: &str) -> Option<LayoutDiff> {
+        let (before, after) = match (self.snapshots.get(a), self.snapshots.get(b)) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return None,
+        };
+
+        let mut moved = Vec::new();
+        for (i, (s, t)) in before.iter().zip(after.iter()).enumerate() {
+            let (r, q) = (s.bounds(), t.bounds());
+            if r != q {
+                moved.push((ShapeId(i), q.x0 - r.x0, q.y0 - r.y0));
+            }
+        }
+        let (area_a, util_a) = envelope_stats(before.as_slice(), &self.config);
+        let (area_b, util_b) = envelope_stats(after.as_slice(), &self.config);
+        Some(LayoutDiff {
+            moved: moved,
+            area_delta: area_b - area_a,
+            utilization_delta: util_b - util_a
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Shape, Up};
+    use super::{Layout, ShapeId};
+
+    #[test]
+    fn layout_groups() {
+        let mut layout = Layout::new();
+        let roads = layout.add_group();
+        let labels = layout.add_group();
+        let road = layout.add(Shape::from_rect(&Rect::new(0.0, 0.0, 10.0, 1.0)), roads, 0);
+        let a = layout.add(Shape::from_rect(&Rect::new(1.0, 0.0, 3.0, 1.0)), labels, 2);
+        let b = layout.add(Shape::from_rect(&Rect::new(5.0, 0.0, 7.0, 2.0)), labels, 1);
+
+        layout.translate_group(labels, 0.0, 1.0);
+        assert_eq!(layout.shape(a).bounds(), Rect::new(1.0, 1.0, 3.0, 2.0));
+        assert_eq!(layout.shape(road).bounds(), Rect::new(0.0, 0.0, 10.0, 1.0));
+        assert_eq!(layout.shapes_in(labels), vec![a, b]);
+        assert_eq!(layout.z_order(), vec![road, b, a]);
+
+        let env = layout.group_envelope::<Up>(labels);
+        assert!(env.height_at(2.0).approx_eq(2.0));
+        assert!(env.height_at(6.0).approx_eq(3.0));
+        assert_eq!(env.height_at(4.0), Float::neg_infinity());
+
+        layout.set_z(road, 5);
+        assert_eq!(layout.z_order()[2], ShapeId(0));
+    }
+    #[test]
+    fn compare_snapshots() {
+        let mut layout = Layout::new();
+        let g = layout.add_group();
+        let a = layout.add(Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 1.0)), g, 0);
+        let b = layout.add(Shape::from_rect(&Rect::new(0.0, 1.0, 2.0, 2.0)), g, 0);
+        layout.snapshot("stacked");
+        layout.translate_shape(b, 2.0, -1.0);
+        layout.snapshot("side by side");
+
+        let diff = layout.compare("stacked", "side by side").unwrap();
+        assert_eq!(diff.moved, vec![(b, 2.0, -1.0)]);
+        assert!(diff.area_delta.approx_eq(0.0));
+        assert!(diff.utilization_delta.approx_eq(0.0));
+        assert!(layout.compare("stacked", "missing").is_none());
+
+        // Lifting `a` leaves a hole under it.
+        layout.translate_shape(a, 0.0, 1.0);
+        layout.snapshot("lifted");
+        let diff = layout.compare("side by side", "lifted").unwrap();
+        assert_eq!(diff.moved, vec![(a, 0.0, 1.0)]);
+        assert!(diff.area_delta.approx_eq(2.0));
+        assert!(diff.utilization_delta.approx_eq(-1.0 / 3.0));
+    }
+}
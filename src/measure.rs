@@ -0,0 +1,683 @@
+use std::cmp::Ordering;
+use {Building, Dir, Direction, Down, Flip, Interval, Skyline};
+use {pieces, search, support};
+#[cfg(feature = "exact")]
+use robust;
+
+// The height of a list of buildings at `x`, in internal coordinates. Where
+// buildings meet, we take the highest one.
+fn height_at(bldgs: &[Building], x: f64) -> f64 {
+    let mut h: f64 = Float::neg_infinity();
+    for b in bldgs.slice_from(search(bldgs, x)).iter() {
+        h = h.max(b.y(x));
+        if b.end > x {
+            break;
+        }
+    }
+    h
+}
+
+// Compares two non-empty buildings at `x`, which may be infinite (in which
+// case we compare the limits).
+fn compare_inexact(b1: &Building, b2: &Building, x: f64) -> Ordering {
+    if x.is_infinite() && b1.m != b2.m {
+        let ord = b1.m.partial_cmp(&b2.m).unwrap();
+        if x > 0.0 { ord } else { ord.reverse() }
+    } else {
+        b1.y(x).partial_cmp(&b2.y(x)).unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(feature = "exact")]
+fn compare_at(b1: &Building, b2: &Building, x: f64) -> Ordering {
+    match robust::compare(b1.m, b1.b, b2.m, b2.b, x) {
+        Some(ord) => ord,
+        None => compare_inexact(b1, b2, x),
+    }
+}
+
+#[cfg(not(feature = "exact"))]
+fn compare_at(b1: &Building, b2: &Building, x: f64) -> Ordering {
+    compare_inexact(b1, b2, x)
+}
+
+// For finding crossings: the last strict comparison we saw, and where the
+// envelopes started agreeing after that (if they do).
+struct CrossingState {
+    last: Ordering,
+    tie: Option<f64>
+}
+
+impl CrossingState {
+    fn visit(&mut self, x: f64, ord: Ordering, out: &mut Vec<f64>) {
+        if ord == Ordering::Equal {
+            if self.tie.is_none() {
+                self.tie = Some(x);
+            }
+        } else {
+            if self.last != Ordering::Equal && ord != self.last {
+                out.push(self.tie.unwrap_or(x));
+            }
+            self.last = ord;
+            self.tie = None;
+        }
+    }
+}
+
+// Ways of measuring how different two envelopes are, for `Skyline::distance`.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Metric {
+    // The largest vertical distance between them (the L-infinity norm).
+    Max,
+    // The area between them (the L1 norm).
+    Area
+}
+
+// The difference between two non-empty buildings on [start, end], in one of
+// the metrics. Either end may be infinite.
+fn piece_distance(b1: &Building, b2: &Building, start: f64, end: f64, metric: &Metric)
+        -> f64 {
+    let dm = b1.m - b2.m;
+    let db = b1.b - b2.b;
+    let d = |x: f64| if x.is_infinite() && dm == 0.0 { db } else { dm * x + db };
+    let (d0, d1) = (d(start), d(end));
+
+    match *metric {
+        Metric::Max => d0.abs().max(d1.abs()),
+        Metric::Area => {
+            if d0 == 0.0 && d1 == 0.0 {
+                0.0
+            } else if !(start.is_finite() && end.is_finite()) {
+                Float::infinity()
+            } else if (d0 < 0.0) != (d1 < 0.0) && d0 != 0.0 && d1 != 0.0 {
+                // The difference changes sign, so the area is two triangles.
+                let mid = -db / dm;
+                ((mid - start) * d0.abs() + (end - mid) * d1.abs()) / 2.0
+            } else {
+                (end - start) * (d0.abs() + d1.abs()) / 2.0
+            }
+        },
+    }
+}
+
+fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    a == b || (a - b).abs() <= tolerance
+}
+
+// The area, and the first moments about the y axis and about the baseline,
+// of the part of the skyline that sticks out past `base` between `x1` and
+// `x2`. Everything is in internal coordinates (i.e., with heights multiplied
+// by the direction multiplier).
+fn moments<T: Direction>(sky: &Skyline<T>, x1: f64, x2: f64, base: f64) -> (f64, f64, f64) {
+    let mut area = 0.0;
+    let mut mx = 0.0;
+    let mut my = 0.0;
+
+    for &(start, ref b) in pieces(sky.buildings.as_slice()).iter() {
+        let mut lo = start.max(x1);
+        let mut hi = b.end.min(x2);
+        if lo >= hi {
+            continue;
+        }
+
+        // Only keep the part of the building above the baseline.
+        let h = |x: f64| b.y(x) - base;
+        if h(lo) <= 0.0 && h(hi) <= 0.0 {
+            continue;
+        } else if h(lo) < 0.0 {
+            lo = (base - b.b) / b.m;
+        } else if h(hi) < 0.0 {
+            hi = (base - b.b) / b.m;
+        }
+
+        // The integrands below are at most quadratic, so Simpson's rule is
+        // exact.
+        let mid = (lo + hi) / 2.0;
+        let w = hi - lo;
+        area += w * (h(lo) + h(hi)) / 2.0;
+        mx += w * (lo * h(lo) + 4.0 * mid * h(mid) + hi * h(hi)) / 6.0;
+        my += w * (h(lo) * h(lo) + 4.0 * h(mid) * h(mid) + h(hi) * h(hi)) / 12.0;
+    }
+
+    (area, mx, my)
+}
+
+impl<T: Direction> Skyline<T> {
+    // The area between `baseline` and the skyline, over the interval
+    // [x1, x2]. Only the parts of the skyline that stick out past the
+    // baseline (upwards, for an upward skyline) count, and empty parts of
+    // the skyline don't count at all.
+    pub fn area(&self, x1: f64, x2: f64, baseline: f64) -> f64 {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let (area, _, _) = moments(self, x1, x2, baseline * mult);
+        area
+    }
+
+    // The centroid of the region whose area is given by `area`, or `None`
+    // if that region is empty.
+    pub fn centroid(&self, x1: f64, x2: f64, baseline: f64) -> Option<(f64, f64)> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let (area, mx, my) = moments(self, x1, x2, baseline * mult);
+        if area > 0.0 {
+            Some((mx / area, baseline + mult * my / area))
+        } else {
+            None
+        }
+    }
+
+    // Returns true if the two skylines' envelopes are within `tolerance` of
+    // each other everywhere (measured vertically), and are empty in the same
+    // places. Unlike comparing buildings, this doesn't care how the
+    // envelopes are split up.
+    pub fn approx_eq_envelope(&self, other: &Skyline<T>, tolerance: f64) -> bool {
+        let in1 = self.buildings.as_slice();
+        let in2 = other.buildings.as_slice();
+        let mut start: f64 = Float::neg_infinity();
+        let mut i = 0u;
+        let mut j = 0u;
+
+        // The highest point of each skyline at the breakpoint `pt`, which
+        // may be the top of a wall.
+        let mut pt: f64 = Float::neg_infinity();
+        let mut top1: f64 = Float::neg_infinity();
+        let mut top2: f64 = Float::neg_infinity();
+
+        while i < in1.len() && j < in2.len() {
+            let b1 = &in1[i];
+            let b2 = &in2[j];
+            let end = if b1.end < b2.end {
+                i += 1;
+                b1.end
+            } else {
+                j += 1;
+                b2.end
+            };
+
+            for &x in [start, end].iter() {
+                if !x.is_finite() {
+                    continue;
+                }
+                if x != pt {
+                    if pt.is_finite() && !close(top1, top2, tolerance) {
+                        return false;
+                    }
+                    pt = x;
+                    top1 = Float::neg_infinity();
+                    top2 = Float::neg_infinity();
+                }
+                top1 = top1.max(b1.y(x));
+                top2 = top2.max(b2.y(x));
+            }
+
+            // Away from the breakpoints, both buildings are linear, so it's
+            // enough to compare them at the ends.
+            if start < end {
+                let empty1 = b1.b == Float::neg_infinity();
+                let empty2 = b2.b == Float::neg_infinity();
+                if empty1 != empty2 {
+                    return false;
+                }
+                if (start.is_finite() && !close(b1.y(start), b2.y(start), tolerance))
+                        || (end.is_finite() && !close(b1.y(end), b2.y(end), tolerance)) {
+                    return false;
+                }
+            }
+            start = end;
+        }
+
+        !pt.is_finite() || close(top1, top2, tolerance)
+    }
+
+    // The places where the two envelopes cross, from left to right: that is,
+    // the points that separate a stretch where one skyline is higher from a
+    // stretch where the other one is. Where the envelopes agree for a while
+    // before crossing, the start of that stretch counts as the crossing.
+    // Wherever either skyline is empty, neither one is higher.
+    pub fn crossings(&self, other: &Skyline<T>) -> Vec<f64> {
+        let in1 = self.buildings.as_slice();
+        let in2 = other.buildings.as_slice();
+        let mut out = Vec::new();
+        let mut start: f64 = Float::neg_infinity();
+        let mut i = 0u;
+        let mut j = 0u;
+
+        let mut state = CrossingState {
+            last: Ordering::Equal,
+            tie: None
+        };
+
+        while i < in1.len() && j < in2.len() {
+            let b1 = &in1[i];
+            let b2 = &in2[j];
+            let end = if b1.end < b2.end {
+                i += 1;
+                b1.end
+            } else {
+                j += 1;
+                b2.end
+            };
+
+            if b1.b == Float::neg_infinity() || b2.b == Float::neg_infinity() {
+                state.last = Ordering::Equal;
+                state.tie = None;
+            } else if start < end {
+                let left = compare_at(b1, b2, start);
+                let right = compare_at(b1, b2, end);
+                state.visit(start, left, &mut out);
+                if left != Ordering::Equal && right != Ordering::Equal && left != right {
+                    let x = b1.intersection(b2).max(start).min(end);
+                    state.visit(x, Ordering::Equal, &mut out);
+                }
+                state.visit(end, right, &mut out);
+            }
+            start = end;
+        }
+        out
+    }
+
+    // How different the two envelopes are, in the given metric. The
+    // distance is infinite if one of them is empty somewhere that the other
+    // one isn't, or if they differ on an infinite stretch (except that the
+    // largest vertical distance can be finite there). Vertical walls don't
+    // count.
+    pub fn distance(&self, other: &Skyline<T>, metric: Metric) -> f64 {
+        let in1 = self.buildings.as_slice();
+        let in2 = other.buildings.as_slice();
+        let mut start: f64 = Float::neg_infinity();
+        let mut i = 0u;
+        let mut j = 0u;
+        let mut dist = 0.0;
+
+        while i < in1.len() && j < in2.len() {
+            let b1 = &in1[i];
+            let b2 = &in2[j];
+            let end = if b1.end < b2.end {
+                i += 1;
+                b1.end
+            } else {
+                j += 1;
+                b2.end
+            };
+
+            if start < end {
+                let empty1 = b1.b == Float::neg_infinity();
+                let empty2 = b2.b == Float::neg_infinity();
+                let d = if empty1 && empty2 {
+                    0.0
+                } else if empty1 || empty2 {
+                    Float::infinity()
+                } else {
+                    piece_distance(b1, b2, start, end, &metric)
+                };
+                dist = match metric {
+                    Metric::Max => dist.max(d),
+                    Metric::Area => dist + d,
+                };
+            }
+            start = end;
+        }
+        dist
+    }
+
+    // The clearance between this skyline and `other` as a function of x:
+    // how far apart they are, measured vertically, where positive means
+    // they're separated. The tightest point of the profile is the negation
+    // of `overlap`. Where either skyline is empty, nothing gets in the way,
+    // so the profile is empty there (i.e., infinite). Use `where_above` on
+    // the result to find where the clearance exceeds a threshold.
+    pub fn gap_profile<S: Flip<T>>(&self, other: &Skyline<S>) -> Skyline<Down> {
+        let in1 = self.buildings.as_slice();
+        let in2 = other.buildings.as_slice();
+        let mut out: Vec<Building> = Vec::with_capacity(in1.len() + in2.len());
+        let mut i = 0u;
+        let mut j = 0u;
+
+        // In internal coordinates the two skylines' heights add up to minus
+        // the clearance, which is the internal height of a downward skyline
+        // whose world height is the clearance.
+        while i < in1.len() && j < in2.len() {
+            let b1 = &in1[i];
+            let b2 = &in2[j];
+            let end = if b1.end < b2.end {
+                i += 1;
+                b1.end
+            } else {
+                j += 1;
+                b2.end
+            };
+
+            if b1.b == Float::neg_infinity() || b2.b == Float::neg_infinity() {
+                let extend = match out.last() {
+                    Some(prev) => prev.b == Float::neg_infinity(),
+                    None => false,
+                };
+                if extend {
+                    out.last_mut().unwrap().end = end;
+                } else {
+                    out.push(Building::empty(end));
+                }
+            } else {
+                out.push(Building { m: b1.m + b2.m, b: b1.b + b2.b, end: end });
+            }
+        }
+
+        let mut ret = Skyline::from_buildings(out);
+        if self.is_bounded() || other.is_bounded() {
+            let (d1, d2) = (self.domain, other.domain);
+            ret.bound(d1.lo.max(d2.lo), d1.hi.min(d2.hi));
+        }
+        ret
+    }
+
+    // The height of the skyline at `x`, in O(log n) time. Where there's a
+    // vertical wall, this is the top of the wall. Where the skyline is
+    // empty, it's infinitely far away in the opposite direction to the
+    // skyline's.
+    pub fn height_at(&self, x: f64) -> f64 {
+        let mult = Direction::direction_multiplier(None::<T>);
+        height_at(self.buildings.as_slice(), x) * mult
+    }
+
+    // Fills `out` with the heights at x0, x0 + dx, x0 + 2 * dx, and so on,
+    // as `height_at` would compute them, but in a single pass over the
+    // buildings.
+    pub fn sample_into(&self, x0: f64, dx: f64, out: &mut [f64]) {
+        assert!(dx > 0.0, "sample spacing must be positive ({})", dx);
+        let mult = Direction::direction_multiplier(None::<T>);
+        let bldgs = self.buildings.as_slice();
+        let mut k = 0u;
+
+        for (i, h) in out.iter_mut().enumerate() {
+            let x = x0 + i as f64 * dx;
+            while k < bldgs.len() && bldgs[k].end < x {
+                k += 1;
+            }
+
+            let mut y: f64 = Float::neg_infinity();
+            for b in bldgs.slice_from(k).iter() {
+                y = y.max(b.y(x));
+                if b.end > x {
+                    break;
+                }
+            }
+            *h = y * mult;
+        }
+    }
+
+    // How far (x, y) is from the skyline, measured in the skyline's
+    // direction: positive if the point is clear of the skyline (above it,
+    // for an upward skyline) and negative if it is inside. The result is
+    // infinite where the skyline is empty, and negative infinity outside
+    // the skyline's domain.
+    pub fn signed_clearance_at(&self, x: f64, y: f64) -> f64 {
+        if !self.domain.contains(x) {
+            return Float::neg_infinity();
+        }
+        let mult = Direction::direction_multiplier(None::<T>);
+        y * mult - height_at(self.buildings.as_slice(), x)
+    }
+
+    // The smallest interval outside of which the skyline is empty, or `None`
+    // if it's empty everywhere.
+    pub fn x_span(&self) -> Option<Interval> {
+        support(self.buildings.as_slice()).map(|(lo, hi)| Interval { lo: lo, hi: hi })
+    }
+
+    // How far the skyline reaches in its own direction: its highest point
+    // for an upward skyline, and its lowest for a downward one. Like
+    // `height_at`, this is infinitely far in the wrong direction if the
+    // skyline is empty.
+    pub fn max_height(&self) -> f64 {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let mut h: f64 = Float::neg_infinity();
+        let mut start: f64 = Float::neg_infinity();
+        for b in self.buildings.iter() {
+            if b.b > Float::neg_infinity() {
+                h = h.max(end_height(b, start)).max(end_height(b, b.end));
+            }
+            start = b.end;
+        }
+        h * mult
+    }
+
+    // The point of the skyline that is furthest in the direction `dir`, or
+    // `None` if the skyline has no finite buildings. This works in the
+    // skyline's own coordinates: `Dir::Left` and `Dir::Right` go along the
+    // axis that the skyline is defined over, and `Dir::Up` and `Dir::Down`
+    // along its heights. Ties go to the left-most point, or for `Dir::Left`
+    // and `Dir::Right` to the point furthest in the skyline's direction.
+    // Buildings that go on forever are ignored.
+    pub fn support_point(&self, dir: Dir) -> Option<(f64, f64)> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let mut best: Option<(f64, f64)> = None;
+        for &(start, ref b) in pieces(self.buildings.as_slice()).iter() {
+            for &x in [start, b.end].iter() {
+                let p = (x, b.y(x));
+                best = match best {
+                    None => Some(p),
+                    Some(q) => Some(if further(dir, mult, p, q) { p } else { q }),
+                };
+            }
+        }
+        best.map(|(x, y)| (x, y * mult))
+    }
+
+    // The steepest slope (in absolute value) of the skyline's finite
+    // pieces, or zero if there aren't any. If this is the configured maximum
+    // slope, some of the input was probably steeper and got capped.
+    pub fn max_abs_slope(&self) -> f64 {
+        pieces(self.buildings.as_slice()).iter().fold(0.0, |m, &(_, ref b)| m.max(b.m.abs()))
+    }
+
+    // How many of the finite pieces have their absolute slopes in each of
+    // `bins` equal parts of [0, max_slope], where `max_slope` is the
+    // configured maximum. Pieces whose slopes were capped end up in the
+    // last bin, so a big count there means that the cap is changing the
+    // shape of the input.
+    pub fn slope_histogram(&self, bins: uint) -> Vec<uint> {
+        assert!(bins > 0, "a histogram needs at least one bin");
+        let mut counts = Vec::from_elem(bins, 0u);
+        let width = self.config.max_slope / bins as f64;
+        for &(_, ref b) in pieces(self.buildings.as_slice()).iter() {
+            let i = (b.m.abs() / width) as uint;
+            counts[i.min(bins - 1)] += 1;
+        }
+        counts
+    }
+}
+
+// The height of a non-empty building at one of its ends, which may be
+// infinite.
+fn end_height(b: &Building, x: f64) -> f64 {
+    if x.is_finite() {
+        b.y(x)
+    } else if b.m == 0.0 {
+        b.b
+    } else {
+        b.m * x
+    }
+}
+
+// Whether the point `p` is strictly further than `q` in the direction `dir`,
+// for points in internal coordinates of a skyline with direction
+// multiplier `mult`. The points come from left to right.
+fn further(dir: Dir, mult: f64, p: (f64, f64), q: (f64, f64)) -> bool {
+    let ((px, py), (qx, qy)) = (p, q);
+    match dir {
+        Dir::Up => py * mult > qy * mult,
+        Dir::Down => py * mult < qy * mult,
+        Dir::Left => px < qx || (px == qx && py > qy),
+        Dir::Right => px > qx || (px == qx && py > qy),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Dir, Interval, Metric, Rect, Skyline, Up, Down};
+
+    #[test]
+    fn extents() {
+        let mut sky = Skyline::<Down>::single(0.0, 1.0, 2.0, -1.0);
+        sky.merge(&Skyline::single(3.0, 2.0, 4.0, 2.0));
+        assert_eq!(sky.x_span(), Some(Interval::new(0.0, 4.0)));
+        assert_eq!(sky.max_height(), -1.0);
+        assert_eq!(sky.support_point(Dir::Down), Some((2.0, -1.0)));
+        assert_eq!(sky.support_point(Dir::Up), Some((3.0, 2.0)));
+        assert_eq!(sky.support_point(Dir::Left), Some((0.0, 1.0)));
+        assert_eq!(sky.support_point(Dir::Right), Some((4.0, 2.0)));
+
+        let empty = Skyline::<Up>::empty();
+        assert_eq!(empty.x_span(), None);
+        assert_eq!(empty.max_height(), Float::neg_infinity());
+        assert_eq!(empty.support_point(Dir::Up), None);
+    }
+
+    #[test]
+    fn slopes() {
+        let mut sky = Skyline::<Down>::single(0.0, 0.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(2.0, 0.0, 3.0, -600.0));
+        sky.merge(&Skyline::single(4.0, 0.0, 4.0001, 5.0));
+        assert_eq!(sky.max_abs_slope(), 1e3);
+        assert_eq!(sky.slope_histogram(4), vec![1, 0, 1, 1]);
+        assert_eq!(Skyline::<Up>::empty().max_abs_slope(), 0.0);
+    }
+
+    #[test]
+    fn area_of_rects() {
+        let sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 1.0),
+                                              Rect::new(3.0, 0.0, 4.0, 3.0)]);
+        assert!(sky.area(-10.0, 10.0, 0.0).approx_eq(5.0));
+        assert!(sky.area(1.0, 3.5, 0.0).approx_eq(2.5));
+        assert!(sky.area(-10.0, 10.0, 2.0).approx_eq(1.0));
+
+        let (cx, cy) = sky.centroid(-10.0, 10.0, 0.0).unwrap();
+        assert!(cx.approx_eq((2.0 * 1.0 + 3.0 * 3.5) / 5.0), "cx = {}", cx);
+        assert!(cy.approx_eq((2.0 * 0.5 + 3.0 * 1.5) / 5.0), "cy = {}", cy);
+    }
+
+    #[test]
+    fn area_of_triangle() {
+        // A slope that crosses the baseline halfway along.
+        let sky = Skyline::<Up>::single(0.0, -1.0, 2.0, 1.0);
+        assert!(sky.area(0.0, 2.0, 0.0).approx_eq(0.5));
+        let (cx, cy) = sky.centroid(0.0, 2.0, 0.0).unwrap();
+        assert!(cx.approx_eq(5.0 / 3.0), "cx = {}", cx);
+        assert!(cy.approx_eq(1.0 / 3.0), "cy = {}", cy);
+        assert!(sky.centroid(0.0, 2.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn clearance() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0);
+        assert!(sky.signed_clearance_at(1.0, 5.0).approx_eq(3.0));
+        assert!(sky.signed_clearance_at(1.0, 1.5).approx_eq(-0.5));
+        assert_eq!(sky.signed_clearance_at(3.0, 0.0), Float::infinity());
+
+        // At a breakpoint, the higher side counts.
+        sky.merge(&Skyline::single(2.0, 0.0, 4.0, 0.0));
+        assert!(sky.signed_clearance_at(2.0, 4.0).approx_eq(1.0));
+
+        sky.bound(0.0, 4.0);
+        assert_eq!(sky.signed_clearance_at(5.0, 10.0), Float::neg_infinity());
+
+        let ceiling = Skyline::<Down>::single(0.0, 2.0, 1.0, 2.0);
+        assert!(ceiling.signed_clearance_at(0.5, 0.5).approx_eq(1.5));
+        assert!(ceiling.signed_clearance_at(0.5, 3.0).approx_eq(-1.0));
+    }
+
+    #[test]
+    fn envelope_comparison() {
+        let mut a = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        a.merge(&Skyline::single(1.0, 1.0, 2.0, 1.0));
+        let b = Skyline::<Up>::single(0.0, 1.001, 2.0, 1.001);
+        assert!(a.approx_eq_envelope(&b, 0.01));
+        assert!(!a.approx_eq_envelope(&b, 0.0001));
+
+        let c = Skyline::<Up>::single(0.0, 1.0, 3.0, 1.0);
+        assert!(!a.approx_eq_envelope(&c, 0.01));
+        assert!(c.approx_eq_envelope(&c, 0.0));
+
+        // Walls count, even though they have no width.
+        let mut d = c.clone();
+        d.merge(&Skyline::single(1.0, 0.0, 1.0, 5.0));
+        assert!(!c.approx_eq_envelope(&d, 0.01));
+    }
+
+    #[test]
+    fn height_lookup() {
+        let sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 1.0, 2.0),
+                                              Rect::new(1.0, 0.0, 2.0, 1.0),
+                                              Rect::new(3.0, 0.0, 4.0, 3.0)]);
+        assert!(sky.height_at(0.5).approx_eq(2.0));
+        assert!(sky.height_at(1.0).approx_eq(2.0));
+        assert!(sky.height_at(1.5).approx_eq(1.0));
+        assert!(sky.height_at(4.0).approx_eq(3.0));
+        assert_eq!(sky.height_at(2.5), Float::neg_infinity());
+        assert_eq!(sky.height_at(-10.0), Float::neg_infinity());
+
+        let down = Skyline::<Down>::single(0.0, 1.0, 1.0, 1.0);
+        assert!(down.height_at(0.5).approx_eq(1.0));
+        assert_eq!(down.height_at(2.0), Float::infinity());
+    }
+
+    #[test]
+    fn crossings() {
+        // An X, and then a step that jumps from below to above.
+        let mut a = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        a.merge(&Skyline::single(3.0, 0.0, 4.0, 0.0));
+        a.merge(&Skyline::single(4.0, 2.0, 5.0, 2.0));
+        let mut b = Skyline::<Up>::single(0.0, 2.0, 2.0, 0.0);
+        b.merge(&Skyline::single(3.0, 1.0, 5.0, 1.0));
+        assert_eq!(a.crossings(&b), vec![1.0, 4.0]);
+        assert_eq!(b.crossings(&a), vec![1.0, 4.0]);
+
+        // Touching isn't crossing, and neither is a gap.
+        let c = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        let mut d = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        d.merge(&Skyline::single(1.0, 1.0, 2.0, 0.0));
+        assert!(c.crossings(&d).is_empty());
+        let e = Skyline::<Up>::single(3.0, 5.0, 4.0, 5.0);
+        assert!(c.crossings(&e).is_empty());
+    }
+
+    #[test]
+    fn sampling() {
+        let sky = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 1.0, 2.0),
+                                              Rect::new(1.0, 0.0, 2.0, 1.0),
+                                              Rect::new(3.0, 0.0, 4.0, 3.0)]);
+        let mut out = [0.0 as f64, ..11];
+        sky.sample_into(-0.5, 0.5, &mut out);
+        for (i, &h) in out.iter().enumerate() {
+            let x = -0.5 + i as f64 * 0.5;
+            assert_eq!(h, sky.height_at(x));
+        }
+
+        let down = Skyline::<Down>::single(0.0, 1.0, 2.0, 3.0);
+        let mut out = [0.0 as f64, ..3];
+        down.sample_into(0.5, 1.0, &mut out);
+        assert!(out[0].approx_eq(1.5) && out[1].approx_eq(2.5));
+        assert_eq!(out[2], Float::infinity());
+    }
+
+    #[test]
+    fn area_down() {
+        let sky = Skyline::<Down>::single(0.0, -2.0, 1.0, -2.0);
+        assert!(sky.area(0.0, 1.0, 0.0).approx_eq(2.0));
+        let (_, cy) = sky.centroid(0.0, 1.0, 0.0).unwrap();
+        assert!(cy.approx_eq(-1.0), "cy = {}", cy);
+    }
+
+    #[test]
+    fn distances() {
+        let a = Skyline::<Down>::from_rects(&[Rect::new(0.0, 1.0, 2.0, 5.0)]);
+        let mut b = Skyline::<Down>::single(0.0, 2.0, 2.0, 0.0);
+        assert!(a.distance(&b, Metric::Max).approx_eq(1.0));
+        assert!(a.distance(&b, Metric::Area).approx_eq(1.0));
+        assert_eq!(b.distance(&a, Metric::Area), a.distance(&b, Metric::Area));
+        assert_eq!(a.distance(&a, Metric::Max), 0.0);
+
+        b.merge(&Skyline::single(3.0, 0.0, 4.0, 0.0));
+        assert_eq!(a.distance(&b, Metric::Max), Float::infinity());
+    }
+}
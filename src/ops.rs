@@ -0,0 +1,752 @@
+use std::cmp::Ordering;
+use {Building, Direction, Interval, Skyline};
+use {merge_buildings, merge_many, pieces, single_buildings_capped, support};
+use simplify::simplify_buildings;
+
+// Which way `Skyline::running_max` accumulates.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum Sweep {
+    LeftToRight,
+    RightToLeft
+}
+
+// The envelope of everything within a square of half-width `r` of the
+// building that starts at `start`. Either end can be infinite.
+fn dilate_piece(start: f64, b: &Building, r: f64) -> Vec<Building> {
+    let mut out = Vec::with_capacity(4);
+    if start.is_finite() {
+        out.push(Building::empty(start - r));
+    }
+
+    // A rising building is highest at its right end, so it gets pushed
+    // left and then capped by a flat stretch; a falling one is the mirror
+    // image of that.
+    if b.m > 0.0 {
+        out.push(Building { m: b.m, b: b.b + b.m * r + r, end: b.end - r });
+        if b.end.is_finite() {
+            out.push(Building { m: 0.0, b: b.y(b.end) + r, end: b.end + r });
+        }
+    } else if b.m < 0.0 {
+        if start.is_finite() {
+            out.push(Building { m: 0.0, b: b.y(start) + r, end: start + r });
+        }
+        out.push(Building { m: b.m, b: b.b - b.m * r + r, end: b.end + r });
+    } else {
+        out.push(Building { m: 0.0, b: b.b + r, end: b.end + r });
+    }
+
+    if b.end.is_finite() {
+        out.push(Building::empty(Float::infinity()));
+    }
+    out
+}
+
+impl<T: Direction> Skyline<T> {
+    // Pads the skyline by `r`. The result is the skyline of everything within
+    // an axis-aligned square of half-width `r` of the original, so buildings
+    // are raised by `r`, their ends are extended by `r`, and sloped buildings
+    // are pushed sideways as well. No point ends up closer than `r` to
+    // the original skyline (along a diagonal, the padding can be as much as
+    // `r` times the square root of two).
+    pub fn dilate(&mut self, r: f64) {
+        assert!(r >= 0.0, "can't dilate by a negative amount ({})", r);
+        let mut padded = Vec::with_capacity(self.buildings.len());
+        let mut start: f64 = Float::neg_infinity();
+        for b in self.buildings.iter() {
+            if b.b > Float::neg_infinity() {
+                padded.push(dilate_piece(start, b, r));
+            }
+            start = b.end;
+        }
+        self.buildings = if padded.is_empty() {
+            vec![Building::empty(Float::infinity())]
+        } else {
+            merge_many(padded)
+        };
+        if self.is_bounded() {
+            let d = self.domain;
+            self.clip(d.lo, d.hi);
+        }
+        self.debug_assert_valid();
+    }
+
+    // Cuts the skyline back to `y` between `x1` and `x2`, so that it doesn't
+    // stick out past `y` there (for an upward skyline, this lowers anything
+    // higher than `y`; for a downward one, it raises anything lower). Empty
+    // parts of the skyline stay empty. This is useful for clearing out the
+    // space that an item was using after it's removed, without rebuilding
+    // the skyline from scratch.
+    pub fn lower(&mut self, x1: f64, x2: f64, y: f64) {
+        assert!(x1 <= x2, "empty range ({}, {})", x1, x2);
+        let h = y * Direction::direction_multiplier(None::<T>);
+        let out = lower_buildings(self.buildings.as_slice(), x1, x2, h);
+        self.buildings = out;
+        self.debug_assert_valid();
+    }
+
+    // The highest the skyline has been so far, sweeping in the direction
+    // `sweep` (for a downward skyline, "highest" means furthest down). The
+    // result is monotone: it only ever rises, and it stays at its last
+    // height all the way to the end of the domain. It's empty until the
+    // sweep reaches the first building.
+    pub fn running_max(&self, sweep: Sweep) -> Skyline<T> {
+        let bldgs = match sweep {
+            Sweep::LeftToRight => running_max_buildings(self.buildings.as_slice()),
+            Sweep::RightToLeft => {
+                let reflected = reflect_buildings(self.buildings.as_slice());
+                reflect_buildings(running_max_buildings(reflected.as_slice()).as_slice())
+            },
+        };
+
+        let mut ret = Skyline {
+            buildings: bldgs,
+            domain: self.domain,
+            config: self.config.clone()
+        };
+        if ret.is_bounded() {
+            let d = ret.domain;
+            ret.clip(d.lo, d.hi);
+        }
+        ret
+    }
+
+    // Mirrors the skyline horizontally about the line `x = x0`, domain and
+    // all. Reflecting twice about the same line gives back the original.
+    pub fn reflect_x(&mut self, x0: f64) {
+        let mut out = reflect_buildings(self.buildings.as_slice());
+        for b in out.iter_mut() {
+            b.slide(2.0 * x0);
+        }
+        self.buildings = out;
+        let d = self.domain;
+        self.domain = Interval { lo: 2.0 * x0 - d.hi, hi: 2.0 * x0 - d.lo };
+        self.debug_assert_valid();
+    }
+
+    // Rounds off the corners that stick out: each one is replaced by a
+    // circular arc of radius `radius` that touches the buildings on either
+    // side (or a smaller arc, where those buildings are short), approximated
+    // by segments within `tolerance` of it. The arcs cut off the corners, so
+    // sharp peaks and spikes stick out less. Corners at the ends of the
+    // skyline's pieces, next to empty space, are left alone, and so are
+    // solid parts and the corners next to them.
+    pub fn fillet(&mut self, radius: f64, tolerance: f64) {
+        assert!(radius >= 0.0, "negative radius ({})", radius);
+        assert!(tolerance > 0.0, "tolerance must be positive, not {}", tolerance);
+        let max_slope = self.config.max_slope;
+
+        // The buildings that go on forever aren't touched, and neither are
+        // the solid ones.
+        let mut unbounded = Vec::with_capacity(self.buildings.len());
+        let mut start: f64 = Float::neg_infinity();
+        for b in self.buildings.iter() {
+            if start.is_finite() && b.end.is_finite() && b.b != Float::infinity() {
+                unbounded.push(Building::empty(b.end));
+            } else {
+                unbounded.push(*b);
+            }
+            start = b.end;
+        }
+        let mut edges = vec![unbounded];
+
+        for line in polylines(self.buildings.as_slice()).iter() {
+            let rounded = round_corners(line.as_slice(), radius, tolerance);
+            if rounded.len() == 1 {
+                let (x, y) = rounded[0];
+                edges.push(single_buildings_capped(1.0, max_slope, x, y, x, y));
+            }
+            for w in rounded.as_slice().windows(2) {
+                let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+                edges.push(single_buildings_capped(1.0, max_slope, x1, y1, x2, y2));
+            }
+        }
+
+        self.buildings = merge_many(edges);
+        if self.is_bounded() {
+            let d = self.domain;
+            self.clip(d.lo, d.hi);
+        }
+        self.debug_assert_valid();
+    }
+
+    // The smallest concave skyline that is at least as high as this one:
+    // the upper part of the convex hull of its buildings (for a downward
+    // skyline, the lower part). It spans the gaps between buildings, and it
+    // usually has far fewer buildings, so it's a cheap first test before
+    // checking against the skyline itself. Buildings that go on forever are
+    // kept as they are, and so are solid ones: the hull is taken of
+    // everything else.
+    pub fn hull(&self) -> Skyline<T> {
+        let finite = match support(self.buildings.as_slice()) {
+            Some((lo, hi)) => lo.is_finite() && hi.is_finite(),
+            None => return self.clone(),
+        } && self.buildings.iter().all(|b| b.b != Float::infinity());
+        let points = hull_points(self.buildings.as_slice());
+        if points.is_empty() {
+            return self.clone();
+        }
+
+        let (x0, _) = points[0];
+        let mut bldgs = vec![Building::empty(x0)];
+        if points.len() == 1 {
+            let (x, y) = points[0];
+            bldgs.push(Building { m: 0.0, b: y, end: x });
+        }
+        for w in points.as_slice().windows(2) {
+            let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+            bldgs.push(Building::from_points_capped(x1, y1, x2, y2, self.config.max_slope));
+        }
+        bldgs.push(Building::empty(Float::infinity()));
+
+        if !finite {
+            let mut merged = Vec::with_capacity(bldgs.len() + self.buildings.len());
+            merge_buildings(bldgs.as_slice(), self.buildings.as_slice(), &mut merged);
+            bldgs = merged;
+        }
+        Skyline {
+            buildings: bldgs,
+            domain: self.domain,
+            config: self.config.clone()
+        }
+    }
+}
+
+// The finite, non-empty buildings as polylines through their endpoints. A
+// new polyline starts after every gap (or solid building), and where
+// neighboring buildings meet at different heights there's a vertical
+// segment.
+fn polylines(bldgs: &[Building]) -> Vec<Vec<(f64, f64)>> {
+    let mut lines: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut after_solid = false;
+    for &(start, ref b) in pieces(bldgs).iter() {
+        if b.b == Float::infinity() {
+            after_solid = true;
+            continue;
+        }
+        let p0 = (start, b.y(start));
+        let p1 = (b.end, b.y(b.end));
+        let cont = match lines.last() {
+            Some(line) if !after_solid => {
+                let (x, _) = line[line.len() - 1];
+                x == start
+            },
+            _ => false,
+        };
+        after_solid = false;
+        if !cont {
+            lines.push(Vec::new());
+        }
+
+        let line = lines.last_mut().unwrap();
+        for &p in [p0, p1].iter() {
+            if line.last() != Some(&p) {
+                line.push(p);
+            }
+        }
+    }
+    lines
+}
+
+// Replaces each corner of the polyline that turns clockwise by an arc
+// tangent to both of its edges, made of segments that are within
+// `tolerance` of the arc. The arc has radius `radius` unless that would use
+// more than half of either edge, in which case it's smaller.
+fn round_corners(pts: &[(f64, f64)], radius: f64, tolerance: f64)
+        -> Vec<(f64, f64)> {
+    let n = pts.len();
+    if n < 3 {
+        return pts.to_vec();
+    }
+
+    let mut out = vec![pts[0]];
+    for i in range(1, n - 1) {
+        let (ax, ay) = pts[i - 1];
+        let (bx, by) = pts[i];
+        let (cx, cy) = pts[i + 1];
+        let l1 = (bx - ax).hypot(by - ay);
+        let l2 = (cx - bx).hypot(cy - by);
+        let (ux, uy) = ((bx - ax) / l1, (by - ay) / l1);
+        let (wx, wy) = ((cx - bx) / l2, (cy - by) / l2);
+
+        // The angle that the polyline turns through at this corner.
+        let cross = ux * wy - uy * wx;
+        let turn = (-cross).atan2(ux * wx + uy * wy);
+        let half = (turn / 2.0).tan();
+        let t = (radius * half).min(l1 / 2.0).min(l2 / 2.0);
+        let r = t / half;
+        if cross >= 0.0 || !(r > 0.0) || !r.is_finite() {
+            out.push(pts[i]);
+            continue;
+        }
+
+        // The arc starts where it touches the incoming edge, and its center
+        // is to the right of that edge.
+        let (px, py) = (bx - ux * t, by - uy * t);
+        let (ox, oy) = (px + uy * r, py - ux * r);
+        let a0 = (py - oy).atan2(px - ox);
+        let max_step = if tolerance >= r { turn } else { 2.0 * (1.0 - tolerance / r).acos() };
+        let steps = (turn / max_step).ceil().max(1.0) as uint;
+        for j in range(0, steps + 1) {
+            let a = a0 - turn * j as f64 / steps as f64;
+            out.push((ox + r * a.cos(), oy + r * a.sin()));
+        }
+    }
+    out.push(pts[n - 1]);
+    out
+}
+
+// The vertices of the upper convex hull of the finite buildings, from left
+// to right. Solid buildings are left out.
+fn hull_points(bldgs: &[Building]) -> Vec<(f64, f64)> {
+    let mut pts = Vec::new();
+    for &(start, ref b) in pieces(bldgs).iter() {
+        if b.b == Float::infinity() {
+            continue;
+        }
+        pts.push((start, b.y(start)));
+        pts.push((b.end, b.y(b.end)));
+    }
+    pts.sort_by(|&(x1, y1), &(x2, y2)| {
+        match x1.partial_cmp(&x2) {
+            Some(Ordering::Equal) | None => y1.partial_cmp(&y2).unwrap_or(Ordering::Equal),
+            Some(o) => o,
+        }
+    });
+
+    // Andrew's monotone chain. Of several points with the same x, the
+    // highest one comes last, and it replaces the others.
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(pts.len());
+    for &(px, py) in pts.iter() {
+        loop {
+            let n = hull.len();
+            if n == 0 {
+                break;
+            }
+            let (ax, ay) = hull[n - 1];
+            if ax == px {
+                hull.pop();
+                continue;
+            }
+            if n == 1 {
+                break;
+            }
+            // Drop `a` if it's on or below the line from `o` to `p`.
+            let (ox, oy) = hull[n - 2];
+            if (ax - ox) * (py - oy) - (ay - oy) * (px - ox) >= 0.0 {
+                hull.pop();
+            } else {
+                break;
+            }
+        }
+        hull.push((px, py));
+    }
+    hull
+}
+
+// Pushes a flat building at height `h`, extending the previous one instead
+// if it's flat at the same height. Since empty buildings are flat at
+// negative infinity, this also works for them.
+fn push_flat(out: &mut Vec<Building>, h: f64, end: f64) {
+    let extend = match out.last() {
+        Some(prev) => prev.m == 0.0 && prev.b == h,
+        None => false,
+    };
+    if extend {
+        out.last_mut().unwrap().end = end;
+    } else {
+        out.push(Building { m: 0.0, b: h, end: end });
+    }
+}
+
+fn running_max_buildings(bldgs: &[Building]) -> Vec<Building> {
+    let mut out = Vec::with_capacity(bldgs.len());
+    let mut start: f64 = Float::neg_infinity();
+    let mut h: f64 = Float::neg_infinity();
+
+    for b in bldgs.iter() {
+        if b.b == Float::neg_infinity() || b.m <= 0.0 || b.end == start {
+            // These buildings are highest where they start.
+            h = h.max(b.y(start));
+            push_flat(&mut out, h, b.end);
+        } else if b.y(b.end) <= h {
+            push_flat(&mut out, h, b.end);
+        } else {
+            if b.y(start) < h {
+                // The building climbs past the running maximum part way.
+                push_flat(&mut out, h, (h - b.b) / b.m);
+            }
+            out.push(*b);
+            h = b.y(b.end);
+        }
+        start = b.end;
+    }
+
+    out
+}
+
+// The same buildings, reflected across the y axis.
+fn reflect_buildings(bldgs: &[Building]) -> Vec<Building> {
+    let mut out = Vec::with_capacity(bldgs.len());
+    for i in range(0, bldgs.len()).rev() {
+        let b = &bldgs[i];
+        let end = if i > 0 { -bldgs[i - 1].end } else { Float::infinity() };
+        out.push(Building { m: -b.m, b: b.b, end: end });
+    }
+    out
+}
+
+fn lower_buildings(bldgs: &[Building], x1: f64, x2: f64, h: f64) -> Vec<Building> {
+    let flat = |end: f64| Building { m: 0.0, b: h, end: end };
+    let mut out = Vec::with_capacity(bldgs.len() + 4);
+    let mut start: f64 = Float::neg_infinity();
+
+    for b in bldgs.iter() {
+        if b.b == Float::neg_infinity() || b.end <= x1 || start >= x2 {
+            out.push(*b);
+            start = b.end;
+            continue;
+        }
+
+        if start < x1 {
+            out.push(b.chop(x1));
+        }
+        let lo = start.max(x1);
+        let hi = b.end.min(x2);
+        if lo == hi {
+            out.push(Building { m: 0.0, b: b.y(lo).min(h), end: hi });
+        } else if b.m == 0.0 {
+            out.push(Building { m: 0.0, b: b.b.min(h), end: hi });
+        } else {
+            let y_lo = b.y(lo);
+            let y_hi = b.y(hi);
+            if y_lo <= h && y_hi <= h {
+                out.push(b.chop(hi));
+            } else if y_lo >= h && y_hi >= h {
+                out.push(flat(hi));
+            } else {
+                // The building crosses `h` somewhere in the middle.
+                let x = ((h - b.b) / b.m).max(lo).min(hi);
+                if y_lo < h {
+                    out.push(b.chop(x));
+                    out.push(flat(hi));
+                } else {
+                    out.push(flat(x));
+                    out.push(b.chop(hi));
+                }
+            }
+        }
+        if b.end > x2 {
+            out.push(*b);
+        }
+        start = b.end;
+    }
+
+    out
+}
+
+// The `p`th percentile of `ys`, which can't be empty, interpolating
+// linearly between ranks.
+fn percentile(ys: &mut [f64], p: f64) -> f64 {
+    ys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let rank = p / 100.0 * (ys.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as uint, rank.ceil() as uint);
+    ys[lo] + (ys[hi] - ys[lo]) * (rank - lo as f64)
+}
+
+// Appends the percentile of the non-empty `pieces` between `start` and
+// `end`. Between two crossings of the pieces their order doesn't change,
+// so the percentile is linear there.
+fn push_percentile(pieces: &[Building], start: f64, end: f64, p: f64,
+                   max_slope: f64, out: &mut Vec<Building>) {
+    if pieces.is_empty() {
+        out.push(Building::empty(end));
+        return;
+    }
+    let at = |x: f64| {
+        let mut ys: Vec<f64> = pieces.iter().map(|b| b.y(x)).collect();
+        percentile(ys.as_mut_slice(), p)
+    };
+
+    // On the unbounded pieces at the ends, use the percentile of the slopes.
+    // That's exact if they're flat, which they usually are.
+    if !start.is_finite() || !end.is_finite() {
+        let x0 = if start.is_finite() { start } else if end.is_finite() { end } else { 0.0 };
+        let mut slopes: Vec<f64> = pieces.iter().map(|b| b.m).collect();
+        let m = percentile(slopes.as_mut_slice(), p);
+        out.push(Building { m: m, b: at(x0) - m * x0, end: end });
+        return;
+    }
+
+    let mut cuts = vec![start, end];
+    for i in range(0, pieces.len()) {
+        for j in range(i + 1, pieces.len()) {
+            let x = pieces[i].intersection(&pieces[j]);
+            if x > start && x < end {
+                cuts.push(x);
+            }
+        }
+    }
+    cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    cuts.dedup();
+    for w in cuts.as_slice().windows(2) {
+        let (x1, x2) = (w[0], w[1]);
+        out.push(Building::from_points_capped(x1, at(x1), x2, at(x2), max_slope));
+    }
+}
+
+// The `p`th percentile (between 0 and 100) of the skylines' heights at
+// every x, measured in their direction: for upward skylines, 50 gives the
+// median height and 100 gives the same envelope as `merge_all`, and for
+// downward ones 100 is the lowest. Where some of the skylines are empty,
+// only the others count, so the result is only empty where all of them
+// are. The percentile is computed exactly (up to rounding) and then
+// simplified to within `tolerance`. The domain is the intersection of
+// theirs, and the configuration is that of the first one.
+//
+// This takes time proportional to the square of the number of skylines
+// for each of their breakpoints, so it's meant for aggregating tens or
+// hundreds of profiles, not millions.
+pub fn percentile_envelope<T: Direction>(skylines: &[Skyline<T>], p: f64, tolerance: f64)
+        -> Skyline<T> {
+    assert!(p >= 0.0 && p <= 100.0, "percentile out of range ({})", p);
+    assert!(tolerance >= 0.0, "negative tolerance ({})", tolerance);
+    if skylines.is_empty() {
+        return Skyline::empty();
+    }
+    let config = skylines[0].config.clone();
+
+    let mut xs: Vec<f64> = Vec::new();
+    for sky in skylines.iter() {
+        xs.extend(sky.buildings.iter().map(|b| b.end).filter(|x| x.is_finite()));
+    }
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.dedup();
+    xs.push(Float::infinity());
+
+    let mut cursors = Vec::from_elem(skylines.len(), 0u);
+    let mut bldgs = Vec::new();
+    let mut start: f64 = Float::neg_infinity();
+    for &end in xs.iter() {
+        let mut pieces = Vec::with_capacity(skylines.len());
+        for (k, sky) in skylines.iter().enumerate() {
+            while sky.buildings[cursors[k]].end < end {
+                cursors[k] += 1;
+            }
+            let b = sky.buildings[cursors[k]];
+            if b.b != Float::neg_infinity() {
+                pieces.push(b);
+            }
+        }
+        push_percentile(pieces.as_slice(), start, end, p, config.max_slope, &mut bldgs);
+        start = end;
+    }
+
+    let mut ret = Skyline {
+        buildings: simplify_buildings(bldgs.as_slice(), tolerance, config.max_slope),
+        domain: Interval::unbounded(),
+        config: config
+    };
+    for sky in skylines.iter() {
+        if sky.is_bounded() {
+            ret.bound(sky.domain.lo, sky.domain.hi);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Building, Interval, Skyline, Up, Down};
+    use super::{percentile_envelope, Sweep};
+
+    #[test]
+    fn dilate_flat() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 0.0);
+        sky.dilate(1.0);
+        let probe = Skyline::<Down>::single(-1.0, 0.0, -1.0, 0.0);
+        assert!(sky.overlap(&probe).approx_eq(1.0));
+        let probe = Skyline::<Down>::single(-1.5, 0.0, -1.2, 0.0);
+        assert_eq!(sky.overlap(&probe), Float::neg_infinity());
+    }
+
+    #[test]
+    fn dilate_slopes() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        sky.dilate(0.5);
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(-0.5),
+                 Building { m: 1.0, b: 1.0, end: 1.5 },
+                 Building { m: 0.0, b: 2.5, end: 2.5 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+    }
+
+    #[test]
+    fn dilate_valley() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 1.0, 0.0);
+        sky.merge(&Skyline::single(1.0, 0.0, 2.0, 1.0));
+        sky.dilate(0.5);
+
+        let probe = Skyline::<Down>::single(1.0, 0.0, 1.0, 0.0);
+        let d = sky.overlap(&probe);
+        assert!(d.approx_eq(1.0), "d = {}, should be 1.0", d);
+    }
+
+    #[test]
+    fn dilate_down() {
+        let mut sky = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.0);
+        sky.dilate(1.0);
+        let floor = Skyline::<Up>::single(0.0, -3.0, 1.0, -3.0);
+        assert!(floor.overlap(&sky).approx_eq(-2.0));
+    }
+
+    #[test]
+    fn dilate_unbounded() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(2.0, 0.0, 4.0, 2.0));
+        let mut padded = sky.running_max(Sweep::LeftToRight);
+        padded.dilate(0.5);
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(-0.5),
+                 Building { m: 0.0, b: 1.5, end: 2.5 },
+                 Building { m: 1.0, b: -1.0, end: 3.5 },
+                 Building { m: 0.0, b: 2.5, end: Float::infinity() }));
+        assert!(padded.approx_eq_envelope(&target, 1e-9), "{}", padded);
+
+        // A sloped building that goes on forever moves up and sideways.
+        let mut ramp = Skyline::<Up>::from_buildings(
+            vec!(Building::empty(0.0), Building { m: 1.0, b: 0.0, end: Float::infinity() }));
+        ramp.dilate(1.0);
+        assert!(ramp.height_at(-1.0).approx_eq(1.0));
+        assert!(ramp.height_at(10.0).approx_eq(12.0));
+    }
+
+    #[test]
+    fn lower_slope() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 4.0, 4.0);
+        sky.merge(&Skyline::single(5.0, 1.0, 6.0, 1.0));
+        sky.lower(1.0, 5.5, 2.0);
+
+        let mut target = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        target.merge(&Skyline::single(2.0, 2.0, 4.0, 2.0));
+        target.merge(&Skyline::single(5.0, 1.0, 6.0, 1.0));
+        assert!(sky.approx_eq_envelope(&target, 1e-9), "{}", sky);
+    }
+
+    #[test]
+    fn lower_down() {
+        let mut sky = Skyline::<Down>::single(0.0, -2.0, 2.0, -2.0);
+        sky.lower(1.0, 3.0, -1.0);
+        assert!(sky.height_at(0.5).approx_eq(-2.0));
+        assert!(sky.height_at(1.5).approx_eq(-1.0));
+        assert_eq!(sky.height_at(2.5), Float::infinity());
+    }
+
+    #[test]
+    fn running_max_both_ways() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(2.0, 0.0, 4.0, 2.0));
+        sky.merge(&Skyline::single(5.0, 0.5, 6.0, 0.5));
+
+        let ltr = sky.running_max(Sweep::LeftToRight);
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 0.0, b: 1.0, end: 3.0 },
+                 Building { m: 1.0, b: -2.0, end: 4.0 },
+                 Building { m: 0.0, b: 2.0, end: Float::infinity() }));
+        assert!(ltr.approx_eq(&target), "{}", ltr);
+
+        let rtl = sky.running_max(Sweep::RightToLeft);
+        assert!(rtl.height_at(-5.0).approx_eq(2.0));
+        assert!(rtl.height_at(3.0).approx_eq(2.0));
+        assert!(rtl.height_at(4.5).approx_eq(0.5));
+        assert_eq!(rtl.height_at(6.5), Float::neg_infinity());
+    }
+
+    #[test]
+    fn running_max_bounded() {
+        let mut sky = Skyline::<Down>::single(1.0, 2.0, 2.0, 1.0);
+        sky.bound(0.0, 3.0);
+        let ltr = sky.running_max(Sweep::LeftToRight);
+        assert_eq!(ltr.validate(), Ok(()));
+        assert!(ltr.height_at(2.5).approx_eq(1.0));
+        assert_eq!(ltr.height_at(0.5), Float::infinity());
+    }
+
+    #[test]
+    fn hull_fills_valleys() {
+        let mut sky = Skyline::<Up>::single(0.0, 2.0, 2.0, 0.0);
+        sky.merge(&Skyline::single(2.0, 0.0, 4.0, 2.0));
+        sky.merge(&Skyline::single(5.0, 1.0, 6.0, 1.0));
+        let hull = sky.hull();
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 0.0, b: 2.0, end: 4.0 },
+                 Building { m: -0.5, b: 4.0, end: 6.0 },
+                 Building::empty(Float::infinity())));
+        assert!(hull.approx_eq(&target), "{}", hull);
+
+        // A tent is already concave.
+        let mut tent = Skyline::<Down>::single(0.0, 0.0, 1.0, -1.0);
+        tent.merge(&Skyline::single(1.0, -1.0, 3.0, 0.0));
+        assert!(tent.hull().approx_eq_envelope(&tent, 1e-9));
+    }
+
+    #[test]
+    fn reflect_x() {
+        let mut sky = Skyline::<Up>::bounded(-1.0, 5.0);
+        sky.merge(&Skyline::single(0.0, 0.0, 2.0, 2.0));
+        sky.merge(&Skyline::single(3.0, 1.0, 4.0, 1.0));
+        let orig = sky.clone();
+        sky.reflect_x(1.5);
+
+        assert_eq!(sky.domain(), Interval::new(-2.0, 4.0));
+        for i in range(0u, 30) {
+            let x = -0.9 + i as f64 * 0.2;
+            assert!(sky.height_at(3.0 - x).approx_eq(orig.height_at(x)), "at {}", x);
+        }
+        sky.reflect_x(1.5);
+        assert!(sky.approx_eq(&orig), "{}", sky);
+    }
+
+    #[test]
+    fn percentiles() {
+        let skylines = vec![Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0),
+                            Skyline::single(0.0, 1.0, 2.0, 1.0),
+                            Skyline::single(1.0, 3.0, 3.0, 3.0)];
+        let median = percentile_envelope(skylines.as_slice(), 50.0, 0.0);
+        assert!(median.height_at(0.5).approx_eq(0.75));
+        // Above x = 1, the median is the middle of three.
+        assert!(median.height_at(1.25).approx_eq(1.25));
+        assert!(median.height_at(1.75).approx_eq(1.75));
+        assert!(median.height_at(2.5).approx_eq(3.0));
+        assert_eq!(median.height_at(4.0), Float::neg_infinity());
+
+        let top = percentile_envelope(skylines.as_slice(), 100.0, 0.0);
+        assert!(top.approx_eq_envelope(&Skyline::merge_all(skylines.clone()), 1e-9));
+        let bottom = percentile_envelope(skylines.as_slice(), 0.0, 0.0);
+        assert!(bottom.height_at(1.5).approx_eq(1.0));
+        assert!(bottom.height_at(0.5).approx_eq(0.5));
+    }
+
+    #[test]
+    fn fillet_box() {
+        let mut sky = Skyline::<Up>::single(-2.0, 0.0, 6.0, 0.0);
+        sky.merge(&Skyline::single(0.0, 2.0, 4.0, 2.0));
+        let orig = sky.clone();
+        sky.fillet(1.0, 0.01);
+
+        // The top corners become quarter circles around (1, 1) and (3, 1).
+        let arc = 1.0 + (0.75 as f64).sqrt();
+        assert!((sky.height_at(0.5) - arc).abs() <= 0.01, "{}", sky.height_at(0.5));
+        assert!((sky.height_at(3.5) - arc).abs() <= 0.01, "{}", sky.height_at(3.5));
+        assert!(sky.height_at(2.0).approx_eq(2.0));
+        assert!(sky.height_at(-1.0).approx_eq(0.0));
+        for i in range(0u, 40) {
+            let x = -1.9 + i as f64 * 0.2;
+            assert!(sky.height_at(x) <= orig.height_at(x) + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use {Dir, DirSkyline, Down, Skyline, Up};
+
+// A skyline whose heights are measured along the unit vector
+// (cos theta, sin theta) instead of along an axis, so that things can be
+// pushed together diagonally. `theta` is in radians; pi / 2 is the same as
+// `Up`, and 0 is the same as `Right`.
+//
+// Since the angle is only known at run time, directions are checked the way
+// `DirSkyline` checks them: the skyline at `theta + pi` is the flip of the
+// one at `theta`, and overlapping or merging anything else panics. Both of
+// them are stored in the same frame (the one for their angle mod pi), as an
+// upward and a downward skyline, so their overlap is an ordinary one.
+#[deriving(Clone, Show)]
+pub struct OrientedSkyline {
+    // In [0, 2 pi).
+    theta: f64,
+    sky: DirSkyline
+}
+
+// Reduces an angle to [0, 2 pi).
+fn normalize(theta: f64) -> f64 {
+    let pi: f64 = Float::pi();
+    let t = theta % (2.0 * pi);
+    let t = if t < 0.0 { t + 2.0 * pi } else { t };
+    if t >= 2.0 * pi { 0.0 } else { t }
+}
+
+// The frame for the pair of directions `theta` and `theta + pi`: the new y
+// axis points along `theta` (or away from it) and the new x axis is
+// perpendicular to it. Returns the frame's angle and the direction that
+// `theta` points in it.
+fn frame(theta: f64) -> (f64, Dir) {
+    let pi: f64 = Float::pi();
+    if theta < pi { (theta, Dir::Up) } else { (theta - pi, Dir::Down) }
+}
+
+fn to_frame(phi: f64, x: f64, y: f64) -> (f64, f64) {
+    let (s, c) = phi.sin_cos();
+    (x * s - y * c, x * c + y * s)
+}
+
+impl OrientedSkyline {
+    pub fn empty(theta: f64) -> OrientedSkyline {
+        let theta = normalize(theta);
+        let (_, dir) = frame(theta);
+        OrientedSkyline {
+            theta: theta,
+            sky: DirSkyline::empty(dir)
+        }
+    }
+
+    pub fn single(theta: f64, x1: f64, y1: f64, x2: f64, y2: f64)
+            -> OrientedSkyline {
+        let theta = normalize(theta);
+        let (phi, dir) = frame(theta);
+        let (u1, v1) = to_frame(phi, x1, y1);
+        let (u2, v2) = to_frame(phi, x2, y2);
+        OrientedSkyline {
+            theta: theta,
+            sky: DirSkyline::single(dir, u1, v1, u2, v2)
+        }
+    }
+
+    // As for `Skyline::from_polygon`.
+    pub fn from_polygon(theta: f64, points: &[(f64, f64)]) -> OrientedSkyline {
+        let theta = normalize(theta);
+        let (phi, dir) = frame(theta);
+        let rotated: Vec<(f64, f64)> =
+            points.iter().map(|&(x, y)| to_frame(phi, x, y)).collect();
+        let sky = match dir {
+            Dir::Up => DirSkyline::from_skyline(&Skyline::<Up>::from_polygon(rotated.as_slice())),
+            _ => DirSkyline::from_skyline(&Skyline::<Down>::from_polygon(rotated.as_slice())),
+        };
+        OrientedSkyline {
+            theta: theta,
+            sky: sky
+        }
+    }
+
+    // The angle, reduced to [0, 2 pi).
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+
+    fn same_frame(&self, other: &OrientedSkyline) -> bool {
+        let (phi1, _) = frame(self.theta);
+        let (phi2, _) = frame(other.theta);
+        (phi1 - phi2).abs() <= 1e-9
+    }
+
+    // How far `other` must move along this skyline's direction so that the
+    // two don't overlap, as for `Skyline::overlap`. The angles must differ
+    // by pi (up to rounding).
+    pub fn overlap(&self, other: &OrientedSkyline) -> f64 {
+        assert!(self.same_frame(other),
+                "can't overlap skylines at angles {} and {}", self.theta, other.theta);
+        self.sky.overlap(&other.sky)
+    }
+
+    // The angles must be the same (up to rounding).
+    pub fn merge(&mut self, other: &OrientedSkyline) {
+        assert!(self.same_frame(other),
+                "can't merge skylines at angles {} and {}", self.theta, other.theta);
+        self.sky.merge(&other.sky);
+    }
+
+    // Moves the skyline by `d` along its direction.
+    pub fn bump(&mut self, d: f64) {
+        let (_, dir) = frame(self.theta);
+        self.sky.bump(d * dir.multiplier());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Shape, Skyline, Up, Down};
+    use super::OrientedSkyline;
+
+    #[test]
+    fn diagonal_overlap() {
+        let pi: f64 = Float::pi();
+        let a = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let c = [(2.0, 2.0), (3.0, 2.0), (3.0, 3.0), (2.0, 3.0)];
+        let front = OrientedSkyline::from_polygon(pi / 4.0, &a);
+        let back = OrientedSkyline::from_polygon(pi / 4.0 - pi, &c);
+        assert!(back.theta().approx_eq(1.25 * pi));
+
+        let shape_a = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let shape_c = Shape::from_rect(&Rect::new(2.0, 2.0, 3.0, 3.0));
+        let d = front.overlap(&back);
+        assert!(d.approx_eq(-shape_a.clearance_along(&shape_c, (1.0, 1.0))), "d = {}", d);
+    }
+
+    #[test]
+    fn axis_angles_match_typed() {
+        let pi: f64 = Float::pi();
+        let up = OrientedSkyline::single(pi / 2.0, -1.0, 3.0, 1.0, 3.0);
+        let mut down = OrientedSkyline::single(-pi / 2.0, -1.0, 2.0, 1.0, 2.0);
+        let typed = Skyline::<Up>::single(-1.0, 3.0, 1.0, 3.0)
+            .overlap(&Skyline::<Down>::single(-1.0, 2.0, 1.0, 2.0));
+        assert!(up.overlap(&down).approx_eq(typed));
+
+        down.bump(-0.5);
+        assert!(up.overlap(&down).approx_eq(typed - 0.5));
+    }
+
+    #[test]
+    #[should_fail]
+    fn mismatched_angles() {
+        let a = OrientedSkyline::single(0.0, 0.0, 0.0, 0.0, 1.0);
+        let b = OrientedSkyline::single(1.0, 0.0, 0.0, 0.0, 1.0);
+        a.overlap(&b);
+    }
+}
@@ -0,0 +1,170 @@
+use {Direction, Skyline};
+use {frame_point, merge_many, single_buildings};
+
+// One step of an outline, in the style of SVG and PostScript paths.
+#[deriving(Clone, PartialEq, Show)]
+pub enum PathCommand {
+    // Starts a new sub-path at (x, y).
+    MoveTo(f64, f64),
+    // A straight line to (x, y).
+    LineTo(f64, f64),
+    // A quadratic Bézier curve with control point (cx, cy), ending at (x, y).
+    QuadTo(f64, f64, f64, f64),
+    // A cubic Bézier curve with control points (c1x, c1y) and (c2x, c2y),
+    // ending at (x, y).
+    CubicTo(f64, f64, f64, f64, f64, f64),
+    // A straight line back to the start of the sub-path.
+    Close
+}
+
+type Point = (f64, f64);
+
+// Curves are split in half until they're flat enough, but never more than
+// this many times (giving at most 2^16 lines per curve).
+static MAX_DEPTH: uint = 16;
+
+fn mid(a: Point, b: Point) -> Point {
+    let (ax, ay) = a;
+    let (bx, by) = b;
+    ((ax + bx) / 2.0, (ay + by) / 2.0)
+}
+
+// The length of a - 2b + c, which bounds how far a curve strays from its
+// chord.
+fn second_difference(a: Point, b: Point, c: Point) -> f64 {
+    let (ax, ay) = a;
+    let (bx, by) = b;
+    let (cx, cy) = c;
+    (ax - 2.0 * bx + cx).hypot(ay - 2.0 * by + cy)
+}
+
+// Appends points along the curve (not including p0) so that the lines
+// between them are within `tol` of it.
+fn flatten_quad(p0: Point, p1: Point, p2: Point, tol: f64, depth: uint,
+                out: &mut Vec<Point>) {
+    if depth >= MAX_DEPTH || second_difference(p0, p1, p2) / 4.0 <= tol {
+        out.push(p2);
+    } else {
+        let a = mid(p0, p1);
+        let b = mid(p1, p2);
+        let m = mid(a, b);
+        flatten_quad(p0, a, m, tol, depth + 1, out);
+        flatten_quad(m, b, p2, tol, depth + 1, out);
+    }
+}
+
+fn flatten_cubic(p0: Point, p1: Point, p2: Point, p3: Point, tol: f64, depth: uint,
+                 out: &mut Vec<Point>) {
+    let flatness = second_difference(p0, p1, p2).max(second_difference(p1, p2, p3)) * 0.75;
+    if depth >= MAX_DEPTH || flatness <= tol {
+        out.push(p3);
+    } else {
+        let a = mid(p0, p1);
+        let b = mid(p1, p2);
+        let c = mid(p2, p3);
+        let ab = mid(a, b);
+        let bc = mid(b, c);
+        let m = mid(ab, bc);
+        flatten_cubic(p0, a, ab, m, tol, depth + 1, out);
+        flatten_cubic(m, bc, c, p3, tol, depth + 1, out);
+    }
+}
+
+// Turns a path into a list of line segments, each given by its endpoints.
+fn flatten(cmds: &[PathCommand], tol: f64) -> Vec<(Point, Point)> {
+    let mut lines = Vec::new();
+    let mut start: Point = (0.0, 0.0);
+    let mut cur: Point = (0.0, 0.0);
+    let mut pts = Vec::new();
+
+    for cmd in cmds.iter() {
+        pts.clear();
+        match *cmd {
+            PathCommand::MoveTo(x, y) => {
+                start = (x, y);
+                cur = start;
+            },
+            PathCommand::LineTo(x, y) => pts.push((x, y)),
+            PathCommand::QuadTo(cx, cy, x, y) =>
+                flatten_quad(cur, (cx, cy), (x, y), tol, 0, &mut pts),
+            PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) =>
+                flatten_cubic(cur, (c1x, c1y), (c2x, c2y), (x, y), tol, 0, &mut pts),
+            PathCommand::Close => pts.push(start),
+        }
+
+        for &p in pts.iter() {
+            lines.push((cur, p));
+            cur = p;
+        }
+    }
+    lines
+}
+
+impl<T: Direction> Skyline<T> {
+    // The skyline of an outline made of lines and Bézier curves. Curves are
+    // replaced by lines that are within `tolerance` of them, so the
+    // resulting skyline is too.
+    //
+    // Every part of the path contributes, whether or not its sub-paths are
+    // closed and whichever way they wind. For a filled shape that gives the
+    // same envelope as the shape itself, since the outside of a shape is
+    // always on its boundary.
+    pub fn from_path(cmds: &[PathCommand], tolerance: f64) -> Skyline<T> {
+        assert!(tolerance > 0.0, "tolerance must be positive, not {}", tolerance);
+        let mult = Direction::direction_multiplier(None::<T>);
+        let lines = flatten(cmds, tolerance).iter().map(|&((x1, y1), (x2, y2))| {
+            let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+            single_buildings(mult, u1, v1, u2, v2)
+        }).collect();
+        Skyline::from_buildings(merge_many(lines))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Skyline, Up, Down};
+    use super::PathCommand;
+    use super::PathCommand::{MoveTo, LineTo, QuadTo, CubicTo, Close};
+
+    #[test]
+    fn path_quad() {
+        // The top of this curve is at (1, 1).
+        let path = [MoveTo(0.0, 0.0), QuadTo(1.0, 2.0, 2.0, 0.0), Close];
+        let top = Skyline::<Up>::from_path(&path, 0.001);
+        assert!((top.height_at(1.0) - 1.0).abs() <= 0.001);
+        assert!((top.height_at(0.5) - 0.75).abs() <= 0.001);
+
+        let bottom = Skyline::<Down>::from_path(&path, 0.001);
+        assert!(bottom.height_at(1.0).approx_eq(0.0));
+    }
+
+    #[test]
+    fn path_circle() {
+        // A unit circle, as four cubics.
+        let k = 0.5522847498;
+        let path: Vec<PathCommand> = vec![
+            MoveTo(1.0, 0.0),
+            CubicTo(1.0, k, k, 1.0, 0.0, 1.0),
+            CubicTo(-k, 1.0, -1.0, k, -1.0, 0.0),
+            CubicTo(-1.0, -k, -k, -1.0, 0.0, -1.0),
+            CubicTo(k, -1.0, 1.0, -k, 1.0, 0.0),
+            Close];
+        let top = Skyline::<Up>::from_path(path.as_slice(), 0.0001);
+        for i in range(0u, 9) {
+            let x = i as f64 * 0.2 - 0.8;
+            let expected = (1.0 - x * x).sqrt();
+            // The cubics are themselves only within about 0.0003 of the
+            // circle.
+            assert!((top.height_at(x) - expected).abs() <= 0.001,
+                    "at {}: {} != {}", x, top.height_at(x), expected);
+        }
+    }
+
+    #[test]
+    fn path_lines() {
+        let path = [MoveTo(0.0, 0.0), LineTo(2.0, 0.0), LineTo(2.0, 1.0), LineTo(0.0, 1.0), Close];
+        let top = Skyline::<Up>::from_path(&path, 0.1);
+        assert!(top.approx_eq_envelope(&Skyline::single(0.0, 1.0, 2.0, 1.0), 1e-9));
+    }
+}
@@ -0,0 +1,128 @@
+use {Direction, Flip, Placement, Skyline};
+use support;
+
+// A skyline on a circle instead of a line, for packing things onto a
+// cylinder (like a texture that tiles horizontally). Positions are taken
+// modulo the period, so anything that crosses the seam at x = 0 (which is
+// the same place as x = period) wraps around to the other side.
+#[deriving(Clone, Show)]
+pub struct PeriodicSkyline<T: Direction> {
+    period: f64,
+    // One period's worth of skyline, over [0, period]. It's empty
+    // everywhere else.
+    base: Skyline<T>
+}
+
+impl<T: Direction + Clone> PeriodicSkyline<T> {
+    pub fn new(period: f64) -> PeriodicSkyline<T> {
+        assert!(period > 0.0 && period.is_finite(), "invalid period {}", period);
+        PeriodicSkyline {
+            period: period,
+            base: Skyline::empty()
+        }
+    }
+
+    pub fn period(&self) -> f64 {
+        self.period
+    }
+
+    // The range of copies of [0, period] that meet [lo, hi].
+    fn periods(&self, lo: f64, hi: f64) -> (int, int) {
+        ((lo / self.period).floor() as int, (hi / self.period).ceil() as int)
+    }
+
+    // The skyline you'd get by laying copies of this one end to end, over
+    // [lo, hi].
+    pub fn unrolled(&self, lo: f64, hi: f64) -> Skyline<T> {
+        assert!(lo.is_finite() && hi.is_finite(), "can't unroll over ({}, {})", lo, hi);
+        let (k0, k1) = self.periods(lo, hi);
+        let copies = range(k0, k1.max(k0 + 1)).map(|k| {
+            let mut copy = self.base.clone();
+            copy.slide(k as f64 * self.period);
+            copy
+        }).collect();
+        let mut ret = Skyline::merge_all(copies);
+        ret.clip(lo, hi);
+        ret
+    }
+
+    // Wraps `other` around the circle and merges it in. `other` must be
+    // empty outside of some finite interval.
+    pub fn merge(&mut self, other: &Skyline<T>) {
+        let (lo, hi) = match support(other.buildings.as_slice()) {
+            Some(s) => s,
+            None => return,
+        };
+        assert!(lo.is_finite() && hi.is_finite(), "can't wrap an infinite skyline");
+
+        let (k0, k1) = self.periods(lo, hi);
+        for k in range(k0, k1.max(k0 + 1)) {
+            let offset = k as f64 * self.period;
+            let mut piece = other.clone();
+            piece.clip(offset, offset + self.period);
+            piece.slide(-offset);
+            self.base.merge(&piece);
+        }
+    }
+
+    // The overlap with `other` (which isn't periodic), taking into account
+    // every copy of this skyline that `other` meets.
+    pub fn overlap<S: Flip<T>>(&self, other: &Skyline<S>) -> f64 {
+        match support(other.buildings.as_slice()) {
+            Some((lo, hi)) => self.unrolled(lo, hi).overlap(other),
+            None => Float::neg_infinity(),
+        }
+    }
+
+    // Like `Skyline::fit`, but the item may go anywhere around the circle,
+    // including across the seam. The returned `dx` is in [0, period).
+    pub fn fit<S: Flip<T>>(&self, item: &Skyline<S>) -> Placement {
+        let (lo, hi) = match support(item.buildings.as_slice()) {
+            Some(s) => s,
+            None => (0.0, 0.0),
+        };
+        let mut p = self.unrolled(lo, hi + self.period).fit(item, (0.0, self.period));
+        if p.dx >= self.period {
+            p.dx -= self.period;
+        }
+        p
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Skyline, Up, Down};
+    use super::PeriodicSkyline;
+
+    #[test]
+    fn wraps_at_seam() {
+        let mut sky = PeriodicSkyline::<Up>::new(10.0);
+        sky.merge(&Skyline::from_rects(&[Rect::new(8.0, 0.0, 12.0, 1.0)]));
+
+        let probe = Skyline::<Down>::single(1.0, 0.0, 1.5, 0.0);
+        assert!(sky.overlap(&probe).approx_eq(1.0));
+        let probe = Skyline::<Down>::single(-1.0, 0.0, -0.5, 0.0);
+        assert!(sky.overlap(&probe).approx_eq(1.0));
+        let probe = Skyline::<Down>::single(3.0, 0.0, 4.0, 0.0);
+        assert_eq!(sky.overlap(&probe), Float::neg_infinity());
+
+        let unrolled = sky.unrolled(-5.0, 25.0);
+        assert!(unrolled.height_at(19.0).approx_eq(1.0));
+        assert_eq!(unrolled.height_at(15.0), Float::neg_infinity());
+    }
+
+    #[test]
+    fn fit_across_seam() {
+        let mut sky = PeriodicSkyline::<Up>::new(10.0);
+        sky.merge(&Skyline::single(0.0, 0.0, 10.0, 0.0));
+        sky.merge(&Skyline::from_rects(&[Rect::new(1.0, 0.0, 9.0, 5.0)]));
+
+        // The only low spot is [9, 11], which wraps around to [9, 10] and
+        // [0, 1].
+        let item = Skyline::<Down>::single(0.0, 0.0, 2.0, 0.0);
+        let p = sky.fit(&item);
+        assert!(p.dx.approx_eq(9.0), "dx = {}", p.dx);
+        assert!(p.dy.approx_eq(0.0), "dy = {}", p.dy);
+    }
+}
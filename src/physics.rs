@@ -0,0 +1,159 @@
+use {Down, Rect, Shape, Skyline, Up};
+
+// A shape moving with constant velocity (in units per unit of time).
+#[deriving(Clone, Show)]
+pub struct Body {
+    pub shape: Shape,
+    pub velocity: (f64, f64)
+}
+
+// Two bodies that may touch at `time`, given as indices into the slice
+// passed to `impacts`.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Impact {
+    pub first: uint,
+    pub second: uint,
+    pub time: f64
+}
+
+// Where a body can be during the next `dt`.
+fn swept_bounds(b: &Body, dt: f64) -> Rect {
+    let r = b.shape.bounds();
+    let (vx, vy) = b.velocity;
+    r.union(&Rect::new(r.x0 + vx * dt, r.y0 + vy * dt, r.x1 + vx * dt, r.y1 + vy * dt))
+}
+
+// The upward and downward skylines of a shape, with the axes swapped if
+// `swap` is true.
+fn envelopes(shape: &Shape, swap: bool) -> (Skyline<Up>, Skyline<Down>) {
+    let points: Vec<(f64, f64)> = if swap {
+        shape.points().iter().map(|&(x, y)| (y, x)).collect()
+    } else {
+        shape.points().to_vec()
+    };
+    (Skyline::from_polygon(points.as_slice()), Skyline::from_polygon(points.as_slice()))
+}
+
+// A lower bound on when `b` first touches `a`, if it does so within `dt`.
+// Only the dominant axis of the relative velocity is used for the gap; the
+// drift along the other axis is accounted for by widening `a`'s skyline
+// by as far as `b` can drift sideways in `dt`, so the time is never later
+// than the true one (but the bodies may turn out not to touch at all).
+fn time_of_impact(a: &Body, b: &Body, dt: f64) -> Option<f64> {
+    let ((ax, ay), (bx, by)) = (a.velocity, b.velocity);
+    let (rx, ry) = (bx - ax, by - ay);
+    let swap = rx.abs() > ry.abs();
+    let (along, across) = if swap { (rx, ry) } else { (ry, rx) };
+    if along == 0.0 {
+        return None;
+    }
+
+    // `b` moves towards smaller heights relative to `a`, or the other way
+    // around; either way the one in front is the floor.
+    let (floor, lid) = if along < 0.0 { (a, b) } else { (b, a) };
+    let (mut floor, floor_back) = envelopes(&floor.shape, swap);
+    let (lid_back, lid) = envelopes(&lid.shape, swap);
+    let drift = across.abs() * dt;
+    if drift > 0.0 {
+        // Dilating pads vertically too, which the bump takes back off.
+        floor.dilate(drift);
+        floor.bump(-drift);
+    }
+
+    // Skylines count everything behind them as in the way, so a lid that is
+    // entirely behind the floor looks like it's overlapping. It's actually
+    // moving away.
+    let gap = -floor.overlap(&lid);
+    if gap < 0.0 && lid_back.overlap(&floor_back) < 0.0 {
+        return None;
+    }
+    let t = gap.max(0.0) / along.abs();
+    if t <= dt { Some(t) } else { None }
+}
+
+// The pairs of bodies that might collide in the next `dt`, with a
+// conservative estimate of when, sorted by time. This is meant as a
+// broad phase for simple simulations (falling blocks, labels settling into
+// place): it never reports a collision later than it happens, but it can
+// report pairs that end up missing each other.
+pub fn impacts(bodies: &[Body], dt: f64) -> Vec<Impact> {
+    assert!(dt >= 0.0, "negative time step ({})", dt);
+    let bounds: Vec<Rect> = bodies.iter().map(|b| swept_bounds(b, dt)).collect();
+    let mut out = Vec::new();
+    for i in range(0, bodies.len()) {
+        for j in range(i + 1, bodies.len()) {
+            if bounds[i].intersection(&bounds[j]).is_none() {
+                continue;
+            }
+            match time_of_impact(&bodies[i], &bodies[j], dt) {
+                Some(t) => out.push(Impact { first: i, second: j, time: t }),
+                None => {},
+            }
+        }
+    }
+    out.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Shape};
+    use super::{impacts, time_of_impact, Body};
+
+    #[test]
+    fn falling_blocks() {
+        let ground = Body {
+            shape: Shape::from_rect(&Rect::new(0.0, -1.0, 10.0, 0.0)),
+            velocity: (0.0, 0.0)
+        };
+        let near = Body {
+            shape: Shape::from_rect(&Rect::new(1.0, 2.0, 2.0, 3.0)),
+            velocity: (0.0, -1.0)
+        };
+        let far = Body {
+            shape: Shape::from_rect(&Rect::new(5.0, 6.0, 6.0, 7.0)),
+            velocity: (0.5, -2.0)
+        };
+        let away = Body {
+            shape: Shape::from_rect(&Rect::new(12.0, 1.0, 13.0, 2.0)),
+            velocity: (0.0, 1.0)
+        };
+
+        let hits = impacts(&[ground, near, far, away], 5.0);
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].first, hits[0].second), (0, 1));
+        assert!(hits[0].time.approx_eq(2.0));
+        assert_eq!((hits[1].first, hits[1].second), (0, 2));
+        assert!(hits[1].time.approx_eq(3.0));
+    }
+
+    #[test]
+    fn sideways() {
+        let wall = Body {
+            shape: Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 10.0)),
+            velocity: (0.0, 0.0)
+        };
+        let puck = Body {
+            shape: Shape::from_rect(&Rect::new(4.0, 4.0, 5.0, 5.0)),
+            velocity: (-1.5, 0.0)
+        };
+        let hits = impacts(&[wall, puck], 10.0);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].time.approx_eq(2.0));
+    }
+
+    #[test]
+    fn receding() {
+        let low = Body {
+            shape: Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0)),
+            velocity: (0.0, 0.0)
+        };
+        let high = Body {
+            shape: Shape::from_rect(&Rect::new(0.0, 2.0, 1.0, 3.0)),
+            velocity: (0.1, 1.0)
+        };
+        assert_eq!(time_of_impact(&low, &high, 10.0), None);
+        assert_eq!(time_of_impact(&high, &low, 10.0), None);
+    }
+}
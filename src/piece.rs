@@ -0,0 +1,114 @@
+use Building;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// What kind of curve a piece of a skyline follows. Every piece is linear
+// for now, but constant pieces are common enough (anything built from
+// rectangles) that it pays to notice them, and they are the place to add
+// exact steps and curved pieces later. Solid pieces (from
+// `Skyline::solid`) are flat at positive infinity.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum Kind {
+    Empty,
+    Solid,
+    Constant,
+    Linear
+}
+
+// The interface that the sweeps in merge, overlap and segments use to look
+// at a piece of a skyline. A piece covers the x coordinates from the end of
+// the previous piece up to and including its own end.
+pub trait Piece: Copy {
+    fn kind(&self) -> Kind;
+
+    fn end(&self) -> f64;
+
+    // The same piece, but ending at `end` instead.
+    fn chop(&self, end: f64) -> Self;
+
+    fn y_at(&self, x: f64) -> f64;
+
+    // The x coordinate where the two pieces cross, ignoring their extents.
+    // If they don't cross, this is either infinity or negative infinity.
+    fn intersection_with(&self, other: &Self) -> f64;
+
+    // The largest height of this piece on the interval [x0, x1].
+    fn max_over(&self, x0: f64, x1: f64) -> f64;
+
+    // Returns true if this piece is at least as high as `other` immediately
+    // to the right of `x`. `intersect` is `self.intersection_with(other)`,
+    // which the caller usually needs anyway.
+    fn conceals(&self, other: &Self, x: f64, intersect: f64) -> bool;
+
+    fn is_empty(&self) -> bool {
+        self.kind() == Kind::Empty
+    }
+
+    fn is_solid(&self) -> bool {
+        self.kind() == Kind::Solid
+    }
+}
+
+impl Piece for Building {
+    fn kind(&self) -> Kind {
+        if self.b == Float::neg_infinity() {
+            Kind::Empty
+        } else if self.b == Float::infinity() {
+            Kind::Solid
+        } else if self.m == 0.0 {
+            Kind::Constant
+        } else {
+            Kind::Linear
+        }
+    }
+
+    fn end(&self) -> f64 {
+        self.end
+    }
+
+    fn chop(&self, end: f64) -> Building {
+        Building { m: self.m, b: self.b, end: end }
+    }
+
+    fn y_at(&self, x: f64) -> f64 {
+        self.y(x)
+    }
+
+    fn intersection_with(&self, other: &Building) -> f64 {
+        self.intersection(other)
+    }
+
+    fn max_over(&self, x0: f64, x1: f64) -> f64 {
+        match self.kind() {
+            Kind::Empty | Kind::Solid | Kind::Constant => self.b,
+            // A line is highest at one of its ends.
+            Kind::Linear => self.y(x0).max(self.y(x1)),
+        }
+    }
+
+    fn conceals(&self, other: &Building, x: f64, intersect: f64) -> bool {
+        self.conceals_with_intersect(other, x, intersect)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use Building;
+    use super::{Kind, Piece};
+
+    #[test]
+    fn kinds() {
+        let flat = Building::from_points(0.0, 1.0, 2.0, 1.0);
+        let sloped = Building::from_points(0.0, 0.0, 2.0, 1.0);
+        assert_eq!(flat.kind(), Kind::Constant);
+        assert_eq!(sloped.kind(), Kind::Linear);
+        assert!(Building::empty(1.0).is_empty());
+        assert_eq!(Building::solid(1.0).kind(), Kind::Solid);
+
+        assert!(flat.max_over(-5.0, 5.0).approx_eq(1.0));
+        assert!(sloped.max_over(0.0, 1.0).approx_eq(0.5));
+        assert!(sloped.intersection_with(&flat).approx_eq(2.0));
+        assert!(sloped.chop(5.0).y_at(4.0).approx_eq(2.0));
+    }
+}
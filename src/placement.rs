@@ -0,0 +1,618 @@
+use std::collections::HashMap;
+use {Building, Direction, Flip, Interval, Shape, Skyline, SkylineKey, Up};
+use {clip_buildings, merge_many, pieces, single_buildings, solid_buildings, support};
+
+// Where to put an item so that it touches a skyline: slide the item by `dx`
+// and then bump it by `dy`. If nothing stops the item, `dy` is infinite
+// (pointing away from the skyline); if the item doesn't fit in the skyline's
+// domain anywhere in the allowed range, `dy` is infinite the other way.
+#[cfg_attr(feature = "serialize", deriving(Encodable, Decodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub struct Placement {
+    pub dx: f64,
+    pub dy: f64
+}
+
+impl Placement {
+    // The shape moved to this placement. The move goes into the shape's
+    // history, so the layout can be rebuilt exactly from the original
+    // outlines.
+    pub fn apply(&self, shape: &Shape) -> Shape {
+        shape.translate(self.dx, self.dy)
+    }
+}
+
+// The height of a building at `x`, leaving out solid ones, whose ends don't
+// block anything.
+fn finite_y(b: &Building, x: f64) -> f64 {
+    if b.b == Float::infinity() { Float::neg_infinity() } else { b.y(x) }
+}
+
+// The breakpoints of a list of buildings, together with the height there.
+// Where two buildings meet at different heights, we take the higher one.
+fn vertices(bldgs: &[Building]) -> Vec<(f64, f64)> {
+    let mut out = Vec::new();
+    for i in range(1, bldgs.len()) {
+        let x = bldgs[i - 1].end;
+        let h = finite_y(&bldgs[i - 1], x).max(finite_y(&bldgs[i], x));
+        if h > Float::neg_infinity() {
+            out.push((x, h));
+        }
+    }
+    out
+}
+
+// Splits the finite pieces of a list of buildings into the solid ones and
+// the rest.
+fn split_solid(bldgs: &[Building]) -> (Vec<(f64, Building)>, Vec<(f64, Building)>) {
+    pieces(bldgs).partition(|&(_, ref b)| b.b == Float::infinity())
+}
+
+// The overlap between `a` and `b` as a function of how far `b` is slid:
+// the result's height at `dx` is the maximum over x of a(x) + b(x - dx).
+//
+// For any fixed `dx` that maximum is attained at a vertex of one of the
+// skylines, and as `dx` varies a given vertex stays on a given piece of the
+// other skyline for an interval of offsets, over which the sum is linear in
+// `dx`. So the overlap is the upper envelope of those O(nm) segments.
+//
+// Solid pieces don't have vertices or heights; instead, the profile is
+// solid at every offset where one of them is over a piece of the other
+// skyline.
+fn offset_profile(a: &[Building], b: &[Building]) -> Vec<Building> {
+    let (a_solid, a_pieces) = split_solid(a);
+    let (b_solid, b_pieces) = split_solid(b);
+    let mut segs = Vec::new();
+
+    // Two open intervals overlap when start1 - end2 < dx < end1 - start2.
+    for &(s, ref p) in a_solid.iter() {
+        for &(t, ref q) in b_pieces.iter().chain(b_solid.iter()) {
+            segs.push(solid_buildings(s - q.end, p.end - t));
+        }
+    }
+    for &(s, ref p) in b_solid.iter() {
+        for &(t, ref q) in a_pieces.iter() {
+            segs.push(solid_buildings(t - p.end, q.end - s));
+        }
+    }
+
+    for &(x, h) in vertices(a).iter() {
+        for &(start, ref p) in b_pieces.iter() {
+            // The vertex touches p when start <= x - dx <= p.end.
+            segs.push(single_buildings(1.0, x - p.end, h + p.y(p.end),
+                                       x - start, h + p.y(start)));
+        }
+    }
+    for &(x, h) in vertices(b).iter() {
+        for &(start, ref p) in a_pieces.iter() {
+            // The vertex touches p when start <= x + dx <= p.end.
+            segs.push(single_buildings(1.0, start - x, h + p.y(start),
+                                       p.end - x, h + p.y(p.end)));
+        }
+    }
+
+    merge_many(segs)
+}
+
+// Pushes an empty building up to `start`, if `out` ends before that.
+fn fill_gap(out: &mut Vec<Building>, start: f64) {
+    let prev_end = out.last().map(|b| b.end).unwrap_or(Float::neg_infinity());
+    if prev_end < start {
+        out.push(Building::empty(start));
+    }
+}
+
+// Replaces `ps` with at most `max_pieces` flat pieces, each as high as the
+// highest point of the pieces it replaces (and covering any gaps between
+// them), so that the result is never lower.
+fn coarsen_into(ps: &[(f64, Building)], max_pieces: uint, out: &mut Vec<Building>) {
+    if ps.is_empty() {
+        return;
+    }
+
+    let group = (ps.len() + max_pieces - 1) / max_pieces;
+    for chunk in ps.chunks(group) {
+        let (start, _) = chunk[0];
+        let (_, last) = chunk[chunk.len() - 1];
+        let mut h: f64 = Float::neg_infinity();
+        for &(s, ref b) in chunk.iter() {
+            h = h.max(b.y(s)).max(b.y(b.end));
+        }
+
+        fill_gap(out, start);
+        out.push(Building { m: 0.0, b: h, end: last.end });
+    }
+}
+
+// Ends the output of `downsample_buildings` with the last building of
+// `bldgs` if it goes on forever, and with empty space otherwise.
+fn push_tail(bldgs: &[Building], out: &mut Vec<Building>) {
+    let n = bldgs.len();
+    if n >= 2 && bldgs[n - 1].b > Float::neg_infinity() {
+        fill_gap(out, bldgs[n - 2].end);
+        out.push(bldgs[n - 1]);
+    } else {
+        out.push(Building::empty(Float::infinity()));
+    }
+}
+
+// Reduces a profile to at most `max_pieces` pieces without lowering it
+// anywhere. The pieces around the lowest point are kept exactly, and only
+// the ones further away are coarsened, since those are the ones that a
+// search for the best offset doesn't care much about. Buildings that go on
+// forever aren't pieces, and are kept as they are.
+fn downsample_buildings(bldgs: &[Building], max_pieces: uint) -> Vec<Building> {
+    let ps = pieces(bldgs);
+    let n = ps.len();
+    if n <= max_pieces {
+        return bldgs.to_vec();
+    }
+
+    let mut out = Vec::new();
+    if bldgs[0].b > Float::neg_infinity() {
+        out.push(bldgs[0]);
+    }
+    if max_pieces < 3 {
+        coarsen_into(ps.as_slice(), max_pieces, &mut out);
+        push_tail(bldgs, &mut out);
+        return out;
+    }
+
+    let mut lowest = 0u;
+    let mut lowest_h: f64 = Float::infinity();
+    for (i, &(start, ref b)) in ps.iter().enumerate() {
+        let h = b.y(start).min(b.y(b.end));
+        if h < lowest_h {
+            lowest = i;
+            lowest_h = h;
+        }
+    }
+
+    // Half of the pieces are exact, and the rest are shared between the two
+    // sides in proportion to how many pieces they replace.
+    let exact = max_pieces / 2;
+    let lo = (if lowest > exact / 2 { lowest - exact / 2 } else { 0 }).min(n - exact);
+    let hi = lo + exact;
+    let budget = max_pieces - exact;
+    let left_budget = if lo == 0 {
+        0
+    } else if hi == n {
+        budget
+    } else {
+        (budget * lo / (n - exact)).max(1).min(budget - 1)
+    };
+
+    coarsen_into(ps.slice_to(lo), left_budget, &mut out);
+    for &(start, b) in ps.slice(lo, hi).iter() {
+        fill_gap(&mut out, start);
+        out.push(b);
+    }
+    coarsen_into(ps.slice_from(hi), budget - left_budget, &mut out);
+    push_tail(bldgs, &mut out);
+    out
+}
+
+// The overlap between `a` and `b` as a function of how far `b` is slid,
+// for slides in `dx_range`: the height of the result at `dx` is what
+// `a.overlap(b)` would return after `b.slide(dx)` (ignoring the domains).
+// This is the one-dimensional no-fit function, so it can be minimized
+// exactly instead of by sampling.
+//
+// The exact function has O(nm) pieces. If it has more than `max_pieces`,
+// it's reduced as by `Skyline::downsample`: exact around its minimum, and
+// an upper bound everywhere else.
+pub fn overlap_vs_offset<T: Direction, S: Flip<T>>(a: &Skyline<T>, b: &Skyline<S>,
+                                                   dx_range: Interval, max_pieces: uint)
+        -> Skyline<Up> {
+    assert!(max_pieces > 0, "max_pieces must be positive");
+    let profile = offset_profile(a.buildings.as_slice(), b.buildings.as_slice());
+    let clipped = clip_buildings(profile.as_slice(), dx_range.lo, dx_range.hi);
+    Skyline::from_buildings(downsample_buildings(clipped.as_slice(), max_pieces))
+}
+
+// Prefers lower heights, and then points closer to zero.
+fn lower(best: (f64, f64), x: f64, h: f64) -> (f64, f64) {
+    let (bx, bh) = best;
+    if h < bh || (h == bh && x.abs() < bx.abs()) { (x, h) } else { best }
+}
+
+// Finds the lowest point of a profile in the interval [x1, x2]. Returns
+// (x, height).
+fn lowest_point(profile: &[Building], x1: f64, x2: f64) -> (f64, f64) {
+    let mut best = (Float::nan(), Float::infinity());
+    let mut start: f64 = Float::neg_infinity();
+
+    for b in profile.iter() {
+        let lo = start.max(x1);
+        let hi = b.end.min(x2);
+        start = b.end;
+        if lo > hi {
+            continue;
+        }
+
+        // We use each building's own height at its endpoints, even where
+        // the neighboring building is higher. That only happens where one
+        // skyline has a vertical wall that the other one touches from the
+        // side, which is exactly what we want to allow.
+        best = lower(best, lo, b.y(lo));
+        best = lower(best, hi, b.y(hi));
+        if (b.m == 0.0 || b.b == Float::neg_infinity()) && lo < hi {
+            let mid = (0.0 as f64).max(lo).min(hi);
+            best = lower(best, mid, b.y(mid));
+        }
+    }
+
+    best
+}
+
+impl<T: Direction> Skyline<T> {
+    // A copy of this skyline with at most `max_pieces` pieces that is
+    // nowhere lower than the original. The pieces around the lowest point
+    // are kept as they are, and the rest are merged into flat pieces at
+    // their highest point. This is meant for overlap-vs-offset profiles
+    // (see `overlap_vs_offset`), where what matters is the minimum and
+    // never underestimating the overlap; it's also fine for drawing them.
+    pub fn downsample(&self, max_pieces: uint) -> Skyline<T> {
+        assert!(max_pieces > 0, "max_pieces must be positive");
+        Skyline {
+            buildings: downsample_buildings(self.buildings.as_slice(), max_pieces),
+            domain: self.domain,
+            config: self.config.clone()
+        }
+    }
+
+    // Finds the offset in `x_range` that lets `item` get as close as possible
+    // to this skyline, and how far the item must then be bumped to touch it
+    // without overlapping.
+    //
+    // The item is allowed to touch vertical walls from the side, so that
+    // things can be placed flush against each other. Note that `overlap`
+    // counts that kind of contact, so at exactly the returned offset it may
+    // report the height of the wall.
+    pub fn fit<S: Flip<T>>(&self, item: &Skyline<S>, x_range: (f64, f64)) -> Placement {
+        let (mut x1, mut x2) = x_range;
+        assert!(x1 <= x2, "empty range ({}, {})", x1, x2);
+        let mult = Direction::direction_multiplier(None::<T>);
+
+        // Each skyline's buildings have to stay inside the other one's domain.
+        let d = self.domain;
+        match support(item.buildings.as_slice()) {
+            Some((lo, hi)) => {
+                x1 = x1.max(d.lo - lo);
+                x2 = x2.min(d.hi - hi);
+            },
+            None => {},
+        }
+        let d = item.domain;
+        match support(self.buildings.as_slice()) {
+            Some((lo, hi)) => {
+                x1 = x1.max(hi - d.hi);
+                x2 = x2.min(lo - d.lo);
+            },
+            None => {},
+        }
+        if x1 > x2 {
+            let (dx, _) = x_range;
+            return Placement { dx: dx, dy: Float::infinity() * mult };
+        }
+
+        let profile = offset_profile(self.buildings.as_slice(), item.buildings.as_slice());
+        let (dx, dist) = lowest_point(profile.as_slice(), x1, x2);
+        Placement {
+            dx: dx,
+            dy: dist * mult
+        }
+    }
+
+    // The offsets `dx` for which `item`, after `item.slide(dx)`, overlaps
+    // this skyline by at most `max_overlap`, as sorted, disjoint intervals.
+    // They come from the exact overlap-vs-offset function, so unlike `fit`
+    // this ignores the domains. Offsets at which the two skylines don't
+    // meet at all are always feasible, so the first and last intervals are
+    // usually unbounded.
+    pub fn feasible_offsets<S: Flip<T>>(&self, item: &Skyline<S>, max_overlap: f64)
+            -> Vec<(f64, f64)> {
+        let profile = offset_profile(self.buildings.as_slice(), item.buildings.as_slice());
+        let too_close = Skyline::<Up>::from_buildings(profile).where_above(max_overlap);
+        too_close.complement().iter().map(|iv| (iv.lo, iv.hi)).collect()
+    }
+}
+
+// Remembers the results of `Skyline::fit`, for packing loops that try the
+// same parts against an envelope that only changes a little at a time.
+//
+// Results are keyed by the fingerprints (see `SkylineKey`) of the item and
+// of the part of the envelope that the item can reach, so a result stays
+// usable after merges elsewhere in the envelope. Since fingerprints are
+// rounded to `grid`, a cached placement can be off by about `grid` from
+// what `fit` would return for the new envelope; choose `grid` accordingly.
+//
+// At most `capacity` results are kept, and the least recently used ones
+// are dropped first. Results whose part of the envelope has changed can
+// never be found again, so passing the ranges returned by
+// `Skyline::merge_tracked` to `invalidate` frees their space early.
+pub struct PlacementCache {
+    grid: f64,
+    capacity: uint,
+    // Incremented on every lookup, to tell which entries are the oldest.
+    tick: uint,
+    entries: HashMap<(SkylineKey, SkylineKey), CacheEntry>
+}
+
+struct CacheEntry {
+    x_range: (f64, f64),
+    domain: Interval,
+    // The part of the envelope that the result depends on.
+    reach: Interval,
+    placement: Placement,
+    used: uint
+}
+
+impl PlacementCache {
+    pub fn new(grid: f64, capacity: uint) -> PlacementCache {
+        assert!(capacity > 0, "capacity must be positive");
+        PlacementCache {
+            grid: grid,
+            capacity: capacity,
+            tick: 0,
+            entries: HashMap::new()
+        }
+    }
+
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Drops the results that depend on the envelope in `dirty`.
+    pub fn invalidate(&mut self, dirty: Interval) {
+        let stale: Vec<(SkylineKey, SkylineKey)> = self.entries.iter()
+            .filter(|&(_, e)| e.reach.intersection(&dirty).is_some())
+            .map(|(k, _)| k.clone())
+            .collect();
+        for k in stale.iter() {
+            self.entries.remove(k);
+        }
+    }
+
+    // The same as `envelope.fit(item, x_range)`, up to rounding.
+    pub fn fit<T: Direction, S: Flip<T>>(&mut self, envelope: &Skyline<T>, item: &Skyline<S>,
+                                         x_range: (f64, f64)) -> Placement {
+        // A bounded item depends on where the whole envelope is, so we
+        // don't cache those.
+        let (x1, x2) = x_range;
+        let reach = match support(item.buildings.as_slice()) {
+            Some((lo, hi)) if !item.is_bounded() => Interval { lo: x1 + lo, hi: x2 + hi },
+            _ => return envelope.fit(item, x_range),
+        };
+
+        let local: Skyline<T> =
+            Skyline::from_buildings(clip_buildings(envelope.buildings.as_slice(),
+                                                   reach.lo, reach.hi));
+        let key = (SkylineKey::new(item, self.grid), SkylineKey::new(&local, self.grid));
+        self.tick += 1;
+        match self.entries.get_mut(&key) {
+            Some(e) => if e.x_range == x_range && e.domain == envelope.domain {
+                e.used = self.tick;
+                return e.placement;
+            },
+            None => {},
+        }
+
+        let placement = envelope.fit(item, x_range);
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            self.evict();
+        }
+        self.entries.insert(key, CacheEntry {
+            x_range: x_range,
+            domain: envelope.domain,
+            reach: reach,
+            placement: placement,
+            used: self.tick
+        });
+        placement
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.entries.iter()
+            .min_by(|&(_, e)| e.used)
+            .map(|(k, _)| k.clone());
+        match oldest {
+            Some(k) => { self.entries.remove(&k); },
+            None => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Interval, Rect, Skyline, Sweep, Up, Down};
+    use super::{overlap_vs_offset, PlacementCache};
+
+    #[test]
+    fn fit_finds_notch() {
+        let scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 3.0),
+                                                Rect::new(2.0, 0.0, 4.0, 1.0),
+                                                Rect::new(4.0, 0.0, 6.0, 3.0)]);
+        let item = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.0);
+
+        let p = scene.fit(&item, (-10.0, 10.0));
+        assert!(p.dx.approx_eq(2.0), "dx = {}", p.dx);
+        assert!(p.dy.approx_eq(1.0), "dy = {}", p.dy);
+
+        let p = scene.fit(&item, (4.5, 5.0));
+        assert!(p.dx.approx_eq(4.5), "dx = {}", p.dx);
+        assert!(p.dy.approx_eq(3.0), "dy = {}", p.dy);
+
+        // Past the end of the scene, nothing stops the item.
+        let p = scene.fit(&item, (4.5, 10.0));
+        assert!(p.dx.approx_eq(6.0), "dx = {}", p.dx);
+        assert_eq!(p.dy, Float::neg_infinity());
+    }
+
+    #[test]
+    fn fit_sloped() {
+        // A "V" shaped valley, with a wedge that fits into it.
+        let mut scene = Skyline::<Up>::single(0.0, 2.0, 2.0, 0.0);
+        scene.merge(&Skyline::single(2.0, 0.0, 4.0, 2.0));
+        let mut item = Skyline::<Down>::single(0.0, 1.0, 1.0, 0.0);
+        item.merge(&Skyline::single(1.0, 0.0, 2.0, 1.0));
+
+        let p = scene.fit(&item, (-5.0, 5.0));
+        assert!(p.dx.approx_eq(1.0), "dx = {}", p.dx);
+        assert!(p.dy.approx_eq(0.0), "dy = {}", p.dy);
+
+        // The placement must be consistent with sliding and bumping.
+        item.slide(p.dx);
+        item.bump(p.dy);
+        assert!(scene.overlap(&item).approx_eq(0.0));
+    }
+
+    #[test]
+    fn offset_profile_matches_overlap() {
+        let scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 3.0),
+                                                Rect::new(2.0, 0.0, 4.0, 1.0),
+                                                Rect::new(4.0, 0.0, 6.0, 2.0)]);
+        let item = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.5);
+        let dxs = Interval::new(-2.0, 7.0);
+        let profile = overlap_vs_offset(&scene, &item, dxs, 1000);
+
+        let mut moved = item.clone();
+        moved.slide(-2.0);
+        for i in range(0u, 37) {
+            let dx = -2.0 + i as f64 * 0.25;
+            let d = scene.overlap(&moved);
+            assert!(profile.height_at(dx).approx_eq(d), "at {}: {} != {}",
+                    dx, profile.height_at(dx), d);
+            moved.slide(0.25);
+        }
+        assert_eq!(profile.height_at(8.0), Float::neg_infinity());
+
+        let coarse = overlap_vs_offset(&scene, &item, dxs, 2);
+        assert!(coarse.segments().count() <= 2);
+        for i in range(0u, 37) {
+            let dx = -2.0 + i as f64 * 0.25;
+            assert!(coarse.height_at(dx) >= profile.height_at(dx));
+        }
+    }
+
+    #[test]
+    fn feasible_offsets() {
+        let scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 3.0),
+                                                Rect::new(2.0, 0.0, 4.0, 1.0),
+                                                Rect::new(4.0, 0.0, 6.0, 2.0)]);
+        let item = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.0);
+
+        // The item can only sit over the low middle rectangle, or off to
+        // either side.
+        let ivs = scene.feasible_offsets(&item, 1.5);
+        assert_eq!(ivs.len(), 3);
+        assert_eq!(ivs[0].0, Float::neg_infinity());
+        assert!(ivs[0].1.approx_eq(-1.0));
+        assert!(ivs[1].0.approx_eq(2.0) && ivs[1].1.approx_eq(3.0));
+        assert!(ivs[2].0.approx_eq(6.0));
+        assert_eq!(ivs[2].1, Float::infinity());
+
+        let mut moved = item.clone();
+        moved.slide(2.5);
+        assert!(scene.overlap(&moved) <= 1.5);
+    }
+
+    #[test]
+    fn downsample_keeps_minimum() {
+        let mut profile = Skyline::<Up>::empty();
+        let h = |i: uint| (i as f64 - 50.0).abs() * 0.1 + if i % 2 == 0 { 0.0 } else { 0.3 };
+        for i in range(0u, 100) {
+            profile.merge(&Skyline::single(i as f64, h(i), (i + 1) as f64, h(i + 1)));
+        }
+
+        let small = profile.downsample(10);
+        assert!(small.segments().count() <= 10);
+        assert!(small.height_at(50.0).approx_eq(0.0));
+        assert!(small.height_at(48.5).approx_eq(profile.height_at(48.5)));
+        for i in range(0u, 200) {
+            let x = i as f64 * 0.5 + 0.25;
+            assert!(small.height_at(x) >= profile.height_at(x));
+        }
+    }
+
+    #[test]
+    fn downsample_unbounded() {
+        let mut scene = Skyline::<Up>::empty();
+        for i in range(0u, 20) {
+            let x = i as f64;
+            scene.merge(&Skyline::single(x, (x * 0.7).sin(), x + 1.0, (x * 1.3).cos()));
+        }
+        let scene = scene.running_max(Sweep::RightToLeft);
+        let item = Skyline::<Down>::single(0.0, 0.0, 0.5, 0.0);
+        let dxs = Interval::unbounded();
+        let exact = overlap_vs_offset(&scene, &item, dxs, 1000);
+        let coarse = overlap_vs_offset(&scene, &item, dxs, 4);
+        assert!(coarse.segments().count() < exact.segments().count());
+        assert_eq!(coarse.validate(), Ok(()));
+        for i in range(0u, 60) {
+            let dx = i as f64 * 0.5 - 10.0;
+            assert!(coarse.height_at(dx) >= exact.height_at(dx),
+                    "at {}: {} < {}", dx, coarse.height_at(dx), exact.height_at(dx));
+        }
+        assert!(coarse.height_at(-100.0).approx_eq(exact.height_at(-100.0)));
+    }
+
+    #[test]
+    fn fit_ceiling() {
+        let ceiling = Skyline::<Down>::single(0.0, 5.0, 4.0, 5.0);
+        let item = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        let p = ceiling.fit(&item, (0.0, 3.0));
+        assert!(p.dy.approx_eq(4.0), "dy = {}", p.dy);
+    }
+
+    #[test]
+    fn fit_in_container() {
+        let mut container = Skyline::<Up>::bounded(0.0, 10.0);
+        container.merge(&Skyline::single(0.0, 5.0, 10.0, 0.0));
+        let item = Skyline::<Down>::single(0.0, 0.0, 2.0, 0.0);
+
+        let p = container.fit(&item, (-5.0, 20.0));
+        assert!(p.dx.approx_eq(8.0), "dx = {}", p.dx);
+        assert!(p.dy.approx_eq(1.0), "dy = {}", p.dy);
+
+        let p = container.fit(&item, (9.0, 20.0));
+        assert_eq!(p.dy, Float::infinity());
+    }
+
+    #[test]
+    fn cache_survives_distant_merges() {
+        let mut scene = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 3.0),
+                                                    Rect::new(2.0, 0.0, 4.0, 1.0)]);
+        let item = Skyline::<Down>::single(0.0, 0.0, 1.0, 0.0);
+        let mut cache = PlacementCache::new(1e-6, 2);
+
+        let p = cache.fit(&scene, &item, (0.0, 3.0));
+        assert_eq!(p, scene.fit(&item, (0.0, 3.0)));
+        assert_eq!(cache.len(), 1);
+
+        // A merge far away doesn't change the result, or the key.
+        let dirty = scene.merge_tracked(&Skyline::single(10.0, 5.0, 11.0, 5.0)).unwrap();
+        cache.invalidate(dirty);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.fit(&scene, &item, (0.0, 3.0)), p);
+        assert_eq!(cache.len(), 1);
+
+        // A merge nearby does.
+        let dirty = scene.merge_tracked(&Skyline::single(2.0, 2.0, 4.0, 2.0)).unwrap();
+        cache.invalidate(dirty);
+        assert_eq!(cache.len(), 0);
+        let p = cache.fit(&scene, &item, (0.0, 3.0));
+        assert!(p.dy.approx_eq(2.0), "dy = {}", p.dy);
+
+        // The oldest entry goes first.
+        cache.fit(&scene, &item, (5.0, 6.0));
+        cache.fit(&scene, &item, (12.0, 13.0));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.fit(&scene, &item, (5.0, 6.0)), scene.fit(&item, (5.0, 6.0)));
+    }
+}
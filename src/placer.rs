@@ -0,0 +1,523 @@
+use std::uint;
+use {Candidate, Down, Interval, Placement, Rect, Scorer, Shape, Skyline,
+     SkylineConfig, Up};
+use placement::overlap_vs_offset;
+
+// A placed shape that a best-effort placement cuts into, and how far the
+// new shape would have to move up to clear it.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Overlap {
+    // The shape's id, as returned by `reserve` (shapes placed with `place`
+    // get ids too, in the same sequence).
+    pub index: uint,
+    pub depth: f64
+}
+
+// Sorted by id.
+pub type Overlaps = Vec<Overlap>;
+
+// What `Placer::explain` thought of a candidate placement.
+#[cfg_attr(feature = "serialize", deriving(Encodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub enum Verdict {
+    // This is where `place` would put the shape.
+    Chosen,
+    // The shape fits here, but the chosen placement is at least as low.
+    NotChosen,
+    // The shape would stick out of the top of the bin.
+    TooTall,
+    // Nothing stops the shape here, which means it would be outside the bin.
+    Unsupported
+}
+
+#[cfg_attr(feature = "serialize", deriving(Encodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub struct Evaluated {
+    // The shape dropped as far as it goes at this offset.
+    pub placement: Placement,
+    // How far above the floor of the bin the top of the shape ends up.
+    // Lower is better.
+    pub score: f64,
+    pub verdict: Verdict
+}
+
+// A record of how `Placer::place` would place a shape, for finding out why
+// a shape ended up where it did. With the "serialize" feature this can be
+// written out as JSON.
+#[cfg_attr(feature = "serialize", deriving(Encodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub struct PlacementExplanation {
+    // True if the shape is wider than the bin, in which case nothing else
+    // was looked at.
+    pub too_wide: bool,
+    // The offsets that line the shape up with the breakpoints of what's
+    // already there, followed by the best offset overall.
+    pub candidates: Vec<Evaluated>,
+    pub chosen: Option<Placement>
+}
+
+// Packs shapes into a bin of a fixed width and height by dropping each one
+// from above onto the shapes that are already there. Regions of the bin can
+// also be reserved directly, and released again later.
+pub struct Placer {
+    width: f64,
+    height: f64,
+    floor: Skyline<Up>,
+    // Applied to the floor and to the tops of the shapes.
+    config: SkylineConfig,
+    // The tops of the placed shapes, tagged by their position in this list.
+    // Released shapes leave a hole, so that the ids don't change.
+    placed: Vec<Option<Skyline<Up>>>
+}
+
+fn ground(width: f64, config: &SkylineConfig) -> Skyline<Up> {
+    let mut floor = Skyline::with_config(config.clone());
+    floor.bound(0.0, width);
+    floor.merge(&Skyline::single(0.0, 0.0, width, 0.0));
+    floor
+}
+
+// Returns the x in [x1, x2] where the profile is lowest. The profile is
+// piecewise linear, so its minimum is at a breakpoint, or anywhere in a
+// flat or empty stretch between two breakpoints.
+fn lowest(profile: &Skyline<Up>, x1: f64, x2: f64) -> f64 {
+    let mut xs = vec![x1, x2];
+    for s in profile.segments() {
+        for &x in [s.x0, s.x1].iter() {
+            if x1 < x && x < x2 {
+                xs.push(x);
+            }
+        }
+    }
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut best = x1;
+    for i in range(0, xs.len()) {
+        let mid = if i + 1 < xs.len() { (xs[i] + xs[i + 1]) / 2.0 } else { xs[i] };
+        for &x in [xs[i], mid].iter() {
+            if profile.height_at(x) < profile.height_at(best) {
+                best = x;
+            }
+        }
+    }
+    best
+}
+
+impl Placer {
+    pub fn new(width: f64, height: f64) -> Placer {
+        Placer::with_config(width, height, SkylineConfig::new())
+    }
+
+    pub fn with_config(width: f64, height: f64, config: SkylineConfig) -> Placer {
+        Placer {
+            width: width,
+            height: height,
+            floor: ground(width, &config),
+            config: config,
+            placed: Vec::new()
+        }
+    }
+
+    // The number of shapes in the bin.
+    pub fn len(&self) -> uint {
+        self.placed.iter().filter(|p| p.is_some()).count()
+    }
+
+    fn add(&mut self, shape: &Shape, p: Placement) -> uint {
+        let mut top = p.apply(shape).skyline::<Up>();
+        top.set_config(self.config.clone());
+        self.floor.merge(&top);
+        self.placed.push(Some(top));
+        self.placed.len() - 1
+    }
+
+    // Marks everything under `shape` (where it is, without dropping it) as
+    // used. This works for any outline, not just rectangles. Returns an id
+    // for `release`.
+    pub fn reserve(&mut self, shape: &Shape) -> uint {
+        self.add(shape, Placement { dx: 0.0, dy: 0.0 })
+    }
+
+    // Removes a shape from the bin, making its space available again. Ids
+    // are never reused, so the other shapes keep theirs.
+    pub fn release(&mut self, id: uint) {
+        assert!(id < self.placed.len() && self.placed[id].is_some(),
+                "no shape with id {}", id);
+        self.placed[id] = None;
+
+        let mut skylines = vec![ground(self.width, &self.config)];
+        for p in self.placed.iter() {
+            match *p {
+                Some(ref top) => skylines.push(top.clone()),
+                None => {},
+            }
+        }
+        self.floor = Skyline::merge_all(skylines);
+    }
+
+    // The range of offsets that keep the shape inside the bin horizontally,
+    // if there are any.
+    fn x_range(&self, b: &Rect) -> Option<(f64, f64)> {
+        let (x1, x2) = (-b.x0, self.width - b.x1);
+        if x1 > x2 { None } else { Some((x1, x2)) }
+    }
+
+    // The offsets in [x1, x2] where one of the shape's sides lines up with a
+    // breakpoint of the floor, together with the ends of the range.
+    fn aligned_offsets(&self, b: &Rect, x1: f64, x2: f64) -> Vec<f64> {
+        let mut dxs = vec![x1, x2];
+        for s in self.floor.segments() {
+            for &x in [s.x0 - b.x0, s.x1 - b.x0, s.x0 - b.x1, s.x1 - b.x1].iter() {
+                if x1 < x && x < x2 {
+                    dxs.push(x);
+                }
+            }
+        }
+        dxs
+    }
+
+    // Drops the shape into the bin, as far down as it will go. Returns
+    // `None` (and leaves the bin alone) if the shape doesn't fit.
+    pub fn place(&mut self, shape: &Shape) -> Option<Placement> {
+        let b = shape.bounds();
+        let (x1, x2) = match self.x_range(&b) {
+            Some(r) => r,
+            None => return None,
+        };
+
+        let p = self.floor.fit(&shape.skyline::<Down>(), (x1, x2));
+        if !p.dy.is_finite() || b.y1 + p.dy > self.height {
+            return None;
+        }
+        self.add(shape, p);
+        Some(p)
+    }
+
+    // Like `place`, but lets `scorer` choose between the places where the
+    // shape could go. The candidates are the places where one of the
+    // shape's sides lines up with a breakpoint of the shapes that are
+    // already there (or with a side of the bin), with the shape dropped as
+    // far as it will go. The candidate with the highest score wins.
+    pub fn place_scored<S: Scorer>(&mut self, shape: &Shape, scorer: &S) -> Option<Placement> {
+        let b = shape.bounds();
+        let (x1, x2) = match self.x_range(&b) {
+            Some(r) => r,
+            None => return None,
+        };
+
+        let dxs = self.aligned_offsets(&b, x1, x2);
+        let bottom = shape.skyline::<Down>();
+        let mut best = None;
+        for &dx in dxs.iter() {
+            let p = self.floor.fit(&bottom, (dx, dx));
+            if !p.dy.is_finite() || b.y1 + p.dy > self.height {
+                continue;
+            }
+            let score = scorer.score(&Candidate::new(&b, p));
+            let better = match best {
+                Some((s, _)) => score > s,
+                None => true,
+            };
+            if better {
+                best = Some((score, p));
+            }
+        }
+
+        match best {
+            Some((_, p)) => {
+                self.add(shape, p);
+                Some(p)
+            },
+            None => None,
+        }
+    }
+
+    // Which of the candidates would still fit in the bin, and where `place`
+    // would put them, as pairs of (index into `candidates`, placement). The
+    // bin isn't changed, and each candidate is considered on its own: after
+    // placing one of them, the others might not fit any more.
+    //
+    // Candidates that are wider than the bin, or taller than the space above
+    // the lowest point of what's already there, are rejected without
+    // looking any further.
+    pub fn fill_suggestions(&self, candidates: &[Shape]) -> Vec<(uint, Placement)> {
+        let mut floor_min: f64 = Float::infinity();
+        for s in self.floor.segments() {
+            floor_min = floor_min.min(s.y0).min(s.y1);
+        }
+        let room = self.height - floor_min;
+
+        let mut out = Vec::new();
+        for (i, shape) in candidates.iter().enumerate() {
+            let b = shape.bounds();
+            let (x1, x2) = match self.x_range(&b) {
+                Some(r) => r,
+                None => continue,
+            };
+            if b.height() > room {
+                continue;
+            }
+
+            let p = self.floor.fit(&shape.skyline::<Down>(), (x1, x2));
+            if p.dy.is_finite() && b.y1 + p.dy <= self.height {
+                out.push((i, p));
+            }
+        }
+        out
+    }
+
+    // Explains what `place` would do with the shape, without placing it.
+    pub fn explain(&self, shape: &Shape) -> PlacementExplanation {
+        let b = shape.bounds();
+        let (x1, x2) = match self.x_range(&b) {
+            Some(r) => r,
+            None => return PlacementExplanation {
+                too_wide: true,
+                candidates: Vec::new(),
+                chosen: None
+            },
+        };
+
+        let bottom = shape.skyline::<Down>();
+        let best = self.floor.fit(&bottom, (x1, x2));
+        let mut dxs = self.aligned_offsets(&b, x1, x2);
+        dxs.push(best.dx);
+
+        let mut chosen = None;
+        let candidates = dxs.iter().enumerate().map(|(i, &dx)| {
+            let p = if i + 1 == dxs.len() { best } else { self.floor.fit(&bottom, (dx, dx)) };
+            let score = b.y1 + p.dy;
+            let verdict = if !p.dy.is_finite() {
+                Verdict::Unsupported
+            } else if score > self.height {
+                Verdict::TooTall
+            } else if i + 1 == dxs.len() {
+                chosen = Some(p);
+                Verdict::Chosen
+            } else {
+                Verdict::NotChosen
+            };
+            Evaluated { placement: p, score: score, verdict: verdict }
+        }).collect();
+
+        PlacementExplanation {
+            too_wide: false,
+            candidates: candidates,
+            chosen: chosen
+        }
+    }
+
+    // Like `place`, but always places the shape. If it doesn't fit, it goes
+    // at the top of the bin (or at the left edge, if it's too wide), wherever
+    // it overlaps the least, and the shapes it overlaps are reported.
+    pub fn place_best_effort(&mut self, shape: &Shape) -> (Placement, Overlaps) {
+        match self.place(shape) {
+            Some(p) => return (p, Vec::new()),
+            None => {},
+        }
+
+        let b = shape.bounds();
+        let x1 = -b.x0;
+        let x2 = (self.width - b.x1).max(x1);
+        let dy = self.height - b.y1;
+        let mut bottom = shape.skyline::<Down>();
+        bottom.bump(dy);
+
+        let profile = overlap_vs_offset(&self.floor, &bottom, Interval::new(x1, x2), uint::MAX);
+        let p = Placement { dx: lowest(&profile, x1, x2), dy: dy };
+        bottom.slide(p.dx);
+
+        let overlaps = self.overlaps_with(&bottom);
+        self.add(shape, p);
+        (p, overlaps)
+    }
+
+    // The placed shapes that `shape` would cut into if it were moved by `p`,
+    // and how deep. This tells which shapes are in the way of a placement
+    // that doesn't work out. Reserved regions count as shapes, but the
+    // floor and the sides of the bin don't.
+    pub fn overlaps_at(&self, shape: &Shape, p: Placement) -> Overlaps {
+        self.overlaps_with(&p.apply(shape).skyline::<Down>())
+    }
+
+    fn overlaps_with(&self, bottom: &Skyline<Down>) -> Overlaps {
+        let mut overlaps = Vec::new();
+        for (i, p) in self.placed.iter().enumerate() {
+            match *p {
+                Some(ref top) => {
+                    let depth = top.overlap(bottom);
+                    if depth > 0.0 {
+                        overlaps.push(Overlap { index: i, depth: depth });
+                    }
+                },
+                None => {},
+            }
+        }
+        overlaps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libc::{c_double, c_void};
+    use test_utils::ApproxEq;
+    use {Candidate, CScorer, Placement, Rect, Shape};
+    use super::{Overlap, Placer, Verdict};
+
+    // Prefers candidates further to the right, and counts how often it's
+    // called.
+    extern "C" fn rightmost(ctx: *mut c_void, c: *const Candidate) -> c_double {
+        unsafe {
+            *(ctx as *mut uint) += 1;
+            (*c).x1
+        }
+    }
+
+    #[test]
+    fn best_effort_reports_overlaps() {
+        let mut placer = Placer::new(4.0, 3.0);
+        let slab = Shape::from_rect(&Rect::new(0.0, 0.0, 4.0, 2.0));
+        let p = placer.place(&slab).unwrap();
+        assert!(p.dy.approx_eq(0.0), "dy = {}", p.dy);
+
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 2.0));
+        assert!(placer.place(&block).is_none());
+        assert_eq!(placer.len(), 1);
+
+        let (p, overlaps) = placer.place_best_effort(&block);
+        assert!(p.dy.approx_eq(1.0), "dy = {}", p.dy);
+        assert!(0.0 <= p.dx && p.dx <= 2.0, "dx = {}", p.dx);
+        assert_eq!(overlaps.len(), 1);
+        let Overlap { index, depth } = overlaps[0];
+        assert_eq!(index, 0);
+        assert!(depth.approx_eq(1.0), "depth = {}", depth);
+        assert_eq!(placer.len(), 2);
+    }
+
+    #[test]
+    fn blockers() {
+        let mut placer = Placer::new(6.0, 4.0);
+        let left = placer.reserve(&Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 2.0)));
+        let right = placer.reserve(&Shape::from_rect(&Rect::new(4.0, 0.0, 6.0, 3.0)));
+        let released = placer.reserve(&Shape::from_rect(&Rect::new(2.0, 0.0, 4.0, 1.0)));
+        placer.release(released);
+
+        let bar = Shape::from_rect(&Rect::new(0.0, 0.0, 6.0, 1.0));
+        let overlaps = placer.overlaps_at(&bar, Placement { dx: 0.0, dy: 1.5 });
+        assert_eq!(overlaps.len(), 2);
+        assert_eq!(overlaps[0].index, left);
+        assert!(overlaps[0].depth.approx_eq(0.5));
+        assert_eq!(overlaps[1].index, right);
+        assert!(overlaps[1].depth.approx_eq(1.5));
+        assert!(placer.overlaps_at(&bar, Placement { dx: 0.0, dy: 3.0 }).is_empty());
+    }
+
+    #[test]
+    fn best_effort_when_it_fits() {
+        let mut placer = Placer::new(4.0, 3.0);
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 2.0));
+        let (p, overlaps) = placer.place_best_effort(&block);
+        assert!(p.dy.approx_eq(0.0), "dy = {}", p.dy);
+        assert!(overlaps.is_empty());
+    }
+
+    #[test]
+    fn reserve_and_release() {
+        let mut placer = Placer::new(4.0, 10.0);
+        let wedge = Shape::from_polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 2.0)]);
+        let id = placer.reserve(&wedge);
+
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let p = placer.place(&block).unwrap();
+        assert!(p.dx.approx_eq(0.0) && p.dy.approx_eq(0.5), "{}", p);
+
+        placer.release(id);
+        assert_eq!(placer.len(), 1);
+        let p = placer.place(&block).unwrap();
+        assert!(p.dx.approx_eq(1.0) && p.dy.approx_eq(0.0), "{}", p);
+    }
+
+    #[test]
+    fn scored_placement() {
+        let mut placer = Placer::new(4.0, 10.0);
+        placer.reserve(&Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 1.0)));
+
+        let mut calls = 0u;
+        let scorer = CScorer {
+            ctx: &mut calls as *mut uint as *mut c_void,
+            score: rightmost
+        };
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let p = placer.place_scored(&block, &scorer).unwrap();
+        assert!(p.dx.approx_eq(3.0) && p.dy.approx_eq(0.0), "{}", p);
+        assert!(calls >= 2);
+        assert_eq!(placer.len(), 2);
+    }
+
+    #[test]
+    fn ids_in_order() {
+        let mut placer = Placer::new(3.0, 2.0);
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let ids: Vec<uint> = range(0u, 3)
+            .map(|i| placer.reserve(&block.translate(i as f64, 0.0)))
+            .collect();
+        assert_eq!(ids, vec![0, 1, 2]);
+        placer.release(1);
+        assert_eq!(placer.reserve(&block), 3);
+
+        // A wide shape that has to go on top overlaps everything, in order.
+        let wide = Shape::from_rect(&Rect::new(0.0, 0.0, 3.0, 1.5));
+        let (_, overlaps) = placer.place_best_effort(&wide);
+        let hit: Vec<uint> = overlaps.iter().map(|o| o.index).collect();
+        assert_eq!(hit, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn explain_matches_place() {
+        let mut placer = Placer::new(4.0, 2.0);
+        placer.reserve(&Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 1.0)));
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.5));
+
+        let e = placer.explain(&block);
+        assert!(!e.too_wide);
+        let last = e.candidates.last().unwrap();
+        assert_eq!(last.verdict, Verdict::Chosen);
+        assert_eq!(e.chosen, Some(last.placement));
+        // On top of the reserved block, the shape would poke out of the bin.
+        assert!(e.candidates.iter().any(|c| c.verdict == Verdict::TooTall));
+        assert!(e.candidates.iter().all(|c| c.score >= last.score));
+        assert_eq!(e.chosen, placer.place(&block));
+
+        let wide = Shape::from_rect(&Rect::new(0.0, 0.0, 5.0, 1.0));
+        assert!(placer.explain(&wide).too_wide);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn explanation_to_json() {
+        use serialize::json;
+
+        let placer = Placer::new(4.0, 2.0);
+        let block = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let encoded = json::encode(&placer.explain(&block));
+        assert!(encoded.as_slice().contains("\"Chosen\""), "{}", encoded);
+    }
+
+    #[test]
+    fn suggestions_for_leftover_space() {
+        let mut placer = Placer::new(4.0, 3.0);
+        placer.reserve(&Shape::from_rect(&Rect::new(0.0, 0.0, 3.0, 2.0)));
+
+        let candidates = [Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 3.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 2.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 5.0, 1.0)),
+                          Shape::from_rect(&Rect::new(0.0, 0.0, 2.0, 1.0))];
+        let suggestions = placer.fill_suggestions(&candidates);
+        let ids: Vec<uint> = suggestions.iter().map(|&(i, _)| i).collect();
+        assert_eq!(ids, vec![0, 3]);
+
+        let (_, p) = suggestions[0];
+        assert!(p.dx.approx_eq(3.0) && p.dy.approx_eq(0.0), "{}", p);
+        assert_eq!(placer.len(), 1);
+    }
+}
@@ -0,0 +1,85 @@
+// Rendering skylines as SVG, for debugging. The output is in the skyline's
+// own coordinates (so a `Left` or `Right` skyline is drawn as if it were
+// vertical), with y pointing up and `viewport` mapped onto the whole
+// picture.
+
+use {Direction, Flip, Rect, Segment, Skyline};
+
+static UP_COLOR: &'static str = "steelblue";
+static DOWN_COLOR: &'static str = "firebrick";
+
+fn color<T: Direction>() -> &'static str {
+    if Direction::direction_multiplier(None::<T>) > 0.0 { UP_COLOR } else { DOWN_COLOR }
+}
+
+fn header(view: &Rect) -> String {
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" \
+             width=\"{}\" height=\"{}\">\n",
+            view.width(), view.height(), view.width(), view.height())
+}
+
+// Draws the segments as one path, joining neighboring segments with
+// vertical lines where they meet at different heights.
+fn path<I: Iterator<Segment>>(mut segs: I, view: &Rect, color: &str) -> String {
+    let mut d = String::new();
+    let mut last_end: f64 = Float::nan();
+    for s in segs {
+        let cmd = if s.x0 == last_end { "L" } else { "M" };
+        d.push_str(format!("{} {} {} L {} {} ",
+                           cmd, s.x0 - view.x0, view.y1 - s.y0,
+                           s.x1 - view.x0, view.y1 - s.y1).as_slice());
+        last_end = s.x1;
+    }
+    format!("<path d=\"{}\" fill=\"none\" stroke=\"{}\" \
+             vector-effect=\"non-scaling-stroke\"/>\n", d.as_slice().trim(), color)
+}
+
+impl<T: Direction> Skyline<T> {
+    pub fn to_svg(&self, viewport: Rect) -> String {
+        let mut out = header(&viewport);
+        out.push_str(path(self.segments(), &viewport, color::<T>()).as_slice());
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+// Draws a skyline and one that faces it in the same picture, along with
+// the distance between them.
+pub fn overlay_svg<T: Direction, S: Flip<T>>(a: &Skyline<T>, b: &Skyline<S>, viewport: Rect)
+        -> String {
+    let mut out = header(&viewport);
+    out.push_str(path(a.segments(), &viewport, color::<T>()).as_slice());
+    out.push_str(path(b.segments(), &viewport, color::<S>()).as_slice());
+    out.push_str(format!("<text x=\"2\" y=\"12\" font-size=\"10\">overlap = {}</text>\n",
+                         a.overlap(b)).as_slice());
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use {Rect, Skyline, Up, Down};
+    use super::overlay_svg;
+
+    #[test]
+    fn svg_paths() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(1.0, 2.0, 2.0, 2.0));
+        sky.merge(&Skyline::single(3.0, 1.0, 4.0, 1.0));
+
+        let svg = sky.to_svg(Rect::new(0.0, 0.0, 4.0, 4.0));
+        assert!(svg.as_slice().starts_with("<svg"));
+        assert!(svg.as_slice().ends_with("</svg>\n"));
+        // The first two buildings are joined, and the third is separate.
+        assert_eq!(svg.as_slice().matches_indices("M ").count(), 2);
+    }
+
+    #[test]
+    fn svg_overlay() {
+        let up = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        let down = Skyline::<Down>::single(0.0, 3.0, 1.0, 3.0);
+        let svg = overlay_svg(&up, &down, Rect::new(0.0, 0.0, 4.0, 4.0));
+        assert_eq!(svg.as_slice().matches_indices("<path").count(), 2);
+        assert!(svg.as_slice().contains("overlap = -2"));
+    }
+}
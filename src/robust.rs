@@ -0,0 +1,95 @@
+// Exact evaluation of the predicates that merging depends on.
+//
+// With floating point, deciding which of two nearly coincident lines is
+// higher at a point can give different answers depending on how the
+// question is asked, and merges that get inconsistent answers can produce
+// out-of-order buildings. Here we represent sums exactly as "expansions"
+// (lists of non-overlapping floats whose sum is the exact value, as in
+// Shewchuk's robust predicates), so the answer is always right.
+
+use std::cmp::Ordering;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// a + b, exactly, as a rounded sum and an error term.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    let err = (a - (s - bb)) + (b - bb);
+    (s, err)
+}
+
+// a * b, exactly, as a rounded product and an error term.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+// Adds `b` to the expansion `e`, exactly.
+fn grow(e: &mut Vec<f64>, b: f64) {
+    let mut q = b;
+    let mut out = Vec::with_capacity(e.len() + 1);
+    for &x in e.iter() {
+        let (s, err) = two_sum(q, x);
+        if err != 0.0 {
+            out.push(err);
+        }
+        q = s;
+    }
+    out.push(q);
+    *e = out;
+}
+
+// The sign of the exact sum of `terms`, or `None` if something overflowed.
+fn sign_of_sum(terms: &[f64]) -> Option<Ordering> {
+    let mut e = Vec::new();
+    for &t in terms.iter() {
+        if !t.is_finite() {
+            return None;
+        }
+        grow(&mut e, t);
+    }
+
+    // The components are sorted by magnitude, and the largest non-zero one
+    // determines the sign.
+    for &c in e.iter().rev() {
+        if !c.is_finite() {
+            return None;
+        }
+        if c != 0.0 {
+            return Some(if c > 0.0 { Ordering::Greater } else { Ordering::Less });
+        }
+    }
+    Some(Ordering::Equal)
+}
+
+// Compares the lines y = m1*x + b1 and y = m2*x + b2 at `x`. Returns `None`
+// if any of the inputs are infinite (or the computation overflows), in
+// which case the caller should fall back to ordinary arithmetic.
+pub fn compare(m1: f64, b1: f64, m2: f64, b2: f64, x: f64)
+        -> Option<Ordering> {
+    let (p1, e1) = two_product(m1, x);
+    let (p2, e2) = two_product(m2, x);
+    sign_of_sum(&[b1, -b2, p1, e1, -p2, -e2])
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use super::compare;
+
+    #[test]
+    fn compare_is_exact() {
+        // In floating point, 0.1 * 3 rounds to 0.30000000000000004, but the
+        // exact product is a little smaller.
+        assert_eq!(0.1 * 3.0, 0.30000000000000004);
+        assert_eq!(compare(0.1, 0.0, 0.0, 0.30000000000000004, 3.0), Some(Ordering::Less));
+        assert_eq!(compare(0.5, 0.0, 0.0, 1.5, 3.0), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn compare_infinite() {
+        assert_eq!(compare(0.0, Float::neg_infinity(), 0.0, 1.0, 0.0), None);
+        assert_eq!(compare(1.0, 0.0, 0.0, 1.0, Float::infinity()), None);
+    }
+}
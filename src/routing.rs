@@ -0,0 +1,77 @@
+use {Down, Skyline, Up};
+
+// Finds the widest vertical gap between `floor` and `ceiling` at any x in
+// [x0, x1], for threading a line (such as a label's leader line) between
+// obstacles. Returns (y0, y1, x): the gap is from y0 up to y1, at x. Where
+// there's nothing above or below, the gap goes off to infinity. Returns
+// `None` if there's no gap anywhere in the range.
+//
+// Where either skyline has a vertical wall, the gap is measured to the top
+// (or bottom) of the wall, so the returned gap is always clear.
+pub fn widest_corridor(ceiling: &Skyline<Down>, floor: &Skyline<Up>, x0: f64, x1: f64)
+        -> Option<(f64, f64, f64)> {
+    if x0 > x1 {
+        return None;
+    }
+
+    // Between breakpoints both skylines are linear, so the gap is widest
+    // at one of the ends (or all along, if it's constant).
+    let mut xs = vec![x0, x1];
+    for s in ceiling.segments().chain(floor.segments()) {
+        for &x in [s.x0, s.x1].iter() {
+            if x0 < x && x < x1 {
+                xs.push(x);
+            }
+        }
+    }
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.dedup();
+
+    let mut best: Option<(f64, f64, f64)> = None;
+    for i in range(0, xs.len()) {
+        let mid = if i + 1 < xs.len() { (xs[i] + xs[i + 1]) / 2.0 } else { xs[i] };
+        for &x in [xs[i], mid].iter() {
+            let lo = floor.height_at(x);
+            let hi = ceiling.height_at(x);
+            let wider = match best {
+                Some((best_lo, best_hi, _)) => hi - lo > best_hi - best_lo,
+                None => hi > lo,
+            };
+            if wider {
+                best = Some((lo, hi, x));
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Skyline, Up, Down};
+    use super::widest_corridor;
+
+    #[test]
+    fn corridor_between_obstacles() {
+        let floor = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 1.0),
+                                                Rect::new(2.0, 0.0, 4.0, 3.0)]);
+        let ceiling = Skyline::<Down>::from_rects(&[Rect::new(0.0, 4.0, 1.0, 10.0),
+                                                    Rect::new(1.0, 2.0, 4.0, 10.0)]);
+
+        let (y0, y1, x) = widest_corridor(&ceiling, &floor, 0.0, 4.0).unwrap();
+        assert!(y0.approx_eq(1.0) && y1.approx_eq(4.0), "({}, {})", y0, y1);
+        assert!(0.0 <= x && x <= 1.0, "x = {}", x);
+
+        // Past x = 2 the obstacles overlap.
+        assert!(widest_corridor(&ceiling, &floor, 2.5, 4.0).is_none());
+        assert!(widest_corridor(&ceiling, &floor, 3.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn open_corridor() {
+        let floor = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        let (y0, y1, _) = widest_corridor(&Skyline::empty(), &floor, 0.0, 1.0).unwrap();
+        assert!(y0.approx_eq(1.0));
+        assert_eq!(y1, Float::infinity());
+    }
+}
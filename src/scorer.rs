@@ -0,0 +1,55 @@
+// Custom scoring of candidate placements, for `Placer::place_scored`. Rust
+// code can implement `Scorer` directly; other languages can fill in a
+// `CScorer`, whose layout is fixed.
+
+use libc::{c_double, c_void};
+use {Placement, Rect};
+
+// A position that the placer is considering for a shape. Coordinates are
+// C doubles here, so that the layout is fixed for C callers.
+#[repr(C)]
+#[deriving(Clone, PartialEq, Show)]
+pub struct Candidate {
+    // How far the shape would be moved.
+    pub dx: c_double,
+    pub dy: c_double,
+    // The shape's bounding box, after moving it.
+    pub x0: c_double,
+    pub y0: c_double,
+    pub x1: c_double,
+    pub y1: c_double
+}
+
+impl Candidate {
+    pub fn new(bounds: &Rect, p: Placement) -> Candidate {
+        let r = bounds.translate(p.dx, p.dy);
+        Candidate {
+            dx: p.dx as c_double,
+            dy: p.dy as c_double,
+            x0: r.x0 as c_double,
+            y0: r.y0 as c_double,
+            x1: r.x1 as c_double,
+            y1: r.y1 as c_double
+        }
+    }
+}
+
+// Higher scores are better.
+pub trait Scorer {
+    fn score(&self, candidate: &Candidate) -> c_double;
+}
+
+// A scorer implemented in C (or anything else that can export a C function).
+// `score` is called with `ctx` and a pointer to the candidate, which is only
+// valid during the call.
+#[repr(C)]
+pub struct CScorer {
+    pub ctx: *mut c_void,
+    pub score: extern "C" fn(ctx: *mut c_void, candidate: *const Candidate) -> c_double
+}
+
+impl Scorer for CScorer {
+    fn score(&self, candidate: &Candidate) -> c_double {
+        (self.score)(self.ctx, candidate as *const Candidate)
+    }
+}
@@ -0,0 +1,173 @@
+use {Building, Direction, Extremum, Skyline};
+use {first_intersection, wins};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// One straight piece of a skyline, in world coordinates. The skyline is
+// empty wherever there isn't a segment.
+#[cfg_attr(feature = "serialize", deriving(Encodable, Decodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub struct Segment {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64
+}
+
+// The non-empty pieces of a skyline, from left to right. Each segment starts
+// at or after the end of the one before it (a vertical wall is a segment
+// with `x0 == x1`), so the segments are sorted by both ends.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Segments<'a> {
+    buildings: &'a [Building],
+    mult: f64,
+    start: f64,
+    idx: uint
+}
+
+impl<'a> Iterator<Segment> for Segments<'a> {
+    fn next(&mut self) -> Option<Segment> {
+        while self.idx < self.buildings.len() {
+            let b = &self.buildings[self.idx];
+            let start = self.start;
+            self.idx += 1;
+            self.start = b.end;
+
+            if b.b > Float::neg_infinity() && start.is_finite() && b.end.is_finite() {
+                return Some(Segment {
+                    x0: start,
+                    y0: b.y(start) * self.mult,
+                    x1: b.end,
+                    y1: b.y(b.end) * self.mult
+                });
+            }
+        }
+        None
+    }
+}
+
+// The non-empty pieces of the merge of two skylines, from left to right,
+// in the same order as `Segments`. This runs the same sweep as
+// `Skyline::merge`, one step at a time.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct MergeSegments<'a> {
+    in1: &'a [Building],
+    in2: &'a [Building],
+    mult: f64,
+    start: f64,
+    i: uint,
+    j: uint
+}
+
+impl<'a> Iterator<Segment> for MergeSegments<'a> {
+    fn next(&mut self) -> Option<Segment> {
+        let in1 = self.in1;
+        let in2 = self.in2;
+        while self.i < in1.len() && self.j < in2.len() {
+            let b1 = &in1[self.i];
+            let b2 = &in2[self.j];
+            let start = self.start;
+
+            let b = if wins(b1, b2, start, b1.intersection(b2), Extremum::Max) {
+                self.start = first_intersection(b1, in2, start, &mut self.j, Extremum::Max);
+                if self.start >= b1.end {
+                    self.i += 1;
+                }
+                b1.chop(self.start)
+            } else {
+                self.start = first_intersection(b2, in1, start, &mut self.i, Extremum::Max);
+                if self.start >= b2.end {
+                    self.j += 1;
+                }
+                b2.chop(self.start)
+            };
+
+            if b.b > Float::neg_infinity() && start.is_finite() && b.end.is_finite() {
+                return Some(Segment {
+                    x0: start,
+                    y0: b.y(start) * self.mult,
+                    x1: b.end,
+                    y1: b.y(b.end) * self.mult
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    pub fn segments<'a>(&'a self) -> Segments<'a> {
+        Segments {
+            buildings: self.buildings.as_slice(),
+            mult: Direction::direction_multiplier(None::<T>),
+            start: Float::neg_infinity(),
+            idx: 0
+        }
+    }
+
+    // The segments of the merge of this skyline and `other`, computed as
+    // they're needed instead of building a new skyline. This ignores the
+    // domains.
+    pub fn merge_iter<'a>(&'a self, other: &'a Skyline<T>) -> MergeSegments<'a> {
+        MergeSegments {
+            in1: self.buildings.as_slice(),
+            in2: other.buildings.as_slice(),
+            mult: Direction::direction_multiplier(None::<T>),
+            start: Float::neg_infinity(),
+            i: 0,
+            j: 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Rect, Skyline, Up, Down};
+    use super::Segment;
+
+    fn assert_ordered(segs: &[Segment]) {
+        for i in range(0, segs.len()) {
+            assert!(segs[i].x0 <= segs[i].x1, "{}", segs[i]);
+            if i > 0 {
+                assert!(segs[i - 1].x1 <= segs[i].x0, "{} then {}", segs[i - 1], segs[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn segments_world_coordinates() {
+        let sky = Skyline::<Down>::from_rects(&[Rect::new(0.0, -1.0, 1.0, 0.0),
+                                               Rect::new(2.0, -2.0, 3.0, 0.0)]);
+        let segs: Vec<Segment> = sky.segments().collect();
+        assert_eq!(segs, vec![Segment { x0: 0.0, y0: -1.0, x1: 1.0, y1: -1.0 },
+                              Segment { x0: 2.0, y0: -2.0, x1: 3.0, y1: -2.0 }]);
+    }
+
+    #[test]
+    fn lazy_merge() {
+        let a = Skyline::<Up>::from_rects(&[Rect::new(0.0, 0.0, 2.0, 1.0),
+                                            Rect::new(3.0, 0.0, 4.0, 1.0)]);
+        let b = Skyline::<Up>::single(1.0, 0.0, 3.5, 2.5);
+        let mut merged = a.clone();
+        merged.merge(&b);
+
+        let lazy: Vec<Segment> = a.merge_iter(&b).collect();
+        let eager: Vec<Segment> = merged.segments().collect();
+        assert_eq!(lazy, eager);
+        assert_eq!(a.merge_iter(&Skyline::empty()).count(), a.segments().count());
+    }
+
+    #[test]
+    fn segments_are_ordered() {
+        let mut a = Skyline::<Up>::from_rects(&[Rect::new(3.0, 0.0, 4.0, 1.0),
+                                                Rect::new(0.0, 0.0, 2.0, 2.0)]);
+        a.merge(&Skyline::single(1.0, 0.0, 1.0, 5.0));
+        a.merge(&Skyline::single(-1.0, 3.0, 3.5, -1.0));
+        let b = Skyline::<Up>::single(0.5, 4.0, 5.0, 0.0);
+
+        let segs: Vec<Segment> = a.segments().collect();
+        assert_ordered(segs.as_slice());
+        let merged: Vec<Segment> = a.merge_iter(&b).collect();
+        assert_ordered(merged.as_slice());
+    }
+}
@@ -0,0 +1,133 @@
+use serialize::{Decodable, Decoder, Encodable, Encoder};
+use {Building, Dir, Direction, Interval, Skyline};
+
+// The serialized form of a skyline. This is part of the public interface,
+// so don't change it without a good reason.
+//
+// The buildings are listed from left to right. Each one runs from the end
+// of the previous building (or from negative infinity, for the first one)
+// to `end`, where `None` means positive infinity and must only appear on
+// the last building. A building's `line` is `(m, b)` for the line
+// y = m*x + b in world coordinates, or `None` if the building is empty. An
+// unbounded side of the domain is also written as `None`.
+#[deriving(Clone, Encodable, Decodable, Show)]
+pub struct SkylineRepr {
+    pub dir: Dir,
+    domain: (Option<f64>, Option<f64>),
+    buildings: Vec<BuildingRepr>
+}
+
+#[deriving(Clone, Encodable, Decodable, Show)]
+struct BuildingRepr {
+    end: Option<f64>,
+    line: Option<(f64, f64)>
+}
+
+fn finite(x: f64) -> Option<f64> {
+    if x.is_infinite() { None } else { Some(x) }
+}
+
+impl SkylineRepr {
+    pub fn new<T: Direction>(sky: &Skyline<T>) -> SkylineRepr {
+        let mult = Direction::direction_multiplier(None::<T>);
+        SkylineRepr {
+            dir: sky.dir(),
+            domain: (finite(sky.domain.lo), finite(sky.domain.hi)),
+            buildings: sky.buildings.iter().map(|b| BuildingRepr {
+                end: finite(b.end),
+                line: if b.b == Float::neg_infinity() {
+                    None
+                } else {
+                    Some((b.m * mult, b.b * mult))
+                }
+            }).collect()
+        }
+    }
+}
+
+// Checks that the representation describes a valid skyline, returning a
+// description of the problem if it doesn't.
+pub fn to_skyline<T: Direction>(repr: SkylineRepr) -> Result<Skyline<T>, String> {
+    let mult = Direction::direction_multiplier(None::<T>);
+    if repr.dir != Direction::dir(None::<T>) {
+        return Err(format!("expected a {} skyline, found {}",
+                           Direction::dir(None::<T>), repr.dir));
+    }
+
+    let n = repr.buildings.len();
+    let mut bldgs = Vec::with_capacity(n);
+    let mut start: f64 = Float::neg_infinity();
+    for (i, b) in repr.buildings.iter().enumerate() {
+        let end = match b.end {
+            Some(x) if i + 1 < n && x >= start && !x.is_nan() => x,
+            None if i + 1 == n => Float::infinity(),
+            _ => return Err(format!("building {} has an invalid end", i)),
+        };
+        bldgs.push(match b.line {
+            Some((m, y)) => Building { m: m * mult, b: y * mult, end: end },
+            None => Building::empty(end),
+        });
+        start = end;
+    }
+    if bldgs.is_empty() {
+        return Err("a skyline needs at least one building".to_string());
+    }
+
+    let (x1, x2) = repr.domain;
+    let mut sky = Skyline::from_buildings(bldgs);
+    sky.domain = Interval {
+        lo: x1.unwrap_or(Float::neg_infinity()),
+        hi: x2.unwrap_or(Float::infinity())
+    };
+    Ok(sky)
+}
+
+impl<E, S: Encoder<E>, T: Direction> Encodable<S, E> for Skyline<T> {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        SkylineRepr::new(self).encode(s)
+    }
+}
+
+impl<E, D: Decoder<E>, T: Direction> Decodable<D, E> for Skyline<T> {
+    fn decode(d: &mut D) -> Result<Skyline<T>, E> {
+        let repr: SkylineRepr = try!(Decodable::decode(d));
+        match to_skyline(repr) {
+            Ok(sky) => Ok(sky),
+            Err(msg) => Err(d.error(msg.as_slice())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serialize::json;
+    use test_utils::ApproxEq;
+    use {Interval, Skyline, Up, Down};
+
+    #[test]
+    fn json_round_trip() {
+        let mut sky = Skyline::<Down>::bounded(0.0, 10.0);
+        sky.merge(&Skyline::single(1.0, 2.0, 3.0, 4.0));
+
+        let encoded = json::encode(&sky);
+        let decoded: Skyline<Down> = json::decode(encoded.as_slice()).unwrap();
+        assert!(decoded.approx_eq(&sky));
+        assert_eq!(decoded.domain(), Interval::new(0.0, 10.0));
+
+        let probe = Skyline::<Up>::single(2.0, 0.0, 2.5, 0.0);
+        assert!(decoded.overlap(&probe).approx_eq(sky.overlap(&probe)));
+    }
+
+    #[test]
+    fn json_rejects_bad_input() {
+        let sky = Skyline::<Up>::single(0.0, 1.0, 1.0, 1.0);
+        let encoded = json::encode(&sky);
+        assert!(json::decode::<Skyline<Down>>(encoded.as_slice()).is_err());
+
+        let unsorted = r#"{"dir":"Up","domain":[null,null],
+                           "buildings":[{"end":2.0,"line":null},
+                                        {"end":1.0,"line":[0.0,1.0]},
+                                        {"end":null,"line":null}]}"#;
+        assert!(json::decode::<Skyline<Up>>(unsorted).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use {Direction, Down, Rect, Skyline, Up};
+
+// The skylines of a shape's outline in a rotated frame, where `dir` points
+// along the new y axis.
+#[deriving(Clone, Show)]
+struct Frame {
+    dir: (f64, f64),
+    front: Skyline<Up>,
+    back: Skyline<Down>
+}
+
+// One of the transforms that a shape remembers having had applied to it.
+#[cfg_attr(feature = "serialize", deriving(Encodable, Decodable))]
+#[deriving(Clone, PartialEq, Show)]
+pub enum ShapeTransform {
+    Translate(f64, f64),
+    // Counter-clockwise around the origin, by an angle in radians.
+    Rotate(f64),
+    // Reflects across the y axis, so x becomes -x.
+    FlipX,
+    // Reflects across the x axis, so y becomes -y.
+    FlipY
+}
+
+impl ShapeTransform {
+    fn apply(&self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        match *self {
+            ShapeTransform::Translate(dx, dy) => {
+                points.iter().map(|&(x, y)| (x + dx, y + dy)).collect()
+            },
+            ShapeTransform::Rotate(theta) => {
+                let (s, c) = theta.sin_cos();
+                points.iter().map(|&(x, y)| (x * c - y * s, x * s + y * c)).collect()
+            },
+            ShapeTransform::FlipX => points.iter().map(|&(x, y)| (-x, y)).collect(),
+            ShapeTransform::FlipY => points.iter().map(|&(x, y)| (x, -y)).collect(),
+        }
+    }
+}
+
+// A polygonal object to be laid out. Its skylines are computed on demand
+// from the outline. The skylines for the last direction passed to
+// `clearance_along` are cached, since the same shapes tend to be tested
+// against each other many times in the same direction. The cache is behind
+// a lock, so shapes can be shared between threads; two threads asking for
+// different directions at once just recompute more often.
+//
+// A shape also keeps a log of the transforms that made it from the outline
+// it was created with. Passing that outline and the log to `replay` redoes
+// exactly the same arithmetic, so the result is bit-for-bit the same shape.
+pub struct Shape {
+    points: Vec<(f64, f64)>,
+    history: Vec<ShapeTransform>,
+    frame: Mutex<Option<Arc<Frame>>>
+}
+
+impl Clone for Shape {
+    fn clone(&self) -> Shape {
+        Shape {
+            points: self.points.clone(),
+            history: self.history.clone(),
+            frame: Mutex::new(self.frame.lock().clone())
+        }
+    }
+}
+
+// The cache isn't part of the shape, as far as anyone outside can tell.
+impl fmt::Show for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Shape {{ points: {}, history: {} }}", self.points, self.history)
+    }
+}
+
+impl PartialEq for Shape {
+    fn eq(&self, other: &Shape) -> bool {
+        self.points == other.points
+    }
+}
+
+impl Shape {
+    // The points describe a simple polygon, in either orientation.
+    pub fn from_polygon(points: &[(f64, f64)]) -> Shape {
+        assert!(!points.is_empty(), "a shape needs at least one point");
+        Shape {
+            points: points.to_vec(),
+            history: Vec::new(),
+            frame: Mutex::new(None)
+        }
+    }
+
+    // Rebuilds a shape from the outline it was created with and its
+    // `history`.
+    pub fn replay(points: &[(f64, f64)], history: &[ShapeTransform]) -> Shape {
+        history.iter().fold(Shape::from_polygon(points), |shape, t| shape.apply(t))
+    }
+
+    pub fn from_rect(r: &Rect) -> Shape {
+        Shape::from_polygon(&[(r.x0, r.y0), (r.x1, r.y0), (r.x1, r.y1), (r.x0, r.y1)])
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        self.points.as_slice()
+    }
+
+    pub fn bounds(&self) -> Rect {
+        let (x, y) = self.points[0];
+        let mut r = Rect::new(x, y, x, y);
+        for &(x, y) in self.points.iter() {
+            r = r.union(&Rect::new(x, y, x, y));
+        }
+        r
+    }
+
+    // The transforms that have been applied to this shape, oldest first.
+    pub fn history(&self) -> &[ShapeTransform] {
+        self.history.as_slice()
+    }
+
+    // The transformed shape, with `t` added to the end of its history.
+    pub fn apply(&self, t: &ShapeTransform) -> Shape {
+        let mut history = self.history.clone();
+        history.push(t.clone());
+        Shape {
+            points: t.apply(self.points.as_slice()),
+            history: history,
+            frame: Mutex::new(None)
+        }
+    }
+
+    pub fn translate(&self, dx: f64, dy: f64) -> Shape {
+        self.apply(&ShapeTransform::Translate(dx, dy))
+    }
+
+    pub fn rotate(&self, theta: f64) -> Shape {
+        self.apply(&ShapeTransform::Rotate(theta))
+    }
+
+    pub fn flip_x(&self) -> Shape {
+        self.apply(&ShapeTransform::FlipX)
+    }
+
+    pub fn flip_y(&self) -> Shape {
+        self.apply(&ShapeTransform::FlipY)
+    }
+
+    pub fn skyline<T: Direction>(&self) -> Skyline<T> {
+        Skyline::from_polygon(self.points.as_slice())
+    }
+
+    // The frame for the unit vector `dir`, from the cache if it's there.
+    // The lock isn't held after this returns, so asking for the frames of
+    // the same shape twice doesn't deadlock.
+    fn frame(&self, dir: (f64, f64)) -> Arc<Frame> {
+        let mut cached = self.frame.lock();
+        match *cached {
+            Some(ref f) if f.dir == dir => return f.clone(),
+            _ => {},
+        }
+
+        // In the new frame, x is measured along the perpendicular to `dir`
+        // and y is measured along `dir`.
+        let (dx, dy) = dir;
+        let rotated: Vec<(f64, f64)> = self.points.iter()
+            .map(|&(x, y)| (x * dy - y * dx, x * dx + y * dy))
+            .collect();
+        let f = Arc::new(Frame {
+            dir: dir,
+            front: Skyline::from_polygon(rotated.as_slice()),
+            back: Skyline::from_polygon(rotated.as_slice())
+        });
+        *cached = Some(f.clone());
+        f
+    }
+
+    // How far this shape can move in `direction` (which doesn't need to be
+    // normalized) before it touches `other`. This generalizes dropping
+    // things onto a skyline to gravity in any direction. As with skylines,
+    // all of `other` counts as being in the way, so the result is negative
+    // if `other` is (even partly) behind this shape, and infinite if this
+    // shape can move forever without hitting it.
+    pub fn clearance_along(&self, other: &Shape, direction: (f64, f64)) -> f64 {
+        let (x, y) = direction;
+        let len = (x * x + y * y).sqrt();
+        assert!(len > 0.0 && len.is_finite(), "invalid direction ({}, {})", x, y);
+        let dir = (x / len, y / len);
+
+        let a = self.frame(dir);
+        let b = other.frame(dir);
+        -a.front.overlap(&b.back)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Up};
+    use super::{Shape, ShapeTransform};
+
+    #[test]
+    fn shape_basics() {
+        let s = Shape::from_polygon(&[(0.0, 0.0), (4.0, 0.0), (2.0, 3.0)]).translate(1.0, 1.0);
+        assert_eq!(s.bounds(), Rect::new(1.0, 1.0, 5.0, 4.0));
+        assert!(s.skyline::<Up>().height_at(3.0).approx_eq(4.0));
+        assert_eq!(Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 2.0)).bounds(),
+                   Rect::new(0.0, 0.0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn replay_is_exact() {
+        let outline = [(0.0, 0.0), (3.0, 0.0), (1.0, 2.0)];
+        let s = Shape::from_polygon(&outline)
+            .rotate(0.3)
+            .translate(0.1, 0.7)
+            .flip_x()
+            .rotate(-1.1)
+            .flip_y();
+        assert_eq!(s.history().len(), 5);
+        assert_eq!(s.history()[1], ShapeTransform::Translate(0.1, 0.7));
+
+        let again = Shape::replay(&outline, s.history());
+        assert_eq!(again.points(), s.points());
+        assert_eq!(again.history(), s.history());
+    }
+
+    #[test]
+    fn clearance_in_any_direction() {
+        let a = Shape::from_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+        let b = Shape::from_rect(&Rect::new(3.0, 0.0, 4.0, 1.0));
+        let c = Shape::from_rect(&Rect::new(2.0, 2.0, 3.0, 3.0));
+
+        assert!(a.clearance_along(&b, (1.0, 0.0)).approx_eq(2.0));
+        assert!(a.clearance_along(&b, (5.0, 0.0)).approx_eq(2.0));
+        assert!(a.clearance_along(&c, (1.0, 1.0)).approx_eq((2.0 as f64).sqrt()));
+        assert!(a.clearance_along(&b, (-1.0, 0.0)) < 0.0);
+        assert_eq!(a.clearance_along(&b, (0.0, 1.0)), Float::infinity());
+
+        // Going back to a direction we've seen before gives the same answer.
+        assert!(a.clearance_along(&b, (1.0, 0.0)).approx_eq(2.0));
+    }
+}
@@ -0,0 +1,143 @@
+use {Building, Direction, Skyline};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+impl<T: Direction> Skyline<T> {
+    // Cleans up the buildings that pile up after many merges: neighboring
+    // empty buildings are combined, zero-width buildings that don't stick
+    // out more than `tolerance` above their neighbors are dropped, and runs
+    // of buildings that are collinear to within `tolerance` are replaced by a
+    // single building. The result is never more than `tolerance` away from
+    // the original.
+    pub fn simplify(&mut self, tolerance: f64) {
+        assert!(tolerance >= 0.0, "negative tolerance ({})", tolerance);
+        let out = simplify_buildings(self.buildings.as_slice(), tolerance, self.config.max_slope);
+        self.buildings = out;
+        self.debug_assert_valid();
+    }
+}
+
+pub fn simplify_buildings(bldgs: &[Building], tolerance: f64, max_slope: f64)
+        -> Vec<Building> {
+    let n = bldgs.len();
+    let mut out: Vec<Building> = Vec::with_capacity(n);
+    let mut start: f64 = Float::neg_infinity();
+    let mut i = 0u;
+
+    while i < n {
+        let b = bldgs[i];
+        if b.b.is_infinite() {
+            // Neighboring empty (or solid) buildings are combined.
+            let extend = match out.last() {
+                Some(prev) => prev.b == b.b,
+                None => false,
+            };
+            if extend {
+                out.last_mut().unwrap().end = b.end;
+            } else {
+                out.push(b);
+            }
+        } else if b.end == start {
+            let left = if i > 0 { bldgs[i - 1].y(start) } else { Float::neg_infinity() };
+            let right = if i + 1 < n { bldgs[i + 1].y(start) } else { Float::neg_infinity() };
+            if b.y(start) > left.max(right) + tolerance {
+                out.push(b);
+            }
+        } else if !start.is_finite() {
+            out.push(b);
+        } else {
+            // Extend a run of buildings for as long as there's a line from
+            // the start of the run to the end of the next building that
+            // stays within the tolerance at every breakpoint in between. The
+            // slopes of such lines form an interval that can only shrink as
+            // the run gets longer.
+            let y0 = b.y(start);
+            let mut lo: f64 = -max_slope;
+            let mut hi: f64 = max_slope;
+            let mut j = i;
+            while j + 1 < n {
+                let next = &bldgs[j + 1];
+                let x = bldgs[j].end;
+                if next.b.is_infinite() || next.end == x {
+                    break;
+                }
+
+                let left = bldgs[j].y(x);
+                let right = next.y(x);
+                lo = lo.max((left.max(right) - tolerance - y0) / (x - start));
+                hi = hi.min((left.min(right) + tolerance - y0) / (x - start));
+
+                let slope = (next.y(next.end) - y0) / (next.end - start);
+                if lo <= slope && slope <= hi {
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+
+            if j == i {
+                out.push(b);
+            } else {
+                let end = bldgs[j].end;
+                let m = (bldgs[j].y(end) - y0) / (end - start);
+                out.push(Building { m: m, b: y0 - m * start, end: end });
+            }
+            i = j;
+        }
+
+        start = bldgs[i].end;
+        i += 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Building, Skyline, Up, Down};
+
+    #[test]
+    fn simplify_collinear() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        sky.merge(&Skyline::single(1.0, 1.0, 2.0, 2.0));
+        sky.merge(&Skyline::single(-1.0, -5.0, -0.5, -5.0));
+        sky.simplify(1e-9);
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(-1.0),
+                 Building { m: 0.0, b: -5.0, end: -0.5 },
+                 Building::empty(0.0),
+                 Building { m: 1.0, b: 0.0, end: 2.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target), "{}", sky);
+    }
+
+    #[test]
+    fn simplify_zigzag() {
+        let mut sky = Skyline::<Up>::empty();
+        for i in range(0u, 10) {
+            let x = i as f64;
+            let y = if i % 2 == 0 { 0.0 } else { 0.01 };
+            sky.merge(&Skyline::single(x, y, x + 1.0, 0.01 - y));
+        }
+        let mut coarse = sky.clone();
+        coarse.simplify(0.05);
+        assert_eq!(coarse.segments().count(), 1);
+        sky.simplify(0.001);
+        assert!(sky.segments().count() > 1);
+    }
+
+    #[test]
+    fn simplify_spikes() {
+        let mut sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        sky.merge(&Skyline::single(1.0, 1.0, 1.0, 1.01));
+        sky.merge(&Skyline::single(1.5, 1.0, 1.5, 3.0));
+        sky.simplify(0.1);
+
+        let probe = Skyline::<Down>::single(0.5, 0.0, 1.2, 0.0);
+        assert!(sky.overlap(&probe).approx_eq(1.0));
+        let probe = Skyline::<Down>::single(1.4, 0.0, 1.6, 0.0);
+        assert!(sky.overlap(&probe).approx_eq(3.0));
+    }
+}
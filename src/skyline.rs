@@ -1,3 +1,215 @@
+// Without the "std" feature (which is on by default), only the core of the
+// crate is built: `Skyline` and its constructors, merges, overlaps,
+// segments and configurations, along with `DirSkyline` and the compact
+// format. That part only needs `core` and `collections`, so it builds with
+// `#![no_std]` for embedded targets.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), feature(globs, macro_rules, phase))]
+
+#[cfg(not(feature = "std"))]
+#[phase(plugin, link)]
+extern crate core;
+#[cfg(not(feature = "std"))]
+extern crate collections;
+#[cfg(feature = "std")]
+extern crate libc;
+#[cfg(feature = "arbitrary")]
+extern crate quickcheck;
+#[cfg(feature = "serialize")]
+extern crate serialize;
+
+pub use config::SkylineConfig;
+pub use dir::{Dir, DirSkyline};
+pub use error::{Blocked, SkylineError};
+pub use geom::{Affine, Interval, Rect};
+pub use segment::{MergeSegments, Segment, Segments};
+pub use validate::InvariantError;
+
+#[cfg(feature = "std")]
+pub use accumulator::{Overflow, SkylineAccumulator};
+#[cfg(feature = "std")]
+pub use chunked::ChunkedSkyline;
+#[cfg(feature = "std")]
+pub use cloud::WordCloud;
+#[cfg(feature = "std")]
+pub use contact::{Contact, Witness};
+#[cfg(feature = "std")]
+pub use frozen::FrozenSkyline;
+#[cfg(feature = "std")]
+pub use incremental::IncrementalSkyline;
+#[cfg(feature = "std")]
+pub use intervals::IntervalSet;
+#[cfg(feature = "std")]
+pub use key::SkylineKey;
+#[cfg(feature = "std")]
+pub use layout::{GroupId, Layout, LayoutDiff, ShapeId};
+#[cfg(feature = "std")]
+pub use measure::Metric;
+#[cfg(feature = "std")]
+pub use ops::Sweep;
+#[cfg(feature = "std")]
+pub use oriented::OrientedSkyline;
+#[cfg(feature = "std")]
+pub use path::PathCommand;
+#[cfg(feature = "std")]
+pub use periodic::PeriodicSkyline;
+#[cfg(feature = "std")]
+pub use physics::{Body, Impact};
+#[cfg(feature = "std")]
+pub use placement::{Placement, PlacementCache};
+#[cfg(feature = "std")]
+pub use placer::{Evaluated, Overlap, Overlaps, PlacementExplanation, Placer, Verdict};
+#[cfg(feature = "std")]
+pub use scorer::{Candidate, CScorer, Scorer};
+#[cfg(feature = "std")]
+pub use shape::{Shape, ShapeTransform};
+#[cfg(feature = "std")]
+pub use source::SegmentSource;
+#[cfg(feature = "std")]
+pub use transform::UnsupportedTransform;
+#[cfg(feature = "std")]
+pub use units::{CoordinateMismatch, CoordinateSystem, ScaledSkyline};
+
+use piece::Piece;
+#[cfg(feature = "exact")]
+use std::cmp::Ordering;
+use std::mem;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+// Without std, this stands in for it: the core modules get the prelude from
+// here, and the paths that macros like `#[deriving]` expand to lead here.
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use core::{clone, cmp, default, fmt, iter, kinds, mem, num, option, ptr, result, slice};
+    pub use collections::vec;
+
+    pub mod collections {
+        pub use collections::BinaryHeap;
+    }
+
+    pub mod prelude {
+        pub use core::prelude::*;
+        pub use core::default::Default;
+        pub use core::num::Float;
+        pub use collections::slice::{CloneSliceAllocPrelude, SliceAllocPrelude};
+        pub use collections::vec::Vec;
+    }
+}
+
+// The std `vec!`, for when it isn't there.
+#[cfg(not(feature = "std"))]
+macro_rules! vec(
+    ($($e:expr),*) => ({
+        let mut v = ::std::vec::Vec::new();
+        $(v.push($e);)*
+        v
+    });
+    ($($e:expr),+,) => (vec!($($e),+))
+)
+
+pub mod config;
+pub mod construct;
+pub mod dir;
+pub mod error;
+pub mod geom;
+pub mod segment;
+pub mod validate;
+
+mod piece;
+mod simplify;
+
+#[cfg(feature = "std")]
+pub mod accumulator;
+#[cfg(feature = "std")]
+pub mod chunked;
+#[cfg(feature = "std")]
+pub mod cloud;
+#[cfg(feature = "std")]
+pub mod contact;
+#[cfg(feature = "std")]
+pub mod frozen;
+#[cfg(feature = "std")]
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod intervals;
+#[cfg(feature = "std")]
+pub mod key;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod measure;
+#[cfg(feature = "std")]
+pub mod ops;
+#[cfg(feature = "std")]
+pub mod oriented;
+#[cfg(feature = "std")]
+pub mod path;
+#[cfg(feature = "std")]
+pub mod periodic;
+#[cfg(feature = "std")]
+pub mod physics;
+#[cfg(feature = "std")]
+pub mod placement;
+#[cfg(feature = "std")]
+pub mod placer;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod routing;
+#[cfg(feature = "std")]
+pub mod scorer;
+#[cfg(feature = "std")]
+pub mod shape;
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "std")]
+pub mod units;
+
+#[cfg(feature = "serialize")]
+pub mod archive;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+#[cfg(feature = "compact-fmt")]
+pub mod compact;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "rand")]
+pub mod jitter;
+
+#[cfg(feature = "exact")]
+mod robust;
+
+#[cfg(feature = "text")]
+pub mod text;
+
+#[cfg(all(feature = "verify", not(ndebug)))]
+mod verify;
+
+// Without verification, the checks compile to nothing.
+#[cfg(not(all(feature = "verify", not(ndebug))))]
+mod verify {
+    use {Building, Extremum};
+
+    #[inline]
+    pub fn check_merge(_: &[Building], _: &[Building], _: Extremum, _: &[Building]) {}
+
+    #[inline]
+    pub fn check_overlap(_: &[Building], _: &[Building], _: f64) {}
+}
+
+#[cfg(feature = "serialize")]
+mod serial;
+
+#[cfg(test)]
+mod test_utils;
+
 #[deriving(Clone, Show)]
 struct Building {
     m: f64,
@@ -5,11 +217,19 @@ struct Building {
     end: f64
 }
 
-// To prevent numerical instability, we don't allow large slopes.
+// To prevent numerical instability, we don't allow large slopes. This is
+// the default cap; `SkylineConfig::with_max_slope` changes it.
 static MAX_SLOPE: f64 = 1e3;
 
 impl Building {
     fn from_points(x1: f64, y1: f64, x2: f64, y2: f64) -> Building {
+        Building::from_points_capped(x1, y1, x2, y2, MAX_SLOPE)
+    }
+
+    // Steeper segments get the slope `max_slope` (or its negative), and are
+    // raised so that they stay above both of the points.
+    fn from_points_capped(x1: f64, y1: f64, x2: f64, y2: f64, max_slope: f64)
+            -> Building {
         // To avoid NaNs, we deal with vertical line segments separately.
         if x1 == x2 {
             return Building {
@@ -20,7 +240,7 @@ impl Building {
         }
 
         let m_orig = (y2 - y1) / (x2 - x1);
-        let m = m_orig.max(-MAX_SLOPE).min(MAX_SLOPE);
+        let m = m_orig.max(-max_slope).min(max_slope);
         let b = (y1 - m*x1).max(y2 - m*x2);
         Building { m: m, b: b, end: x1.max(x2) }
     }
@@ -30,14 +250,31 @@ impl Building {
         if x.is_nan() { Float::neg_infinity() } else { x }
     }
 
-    fn conceals(&self, other: &Building, x: f64) -> bool {
-        self.conceals_with_intersect(other, x, self.intersection(other))
+    #[cfg(feature = "exact")]
+    fn conceals_with_intersect(&self,
+                               other: &Building,
+                               x: f64,
+                               intersect: f64) -> bool {
+        match robust::compare(self.m, self.b, other.m, other.b, x) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Less) => false,
+            Some(Ordering::Equal) => self.m >= other.m,
+            None => self.conceals_inexact(other, x, intersect),
+        }
     }
 
+    #[cfg(not(feature = "exact"))]
     fn conceals_with_intersect(&self,
                                other: &Building,
                                x: f64,
                                intersect: f64) -> bool {
+        self.conceals_inexact(other, x, intersect)
+    }
+
+    fn conceals_inexact(&self,
+                        other: &Building,
+                        x: f64,
+                        intersect: f64) -> bool {
         if self.m == other.m {
             self.b >= other.b
         } else {
@@ -46,6 +283,16 @@ impl Building {
         }
     }
 
+    // Infinitely high, for `Skyline::solid`. This is the only kind of
+    // building with an infinite height other than empty ones.
+    fn solid(end: f64) -> Building {
+        Building {
+            m: 0.0,
+            b: Float::infinity(),
+            end: end
+        }
+    }
+
     fn empty(end: f64) -> Building {
         Building {
             m: 0.0,
@@ -62,6 +309,14 @@ impl Building {
         }
     }
 
+    fn slide(&mut self, x: f64) {
+        // Moving a sloped building sideways changes its intercept too.
+        if !self.b.is_infinite() {
+            self.b -= self.m * x;
+        }
+        self.end += x;
+    }
+
     fn y(&self, x: f64) -> f64 {
         // We assume that the slope is not infinite. Then
         // the only way to get NaN out of m*x + b is if
@@ -76,24 +331,43 @@ impl Building {
 // https://mail.mozilla.org/pipermail/rust-dev/2014-May/009850.html
 pub trait Direction {
     fn direction_multiplier(_: Option<Self>) -> f64;
+    fn dir(_: Option<Self>) -> Dir;
 }
 
-#[deriving(Show)]
+#[deriving(Clone, Show)]
 pub struct Up;
 
-#[deriving(Show)]
+#[deriving(Clone, Show)]
 pub struct Down;
 
-#[deriving(Show)]
+// Leftward and rightward skylines are functions of y, with their heights
+// measured along x (towards negative and positive x respectively), so they
+// describe the sides of things. Constructors that take points (`single`,
+// `from_polygon`, `from_rects` and so on) take them in the usual
+// coordinates and swap them; everything else, like `height_at`, `bounded`
+// and the domain, is in terms of y.
+#[deriving(Clone, Show)]
 pub struct Left;
 
-#[deriving(Show)]
+#[deriving(Clone, Show)]
 pub struct Right;
 
-impl Direction for Up { fn direction_multiplier(_: Option<Up>) -> f64 { 1.0 } }
-impl Direction for Down { fn direction_multiplier(_: Option<Down>) -> f64 { -1.0 } }
-impl Direction for Left { fn direction_multiplier(_: Option<Left>) -> f64 { -1.0 } }
-impl Direction for Right { fn direction_multiplier(_: Option<Right>) -> f64 { 1.0 } }
+impl Direction for Up {
+    fn direction_multiplier(_: Option<Up>) -> f64 { 1.0 }
+    fn dir(_: Option<Up>) -> Dir { Dir::Up }
+}
+impl Direction for Down {
+    fn direction_multiplier(_: Option<Down>) -> f64 { -1.0 }
+    fn dir(_: Option<Down>) -> Dir { Dir::Down }
+}
+impl Direction for Left {
+    fn direction_multiplier(_: Option<Left>) -> f64 { -1.0 }
+    fn dir(_: Option<Left>) -> Dir { Dir::Left }
+}
+impl Direction for Right {
+    fn direction_multiplier(_: Option<Right>) -> f64 { 1.0 }
+    fn dir(_: Option<Right>) -> Dir { Dir::Right }
+}
 
 pub trait Flip<T> {}
 impl Flip<Up> for Down {}
@@ -101,141 +375,694 @@ impl Flip<Down> for Up {}
 impl Flip<Right> for Left {}
 impl Flip<Left> for Right {}
 
+// `S: Transpose<T>` means that a skyline in direction `T` becomes a skyline
+// in direction `S` when the x and y axes are swapped.
+pub trait Transpose<T> {}
+impl Transpose<Up> for Right {}
+impl Transpose<Right> for Up {}
+impl Transpose<Down> for Left {}
+impl Transpose<Left> for Down {}
+
 #[deriving(Clone, Show)]
 pub struct Skyline<T: Direction> {
+    buildings: Vec<Building>,
+    // The skyline is only defined on this interval, and all of its buildings
+    // are empty outside of it. Anything that tries to overlap the skyline
+    // from outside of its domain hits a wall.
+    domain: Interval,
+    config: SkylineConfig
+}
+
+// Swaps the coordinates of a point for horizontal skylines, which are
+// functions of y.
+fn frame_point<T: Direction>(x: f64, y: f64) -> (f64, f64) {
+    if Direction::dir(None::<T>).is_horizontal() { (y, x) } else { (x, y) }
+}
+
+fn empty_buildings() -> Vec<Building> {
+    vec![Building::empty(Float::infinity())]
+}
+
+fn single_buildings(mult: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Building> {
+    single_buildings_capped(mult, MAX_SLOPE, x1, y1, x2, y2)
+}
+
+fn solid_buildings(x1: f64, x2: f64) -> Vec<Building> {
+    vec![Building::empty(x1.min(x2)),
+         Building::solid(x1.max(x2)),
+         Building::empty(Float::infinity())]
+}
+
+fn single_buildings_capped(mult: f64, max_slope: f64,
+                           x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Building> {
+    let b = Building::from_points_capped(x1, y1 * mult, x2, y2 * mult, max_slope);
+    let start = Building::empty(x1.min(x2));
+    let end = Building::empty(Float::infinity());
+
+    vec![start, b, end]
+}
+
+// Solid parts of a skyline count as infinitely far in the way.
+fn overlap_buildings(in1: &[Building], in2: &[Building]) -> f64 {
+    match overlap_pieces(in1, in2) {
+        Ok(dist) => {
+            verify::check_overlap(in1, in2, dist);
+            dist
+        },
+        Err(_) => Float::infinity(),
+    }
+}
+
+// The sum of two heights that face each other. If either is empty, there's
+// nothing there to touch, even if the other one is infinite.
+fn add_heights(y1: f64, y2: f64) -> f64 {
+    if y1 == Float::neg_infinity() || y2 == Float::neg_infinity() {
+        Float::neg_infinity()
+    } else {
+        y1 + y2
+    }
+}
+
+// The overlap of two pieces over [start, end], or `Err(start)` if one of
+// them is solid and the other one is there too. A solid piece only covers
+// the open interval between its ends, so it doesn't block anything that
+// just touches it from the side (or that only meets it at a breakpoint).
+fn piece_overlap<P: Piece>(b1: &P, b2: &P, start: f64, end: f64) -> Result<f64, f64> {
+    if b1.is_solid() || b2.is_solid() {
+        if start < end && !b1.is_empty() && !b2.is_empty() {
+            Err(start)
+        } else {
+            Ok(Float::neg_infinity())
+        }
+    } else {
+        Ok(add_heights(b1.y_at(start), b2.y_at(start))
+            .max(add_heights(b1.y_at(end), b2.y_at(end))))
+    }
+}
+
+// The overlap, or `Err(x)` if a solid part of one skyline is over a
+// non-empty part of the other, starting at `x`.
+fn overlap_pieces<P: Piece>(in1: &[P], in2: &[P]) -> Result<f64, f64> {
+    let mut dist: f64 = Float::neg_infinity();
+    let mut start: f64 = Float::neg_infinity();
+    let mut i = 0u;
+    let mut j = 0u;
+    let imax = in1.len();
+    let jmax = in2.len();
+
+    while i < imax && j < jmax {
+        // Loop invariant: b1 and b2 start at or after `start`.
+        let b1 = in1[i];
+        let b2 = in2[j];
+
+        let end: f64;
+        if b1.end() < b2.end() {
+            end = b1.end();
+            i += 1;
+        } else {
+            end = b2.end();
+            j += 1;
+        }
+
+        dist = dist.max(try!(piece_overlap(&b1, &b2, start, end)));
+        start = end;
+    }
+
+    Ok(dist)
+}
+
+// Spare storage for `Skyline::merge_using`. It starts out empty and grows to
+// fit the biggest merge it has been used for.
+pub struct MergeScratch {
     buildings: Vec<Building>
 }
 
-impl<T: Direction> Skyline<T> {
-    pub fn empty() -> Box<Skyline<T>> {
-        box Skyline {
-            buildings: vec![Building::empty(Float::infinity())]
+impl MergeScratch {
+    pub fn new() -> MergeScratch {
+        MergeScratch { buildings: Vec::new() }
+    }
+}
+
+// Which envelope a merge keeps: the upper one (the usual thing) or the lower
+// one. When keeping the lower envelope, empty parts of a skyline don't count,
+// so the result is only empty where both inputs are.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum Extremum {
+    Max,
+    Min
+}
+
+// Returns true if `b` beats `other` immediately to the right of `x`.
+fn wins<P: Piece>(b: &P, other: &P, x: f64, intersect: f64, ext: Extremum) -> bool {
+    match ext {
+        Extremum::Max => b.conceals(other, x, intersect),
+        Extremum::Min => {
+            if other.is_empty() {
+                true
+            } else if b.is_empty() {
+                false
+            } else {
+                other.conceals(b, x, intersect)
+            }
+        },
+    }
+}
+
+// The index of the first building that ends at or after `x`. Since the
+// buildings are sorted by their ends, this is a binary search.
+fn search<P: Piece>(bldgs: &[P], x: f64) -> uint {
+    let mut lo = 0u;
+    let mut hi = bldgs.len();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if bldgs[mid].end() < x {
+            lo = mid + 1;
+        } else {
+            hi = mid;
         }
     }
+    lo
+}
 
-    pub fn single(x1: f64, y1: f64, x2: f64, y2: f64) -> Box<Skyline<T>> {
-        let mult = Direction::direction_multiplier(None::<T>);
-        let b = Building::from_points(x1, y1 * mult, x2, y2 * mult);
-        let start = Building::empty(x1.min(x2));
-        let end = Building::empty(Float::infinity());
+// Like `overlap_buildings`, but only looks at [x1, x2], and only visits
+// the buildings in that window.
+fn overlap_buildings_in<P: Piece>(in1: &[P], in2: &[P], x1: f64, x2: f64) -> f64 {
+    let mut dist: f64 = Float::neg_infinity();
+    let mut start = x1;
+    let mut i = search(in1, x1);
+    let mut j = search(in2, x1);
+
+    while i < in1.len() && j < in2.len() && start <= x2 {
+        let b1 = in1[i];
+        let b2 = in2[j];
 
-        box Skyline {
-            buildings: vec![start, b, end]
+        let end: f64;
+        if b1.end() < b2.end() {
+            end = b1.end();
+            i += 1;
+        } else {
+            end = b2.end();
+            j += 1;
+        }
+
+        match piece_overlap(&b1, &b2, start, end.min(x2)) {
+            Ok(d) => dist = dist.max(d),
+            Err(_) => return Float::infinity(),
         }
+        start = end;
     }
 
-    #[cfg(test)]
-    fn from_buildings(bldgs: Vec<Building>) -> Box<Skyline<T>> {
-        box Skyline {
-            buildings: bldgs
+    dist
+}
+
+fn first_intersection<P: Piece>(b: &P,
+                                bldgs: &[P],
+                                mut start: f64,
+                                idx: &mut uint,
+                                ext: Extremum) -> f64 {
+    let idxmax = bldgs.len();
+    while *idx < idxmax {
+        let other = &bldgs[*idx];
+        let intersect = b.intersection_with(other);
+        if wins(b, other, start, intersect, ext) {
+            if intersect > start && intersect < b.end().min(other.end()) {
+                // This building intersects with the other one.
+                return intersect;
+            } else if b.end() < other.end() {
+                // This building ends before the other one.
+                return b.end();
+            } else {
+                // The other building ends first (or they end together).
+                *idx += 1;
+                start = other.end();
+            }
+        } else {
+            return start;
         }
     }
+    return Float::infinity();
+}
 
-    pub fn overlap<S: Flip<T>>(&self, other: &Skyline<S>) -> f64 {
-        let mut dist: f64 = Float::neg_infinity();
-        let mut start: f64 = Float::neg_infinity();
-        let mut i = 0u;
-        let mut j = 0u;
-        let imax = self.buildings.len();
-        let jmax = other.buildings.len();
-
-        while i < imax && j < jmax {
-            // Loop invariant: b1 and b2 start at or after `start`.
-            let b1 = self.buildings[i];
-            let b2 = other.buildings[j];
-
-            let end: f64;
-            if b1.end < b2.end {
-                end = b1.end;
+fn merge_buildings(in1: &[Building],
+                   in2: &[Building],
+                   out: &mut Vec<Building>) {
+    merge_buildings_with(in1, in2, Extremum::Max, out)
+}
+
+fn merge_buildings_with(in1: &[Building],
+                        in2: &[Building],
+                        ext: Extremum,
+                        out: &mut Vec<Building>) {
+    let mut start: f64 = Float::neg_infinity();
+    let mut i = 0u;
+    let mut j = 0u;
+    let imax = in1.len();
+    let jmax = in2.len();
+
+    // Loop invariant: if j == jmax then i == imax-1.
+    while i < imax && j < jmax {
+        let b1 = &in1[i];
+        let b2 = &in2[j];
+
+        if wins(b1, b2, start, b1.intersection_with(b2), ext) {
+            start = first_intersection(b1, in2, start, &mut j, ext);
+            out.push(b1.chop(start));
+
+            // If i == imax-1 then b1.end == inf. If in addition,
+            // start >= b1.end then we must have j == jmax-1
+            // (i.e., we're done with with input skylines).
+            if start >= b1.end {
                 i += 1;
-            } else {
-                end = b2.end;
+            }
+        } else {
+            start = first_intersection(b2, in1, start, &mut i, ext);
+            out.push(b2.chop(start));
+            if start >= b2.end {
                 j += 1;
             }
+        }
+    }
 
-            dist = dist.max(b1.y(start) + b2.y(start));
-            dist = dist.max(b1.y(end) + b2.y(end));
-
-            start = end;
-        }
-
-        dist
-    }
-
-    fn first_intersection(b: &Building,
-                          bldgs: &[Building],
-                          mut start: f64,
-                          idx: &mut uint) -> f64 {
-        let idxmax = bldgs.len();
-        while *idx < idxmax {
-            let other = &bldgs[*idx];
-            let intersect = b.intersection(other);
-            if b.conceals_with_intersect(other, start, intersect) {
-                if intersect > start && intersect < b.end.min(other.end) {
-                    // This building intersects with the other one.
-                    return intersect;
-                } else if b.end < other.end {
-                    // This building ends before the other one.
-                    return b.end;
-                } else {
-                    // The other building ends first (or they end together).
-                    *idx += 1;
-                    start = other.end;
-                }
-            } else {
-                return start;
-            }
+    verify::check_merge(in1, in2, ext, out.as_slice());
+}
+
+// The non-empty, finite buildings, together with their starting points.
+fn pieces(bldgs: &[Building]) -> Vec<(f64, Building)> {
+    let mut out = Vec::new();
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if b.b > Float::neg_infinity() && start.is_finite() && b.end.is_finite() {
+            out.push((start, *b));
         }
-        return Float::infinity();
+        start = b.end;
     }
+    out
+}
 
-    fn internal_merge(in1: &[Building],
-                      in2: &[Building],
-                      out: &mut Vec<Building>) {
-        let mut start: f64 = Float::neg_infinity();
-        let mut i = 0u;
-        let mut j = 0u;
-        let imax = in1.len();
-        let jmax = in2.len();
+// Restricts a list of buildings to the interval [x1, x2]. The result is
+// empty outside that interval.
+fn clip_buildings(bldgs: &[Building], x1: f64, x2: f64) -> Vec<Building> {
+    let mut out = if x1 > Float::neg_infinity() {
+        vec![Building::empty(x1)]
+    } else {
+        Vec::new()
+    };
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if start >= x2 {
+            break;
+        }
+        if b.end > x1 {
+            out.push(b.chop(b.end.min(x2)));
+        }
+        start = b.end;
+    }
 
-        // Loop invariant: if j == jmax then i == imax-1.
-        while i < imax && j < jmax {
-            let b1 = &in1[i];
-            let b2 = &in2[j];
+    if x2 < Float::infinity() {
+        out.push(Building::empty(Float::infinity()));
+    }
+    out
+}
 
-            if b1.conceals(b2, start) {
-                start = Skyline::<T>::first_intersection(b1, in2, start, &mut j);
-                out.push(b1.chop(start));
+// Rounds the breakpoints of a list of buildings (and their heights, if
+// `heights` is true) to multiples of `grid`. Buildings that shrink to
+// nothing are dropped, neighbors that end up on the same line are
+// combined, and buildings that go off to infinity keep their slopes.
+fn snap_buildings(bldgs: &[Building], grid: f64, heights: bool, max_slope: f64)
+        -> Vec<Building> {
+    let round = |v: f64| (v / grid).round() * grid;
+    let round_y = |y: f64| if heights { round(y) } else { y };
+    let mut out: Vec<Building> = Vec::with_capacity(bldgs.len());
+    let mut start: f64 = Float::neg_infinity();
+    let mut snapped_start: f64 = Float::neg_infinity();
 
-                // If i == imax-1 then b1.end == inf. If in addition,
-                // start >= b1.end then we must have j == jmax-1
-                // (i.e., we're done with with input skylines).
-                if start >= b1.end {
-                    i += 1;
-                }
+    for b in bldgs.iter() {
+        let end = round(b.end);
+        if b.b.is_infinite() {
+            // Empty and solid buildings just snap their ends.
+            let extend = match out.last() {
+                Some(prev) => prev.b == b.b,
+                None => false,
+            };
+            if extend {
+                out.last_mut().unwrap().end = end;
             } else {
-                start = Skyline::<T>::first_intersection(b2, in1, start, &mut i);
-                out.push(b2.chop(start));
-                if start >= b2.end {
-                    j += 1;
-                }
+                out.push(b.chop(end));
+            }
+        } else if !start.is_finite() || !b.end.is_finite() {
+            out.push(b.chop(end));
+        } else if start == b.end {
+            out.push(Building { m: 0.0, b: round_y(b.b), end: end });
+        } else if snapped_start < end {
+            let new = Building::from_points_capped(snapped_start, round_y(b.y(start)),
+                                                   end, round_y(b.y(b.end)), max_slope);
+            // Pieces that snap onto the same line are combined.
+            let extend = match out.last() {
+                Some(prev) => prev.m == new.m && prev.b == new.b,
+                None => false,
+            };
+            if extend {
+                out.last_mut().unwrap().end = end;
+            } else {
+                out.push(new);
             }
         }
+        start = b.end;
+        snapped_start = end;
+    }
+    out
+}
+
+// The smallest interval containing all of the non-empty buildings, if there
+// are any.
+fn support(bldgs: &[Building]) -> Option<(f64, f64)> {
+    let mut lo: f64 = Float::infinity();
+    let mut hi: f64 = Float::neg_infinity();
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if b.b > Float::neg_infinity() {
+            lo = lo.min(start);
+            hi = hi.max(b.end);
+        }
+        start = b.end;
+    }
+
+    if lo <= hi { Some((lo, hi)) } else { None }
+}
+
+fn within(bldgs: &[Building], domain: &Interval) -> bool {
+    match support(bldgs) {
+        Some((lo, hi)) => domain.contains(lo) && domain.contains(hi),
+        None => true,
+    }
+}
+
+// Merges a collection of building lists pairwise, so that the total work is
+// O(n log n) in the number of lists instead of the O(n^2) that comes from
+// merging them into an accumulator one at a time.
+fn merge_many(mut lists: Vec<Vec<Building>>) -> Vec<Building> {
+    if lists.is_empty() {
+        return empty_buildings();
+    }
+
+    while lists.len() > 1 {
+        lists = merge_round(lists);
+    }
+    lists.pop().unwrap()
+}
+
+// One round of `merge_many`: merges the first list with the second, the
+// third with the fourth, and so on. An odd one out is passed through.
+fn merge_round(lists: Vec<Vec<Building>>) -> Vec<Vec<Building>> {
+    let mut next = Vec::with_capacity((lists.len() + 1) / 2);
+    let mut iter = lists.into_iter();
+    loop {
+        match (iter.next(), iter.next()) {
+            (Some(a), Some(b)) => {
+                let mut out = Vec::with_capacity(a.len() + b.len());
+                merge_buildings(a.as_slice(), b.as_slice(), &mut out);
+                next.push(out);
+            },
+            (Some(a), None) => {
+                next.push(a);
+                break;
+            },
+            _ => break,
+        }
+    }
+    next
+}
+
+impl<T: Direction> Skyline<T> {
+    pub fn empty() -> Skyline<T> {
+        Skyline::from_buildings(empty_buildings())
+    }
+
+    // The skyline of a single segment. If x1 == x2 the segment is a vertical
+    // wall (for a horizontal skyline, if y1 == y2). The coordinates aren't
+    // checked: NaNs give a skyline that breaks every operation on it, so use
+    // `try_single` for untrusted input.
+    pub fn single(x1: f64, y1: f64, x2: f64, y2: f64) -> Skyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+        Skyline::from_buildings(single_buildings(mult, u1, v1, u2, v2))
+    }
+
+    // An impassable obstacle over [x1, x2]: the skyline is infinitely high
+    // there, so anything that overlaps it at all can't get past (`overlap`
+    // is infinite, `try_overlap` says `Blocked::Solid`, and `fit` won't put
+    // anything there). Merging keeps it as it is.
+    //
+    // The obstacle covers the open interval (x1, x2) and blocks anything
+    // that is non-empty on some part of it with positive width. Its ends
+    // don't block anything, so something that only touches it from the side
+    // (whichever skyline is passed to `overlap` first) just gets the
+    // overlap with whatever is next to it. Heights that are merely huge,
+    // like `Float::max_value()`, are ordinary heights; use this instead of
+    // those.
+    pub fn solid(x1: f64, x2: f64) -> Skyline<T> {
+        Skyline::from_buildings(solid_buildings(x1, x2))
+    }
+
+    // An empty skyline that is only defined on [x1, x2], like the inside of
+    // a container. As with `single`, the bounds aren't checked; see
+    // `try_bounded`.
+    pub fn bounded(x1: f64, x2: f64) -> Skyline<T> {
+        let mut sky = Skyline::empty();
+        sky.bound(x1, x2);
+        sky
+    }
+
+    fn from_buildings(bldgs: Vec<Building>) -> Skyline<T> {
+        Skyline {
+            buildings: bldgs,
+            domain: Interval::unbounded(),
+            config: SkylineConfig::new()
+        }
+    }
+
+    fn apply_config(&mut self) {
+        match self.config.snap_grid {
+            Some(grid) => {
+                let heights = self.config.snap_heights;
+                self.buildings = snap_buildings(self.buildings.as_slice(), grid, heights,
+                                                self.config.max_slope);
+            },
+            None => {},
+        }
+        match self.config.simplify_tolerance {
+            Some(tol) => self.simplify(tol),
+            None => {},
+        }
+    }
+
+    pub fn dir(&self) -> Dir {
+        Direction::dir(None::<T>)
+    }
+
+    pub fn domain(&self) -> Interval {
+        self.domain
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.domain.is_bounded()
+    }
+
+    // True if there are no buildings, even if the domain is bounded.
+    pub fn is_empty(&self) -> bool {
+        support(self.buildings.as_slice()).is_none()
+    }
+
+    // If either skyline has buildings outside the other's domain, the
+    // overlap is infinite. If the skylines are never non-empty in the same
+    // place (in particular, if either one is empty), there is no contact and
+    // the overlap is negative infinity.
+    pub fn overlap<S: Flip<T>>(&self, other: &Skyline<S>) -> f64 {
+        if !within(other.buildings.as_slice(), &self.domain)
+                || !within(self.buildings.as_slice(), &other.domain) {
+            return Float::infinity();
+        }
+        overlap_buildings(self.buildings.as_slice(), other.buildings.as_slice())
+    }
+
+    // Like `overlap`, but says why if the overlap is infinite because
+    // `other` can't get past this skyline at all, instead of returning
+    // infinity for that too.
+    pub fn try_overlap<S: Flip<T>>(&self, other: &Skyline<S>) -> Result<f64, Blocked> {
+        if !within(other.buildings.as_slice(), &self.domain)
+                || !within(self.buildings.as_slice(), &other.domain) {
+            return Err(Blocked::OutsideDomain);
+        }
+        match overlap_pieces(self.buildings.as_slice(), other.buildings.as_slice()) {
+            Ok(dist) => {
+                verify::check_overlap(self.buildings.as_slice(), other.buildings.as_slice(),
+                                      dist);
+                Ok(dist)
+            },
+            Err(x) => Err(Blocked::Solid(x)),
+        }
+    }
+
+    // How far `other` has to move (away from this skyline) so that there's
+    // a vertical gap of at least `gap` between them everywhere. This is the
+    // same as bumping `other` by `-gap` before calling `overlap`, but it
+    // doesn't touch either skyline. For clearance in every direction, not
+    // just vertically, `dilate` this skyline instead.
+    //
+    // A negative gap allows the skylines to interpenetrate by up to `-gap`.
+    // If the overlap is infinite, the gap makes no difference: skylines
+    // with nothing facing each other stay at negative infinity, whatever
+    // the gap, and solid obstacles stay at positive infinity.
+    pub fn overlap_with_gap<S: Flip<T>>(&self, other: &Skyline<S>, gap: f64) -> f64 {
+        let dist = self.overlap(other);
+        if dist.is_infinite() { dist } else { dist + gap }
+    }
+
+    // The overlap of the parts of the two skylines in [x1, x2]. This takes
+    // O(log n) time plus the number of buildings in the window. Unlike
+    // `overlap`, it doesn't look at the domains.
+    pub fn overlap_in_range<S: Flip<T>>(&self, other: &Skyline<S>, x1: f64, x2: f64)
+            -> f64 {
+        overlap_buildings_in(self.buildings.as_slice(), other.buildings.as_slice(), x1, x2)
     }
 
+    // The merged skyline is defined on the intersection of the two domains.
+    // Merging with `Skyline::empty()` leaves the skyline exactly as it was,
+    // and merging anything into an empty skyline gives the other skyline's
+    // buildings exactly, so that equal skylines stay equal.
     pub fn merge(&mut self, other: &Skyline<T>) {
-        let mut new_bldgs = Vec::new();
-        Skyline::<T>::internal_merge(self.buildings.as_slice(),
-                                     other.buildings.as_slice(),
-                                     &mut new_bldgs);
+        self.merge_with(other, Extremum::Max);
+    }
+
+    // Like `merge`, but `Extremum::Min` keeps the lower envelope instead
+    // (e.g. for accumulating ceilings).
+    pub fn merge_with(&mut self, other: &Skyline<T>, ext: Extremum) {
+        if other.is_identity() {
+            return;
+        }
+
+        if self.is_identity() {
+            self.buildings = other.buildings.clone();
+        } else {
+            let mut new_bldgs = Vec::new();
+            merge_buildings_with(self.buildings.as_slice(),
+                                 other.buildings.as_slice(),
+                                 ext,
+                                 &mut new_bldgs);
+            self.buildings = new_bldgs;
+        }
+        self.finish_merge(other);
+    }
+
+    // Sets `out` to the result of merging `other` into a copy of this
+    // skyline. The buildings are written into `out`'s existing buffer, so a
+    // loop that alternates between two skylines stops allocating once the
+    // buffers are big enough.
+    pub fn merge_into(&self, other: &Skyline<T>, out: &mut Skyline<T>) {
+        out.buildings.clear();
+        out.domain = self.domain;
+        out.config = self.config.clone();
+        if other.is_identity() {
+            out.buildings.push_all(self.buildings.as_slice());
+            return;
+        }
+
+        if self.is_identity() {
+            out.buildings.push_all(other.buildings.as_slice());
+        } else {
+            merge_buildings(self.buildings.as_slice(), other.buildings.as_slice(),
+                            &mut out.buildings);
+        }
+        out.finish_merge(other);
+    }
+
+    // The same as `merge`, but the buildings are written into `scratch`,
+    // and this skyline's old buildings are left in `scratch` for next time.
+    // A loop that merges into the same skyline over and over can reuse one
+    // scratch buffer, and then the merge itself doesn't allocate once the
+    // buffers are big enough (bounding and snapping still do).
+    pub fn merge_using(&mut self, other: &Skyline<T>, scratch: &mut MergeScratch) {
+        if other.is_identity() {
+            return;
+        }
+
+        scratch.buildings.clear();
+        if self.is_identity() {
+            scratch.buildings.push_all(other.buildings.as_slice());
+        } else {
+            merge_buildings(self.buildings.as_slice(), other.buildings.as_slice(),
+                            &mut scratch.buildings);
+        }
+        mem::swap(&mut self.buildings, &mut scratch.buildings);
+        self.finish_merge(other);
+    }
+
+    // Empty and unbounded, so that merging with it changes nothing.
+    fn is_identity(&self) -> bool {
+        !self.is_bounded() && self.is_empty()
+    }
+
+    // Checks, in debug builds only, that an operation left the skyline
+    // valid. Every public method that modifies a skyline ends with this.
+    // Slopes are only checked for being finite, since merging in a skyline
+    // with a laxer slope cap legitimately keeps its steeper buildings.
+    #[inline]
+    fn debug_assert_valid(&self) {
+        debug_assert!(self.validate_with_max_slope(Float::max_value()).is_ok(),
+                      "{}: {}", self.validate_with_max_slope(Float::max_value()), self);
+    }
+
+    // The part of a merge that comes after merging the buildings: fixing up
+    // the domain and applying the config.
+    fn finish_merge(&mut self, other: &Skyline<T>) {
+        if other.is_bounded() {
+            self.bound(other.domain.lo, other.domain.hi);
+        } else if self.is_bounded() {
+            let d = self.domain;
+            self.clip(d.lo, d.hi);
+        }
+        self.apply_config();
+        self.debug_assert_valid();
+    }
+
+    // Like `merge`, but returns the part of the x axis where the envelope
+    // may have changed (it might not have changed everywhere in there), or
+    // `None` if it can't have changed. Merging a bounded skyline changes
+    // the domain, which counts as changing everything.
+    pub fn merge_tracked(&mut self, other: &Skyline<T>) -> Option<Interval> {
+        self.merge(other);
+        if other.is_bounded() {
+            return Some(Interval::unbounded());
+        }
+        match support(other.buildings.as_slice()) {
+            Some((lo, hi)) => self.domain.intersection(&Interval { lo: lo, hi: hi }),
+            None => None,
+        }
+    }
+
+    // Removes everything outside of [x1, x2].
+    pub fn clip(&mut self, x1: f64, x2: f64) {
+        self.buildings = clip_buildings(self.buildings.as_slice(), x1, x2);
+        self.debug_assert_valid();
+    }
 
-        self.buildings = new_bldgs;
+    // Removes everything outside of [x1, x2], and makes the skyline
+    // undefined there.
+    pub fn bound(&mut self, x1: f64, x2: f64) {
+        // If the domains don't intersect, the result is an empty interval
+        // that contains nothing.
+        let d = self.domain;
+        self.domain = Interval { lo: d.lo.max(x1), hi: d.hi.min(x2) };
+        let d = self.domain;
+        self.clip(d.lo, d.hi);
     }
 
     pub fn slide(&mut self, x: f64) {
         for b in self.buildings.iter_mut() {
-            b.end += x
+            b.slide(x)
         }
+        self.domain = self.domain.translate(x);
+        self.debug_assert_valid();
     }
 
     pub fn bump(&mut self, y: f64) {
@@ -243,13 +1070,42 @@ impl<T: Direction> Skyline<T> {
         for b in self.buildings.iter_mut() {
             b.b += y
         }
+        self.debug_assert_valid();
+    }
+
+    // The same skyline, reflected across the line y = x: an upward skyline
+    // over x becomes a rightward skyline over y, and a downward one becomes
+    // a leftward one. Since transposing pairs up directions with the same
+    // multiplier, the buildings don't change.
+    pub fn transpose<S: Transpose<T>>(&self) -> Skyline<S> {
+        Skyline {
+            buildings: self.buildings.clone(),
+            domain: self.domain,
+            config: self.config.clone()
+        }
+    }
+
+    // The same skyline, reflected across the x axis (so an upward skyline
+    // becomes a downward one, with every height negated). Like transposing,
+    // this leaves the buildings alone.
+    pub fn mirrored<S: Flip<T>>(&self) -> Skyline<S> {
+        Skyline {
+            buildings: self.buildings.clone(),
+            domain: self.domain,
+            config: self.config.clone()
+        }
+    }
+}
+
+impl<T: Direction> Default for Skyline<T> {
+    fn default() -> Skyline<T> {
+        Skyline::empty()
     }
 }
 
 #[cfg(test)]
 mod test {
     use test_utils::ApproxEq;
-    mod test_utils;
 
     impl<'a> ApproxEq for &'a Building {
         fn approx_eq<'b>(self, other: &'b Building) -> bool {
@@ -274,11 +1130,44 @@ mod test {
         }
     }
 
+    fn identical(a: &[Building], b: &[Building]) -> bool {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| {
+            x.m == y.m && x.b == y.b && x.end == y.end
+        })
+    }
+
+    #[test]
+    fn empty_is_identity() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(0.5, 2.0, 2.0, 0.0));
+        let before = sky.clone();
+
+        sky.merge(&Skyline::empty());
+        sky.merge_with(&Skyline::empty(), Extremum::Min);
+        assert!(identical(sky.buildings.as_slice(), before.buildings.as_slice()));
+
+        let mut out = Skyline::empty();
+        sky.merge_into(&Skyline::empty(), &mut out);
+        assert!(identical(out.buildings.as_slice(), before.buildings.as_slice()));
+
+        let mut empty = Skyline::<Up>::empty();
+        empty.merge(&sky);
+        assert!(identical(empty.buildings.as_slice(), before.buildings.as_slice()));
+
+        assert_eq!(sky.overlap(&Skyline::<Down>::empty()), Float::neg_infinity());
+        assert!(sky.overlap_witness(&Skyline::<Down>::empty()).is_none());
+        assert!(Skyline::<Up>::merge_all(vec![]).is_empty());
+
+        // An empty skyline with a bounded domain still bounds the result.
+        sky.merge(&Skyline::bounded(0.0, 1.5));
+        assert_eq!(sky.domain(), Interval::new(0.0, 1.5));
+    }
+
     #[test]
     fn basic_skyline_merge() {
         let mut sky1 = Skyline::<Up>::single(-2.0, 0.0, -1.0, 0.0);
         let mut sky2 = Skyline::<Up>::single(1.0, 0.0, 2.0, 0.0);
-        sky2.merge(&*sky1);
+        sky2.merge(&sky1);
 
         let target = Skyline::from_buildings(
             vec!(Building::empty(-2.0),
@@ -287,9 +1176,45 @@ mod test {
                  Building { m: 0.0, b: 0.0, end: 2.0 },
                  Building::empty(Float::infinity())));
 
-        assert!(sky2.approx_eq(&*target));
-        sky1.merge(&*sky2);
-        assert!(sky1.approx_eq(&*target));
+        assert!(sky2.approx_eq(&target));
+        sky1.merge(&sky2);
+        assert!(sky1.approx_eq(&target));
+    }
+
+    #[test]
+    fn merge_into_reuses_buffer() {
+        let a = Skyline::<Up>::single(-2.0, 0.0, -1.0, 0.0);
+        let b = Skyline::<Up>::single(1.0, 0.0, 2.0, 0.0);
+        let mut out: Skyline<Up> = Default::default();
+        out.merge(&Skyline::single(5.0, 5.0, 6.0, 5.0));
+        out.buildings.reserve(16);
+        let cap = out.buildings.capacity();
+
+        a.merge_into(&b, &mut out);
+        let mut expected = a.clone();
+        expected.merge(&b);
+        assert!(out.approx_eq(&expected));
+        assert_eq!(out.buildings.capacity(), cap);
+    }
+
+    #[test]
+    fn merge_using_reuses_buffers() {
+        let mut sky = Skyline::<Up>::empty();
+        let mut expected = Skyline::<Up>::empty();
+        let mut scratch = MergeScratch::new();
+        let mut caps = Vec::new();
+        for i in range(0u, 20) {
+            let x = (i % 4) as f64;
+            let other = Skyline::single(x, i as f64, x + 1.0, 0.0);
+            sky.merge_using(&other, &mut scratch);
+            expected.merge(&other);
+            caps.push((sky.buildings.capacity(), scratch.buildings.capacity()));
+        }
+        assert!(sky.approx_eq(&expected));
+        // The two buffers just trade places once they're big enough.
+        let (a, b) = caps[caps.len() - 1];
+        let (c, d) = caps[caps.len() - 2];
+        assert_eq!((a, b), (d, c));
     }
 
     #[test]
@@ -297,12 +1222,249 @@ mod test {
         let sky1 = Skyline::<Up>::single(-1.0, 3.0, 1.0, 3.0);
         let sky2 = Skyline::<Down>::single(-1.0, 2.0, 1.0, 2.0);
 
-        let d = sky1.overlap(&*sky2);
+        let d = sky1.overlap(&sky2);
         assert!(d.approx_eq(1.0), "d = {}, should be 1.0", d);
     }
 
+    #[test]
+    fn overlap_gap() {
+        let floor = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+        let item = Skyline::<Down>::single(1.0, 3.0, 3.0, 3.0);
+        assert!(floor.overlap_with_gap(&item, 0.5).approx_eq(-1.5));
+
+        let mut moved = item.clone();
+        moved.bump(-0.5);
+        assert!(floor.overlap_with_gap(&item, 0.5).approx_eq(floor.overlap(&moved)));
+        assert_eq!(floor.overlap_with_gap(&Skyline::<Down>::empty(), 1.0),
+                   Float::neg_infinity());
+
+        // A negative gap lets them interpenetrate.
+        assert!(floor.overlap_with_gap(&item, -0.5).approx_eq(-2.5));
+        let mut moved = item.clone();
+        moved.bump(0.5);
+        assert!(floor.overlap_with_gap(&item, -0.5).approx_eq(floor.overlap(&moved)));
+
+        // Infinite overlaps stay as they are, even with an infinite gap.
+        let inf: f64 = Float::infinity();
+        assert_eq!(floor.overlap_with_gap(&Skyline::<Down>::empty(), inf),
+                   Float::neg_infinity());
+        let wall = Skyline::<Up>::solid(0.0, 2.0);
+        assert_eq!(wall.overlap_with_gap(&item, -inf), Float::infinity());
+        assert_eq!(floor.overlap_with_gap(&item, inf), Float::infinity());
+    }
+
+    #[test]
+    fn slide_and_bump() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        sky.slide(2.0);
+        sky.bump(1.0);
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(2.0),
+                 Building { m: 1.0, b: -1.0, end: 3.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+
+        let d = sky.overlap(&Skyline::<Down>::single(2.0, 0.0, 3.0, 0.0));
+        assert!(d.approx_eq(2.0), "d = {}, should be 2.0", d);
+    }
+
+    #[test]
+    fn clip() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 4.0, 4.0);
+        sky.clip(1.0, 2.0);
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(1.0),
+                 Building { m: 1.0, b: 0.0, end: 2.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+        assert!(!sky.is_bounded());
+    }
+
+    #[test]
+    fn bounded_walls() {
+        let mut container = Skyline::<Up>::bounded(0.0, 10.0);
+        container.merge(&Skyline::single(-5.0, 1.0, 5.0, 1.0));
+        assert_eq!(container.domain(), Interval::new(0.0, 10.0));
+
+        let inside = Skyline::<Down>::single(1.0, 0.0, 2.0, 0.0);
+        assert!(container.overlap(&inside).approx_eq(1.0));
+        let outside = Skyline::<Down>::single(9.0, 0.0, 11.0, 0.0);
+        assert_eq!(container.overlap(&outside), Float::infinity());
+
+        container.slide(1.0);
+        assert_eq!(container.domain(), Interval::new(1.0, 11.0));
+        assert!(container.overlap(&outside).approx_eq(Float::neg_infinity()));
+    }
+
+    #[test]
+    fn merge_min() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        sky.merge_with(&Skyline::single(0.0, 2.0, 2.0, 0.0), Extremum::Min);
+        sky.merge_with(&Skyline::single(3.0, 1.0, 4.0, 1.0), Extremum::Min);
+
+        let target = Skyline::from_buildings(
+            vec!(Building::empty(0.0),
+                 Building { m: 1.0, b: 0.0, end: 1.0 },
+                 Building { m: -1.0, b: 2.0, end: 2.0 },
+                 Building::empty(3.0),
+                 Building { m: 0.0, b: 1.0, end: 4.0 },
+                 Building::empty(Float::infinity())));
+        assert!(sky.approx_eq(&target));
+    }
+
+    #[test]
+    fn overlap_window() {
+        let mut sky1 = Skyline::<Up>::single(0.0, 5.0, 1.0, 5.0);
+        sky1.merge(&Skyline::single(2.0, 0.0, 4.0, 2.0));
+        let sky2 = Skyline::<Down>::single(-10.0, 0.0, 10.0, 0.0);
+
+        assert!(sky1.overlap_in_range(&sky2, 2.0, 3.0).approx_eq(1.0));
+        assert!(sky1.overlap_in_range(&sky2, 1.5, 10.0).approx_eq(2.0));
+        assert!(sky1.overlap_in_range(&sky2, 1.0, 1.5).approx_eq(5.0));
+        assert!(sky1.overlap_in_range(&sky2, -20.0, 20.0).approx_eq(sky1.overlap(&sky2)));
+    }
+
+    #[test]
+    fn transpose() {
+        // A triangle, and the same triangle with x and y swapped.
+        let pts = [(0.0, 0.0), (2.0, 0.0), (1.0, 1.0)];
+        let swapped: Vec<(f64, f64)> = pts.iter().map(|&(x, y)| (y, x)).collect();
+
+        let up = Skyline::<Up>::from_polygon(&pts);
+        let right: Skyline<Right> = up.transpose();
+        assert!(right.approx_eq(&Skyline::<Right>::from_polygon(swapped.as_slice())));
+
+        let left = Skyline::<Left>::from_polygon(&pts);
+        let down: Skyline<Down> = left.transpose();
+        assert!(down.approx_eq(&Skyline::<Down>::from_polygon(swapped.as_slice())));
+
+        let d = right.overlap(&Skyline::<Left>::single(3.0, 0.0, 3.0, 2.0));
+        assert!(d.approx_eq(-2.0), "d = {}, should be -2.0", d);
+    }
+
+    #[test]
+    fn solid_obstacles() {
+        use {Blocked, Interval};
+        use placement::overlap_vs_offset;
+
+        let mut floor = Skyline::<Up>::single(0.0, 0.0, 10.0, 0.0);
+        floor.merge(&Skyline::solid(4.0, 5.0));
+        floor.merge(&Skyline::single(4.5, 1.0, 6.0, 1.0));
+        assert_eq!(floor.validate(), Ok(()));
+        assert_eq!(floor.height_at(4.5), Float::infinity());
+        assert!(floor.height_at(5.5).approx_eq(1.0));
+
+        // Touching the obstacle from the side is fine, overlapping it isn't,
+        // whichever skyline comes first.
+        let beside = Skyline::<Down>::single(3.0, 1.0, 4.0, 1.0);
+        assert!(floor.overlap(&beside).approx_eq(-1.0));
+        assert!(beside.overlap(&floor).approx_eq(-1.0));
+        assert_eq!(beside.try_overlap(&floor), Ok(beside.overlap(&floor)));
+        let above = Skyline::<Down>::single(4.5, 1e300, 6.0, 1e300);
+        assert_eq!(floor.overlap(&above), Float::infinity());
+        assert_eq!(above.overlap(&floor), Float::infinity());
+        assert_eq!(floor.try_overlap(&above), Err(Blocked::Solid(4.5)));
+        assert_eq!(above.try_overlap(&floor), Err(Blocked::Solid(4.5)));
+        let outside = Skyline::<Down>::bounded(0.0, 1.0);
+        assert_eq!(floor.try_overlap(&outside), Err(Blocked::OutsideDomain));
+
+        // Sliding something along the floor, it's blocked exactly while it
+        // covers part of (4, 5).
+        let item = Skyline::<Down>::single(0.0, 0.0, 2.0, 0.0);
+        let profile = overlap_vs_offset(&floor, &item, Interval::new(-10.0, 10.0), 1000);
+        assert_eq!(profile.validate(), Ok(()));
+        assert!(profile.height_at(1.0).approx_eq(0.0));
+        assert_eq!(profile.height_at(2.5), Float::infinity());
+        assert_eq!(profile.height_at(4.5), Float::infinity());
+        assert!(profile.height_at(7.5).approx_eq(0.0));
+
+        // The hull and fillets are taken of the rest, and leave the obstacle
+        // alone.
+        let hull = floor.hull();
+        assert_eq!(hull.validate(), Ok(()));
+        assert_eq!(hull.height_at(4.5), Float::infinity());
+        assert!(hull.height_at(8.0).approx_eq(0.5));
+        let mut rounded = floor.clone();
+        rounded.fillet(0.5, 0.01);
+        assert_eq!(rounded.validate(), Ok(()));
+        assert_eq!(rounded.height_at(4.5), Float::infinity());
+        assert!(rounded.height_at(5.5) <= 1.0 && rounded.height_at(5.5) > 0.0);
+
+        let p = floor.fit(&item, (2.0, 8.0));
+        assert!(p.dy.approx_eq(0.0), "dy = {}", p.dy);
+        assert!(p.dx <= 2.0 || p.dx >= 6.0, "dx = {}", p.dx);
+
+        floor.set_config(SkylineConfig::new().with_grid(0.5).with_simplification(0.1));
+        assert_eq!(floor.validate(), Ok(()));
+        assert_eq!(floor.height_at(4.5), Float::infinity());
+    }
+
+    #[test]
+    fn horizontal_envelopes() {
+        use {Rect, Shape};
+
+        // A triangle pointing right, and a box to its right.
+        let tri = Shape::from_polygon(&[(0.0, 0.0), (2.0, 1.0), (0.0, 2.0)]);
+        let right = tri.skyline::<Right>();
+        assert!(right.height_at(1.0).approx_eq(2.0));
+        assert!(right.height_at(0.5).approx_eq(1.0));
+        assert_eq!(right.height_at(3.0), Float::neg_infinity());
+        let left = tri.skyline::<Left>();
+        assert!(left.height_at(1.5).approx_eq(0.0));
+
+        let boxes = Skyline::<Left>::from_rects(&[Rect::new(3.0, 0.5, 4.0, 1.5)]);
+        assert!(boxes.approx_eq_envelope(&Skyline::single(3.0, 0.5, 3.0, 1.5), 1e-9));
+        // The box can move left until it touches the tip of the triangle.
+        assert!(right.overlap(&boxes).approx_eq(-1.0));
+        let mut both = right.clone();
+        both.merge(&Skyline::from_rects(&[Rect::new(3.0, 0.5, 4.0, 1.5)]));
+        assert!(both.height_at(1.0).approx_eq(4.0));
+        assert!(both.height_at(0.25).approx_eq(0.5));
+    }
+
+    #[test]
+    fn mirror() {
+        let up = Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0);
+        let down: Skyline<Down> = up.mirrored();
+        assert!(down.approx_eq(&Skyline::<Down>::single(0.0, -1.0, 2.0, -3.0)));
+
+        // Stacking a motif on a copy of itself.
+        let mut above = up.mirrored::<Down>();
+        above.bump(10.0);
+        let d = up.overlap(&above);
+        assert!(d.approx_eq(-4.0), "d = {}, should be -4.0", d);
+    }
+
+    fn assert_thread_safe<T: Send + Sync>() {}
+
+    // Everything can be shared between threads, except for `CScorer`, whose
+    // context pointer belongs to the caller, and the types that borrow
+    // (which are as thread safe as what they borrow).
+    #[test]
+    fn thread_safe_types() {
+        use {ChunkedSkyline, DirSkyline, FrozenSkyline, IncrementalSkyline, Layout};
+        use {OrientedSkyline, PeriodicSkyline, PlacementCache, Placer, ScaledSkyline};
+        use {Shape, SkylineAccumulator, WordCloud};
+
+        assert_thread_safe::<Skyline<Up>>();
+        assert_thread_safe::<Skyline<Left>>();
+        assert_thread_safe::<SkylineAccumulator<Down>>();
+        assert_thread_safe::<ChunkedSkyline<Up>>();
+        assert_thread_safe::<DirSkyline>();
+        assert_thread_safe::<FrozenSkyline<Up>>();
+        assert_thread_safe::<IncrementalSkyline<Up>>();
+        assert_thread_safe::<OrientedSkyline>();
+        assert_thread_safe::<PeriodicSkyline<Up>>();
+        assert_thread_safe::<ScaledSkyline<Up>>();
+        assert_thread_safe::<Shape>();
+        assert_thread_safe::<Layout>();
+        assert_thread_safe::<Placer>();
+        assert_thread_safe::<PlacementCache>();
+        assert_thread_safe::<WordCloud>();
+    }
+
     // TODO: once compilefail tests are available, add some to make
     // sure we can't compare skylines with different directions.
-
-    // TODO: test slide and bump
 }
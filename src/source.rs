@@ -0,0 +1,98 @@
+use {Direction, Rect, Segment, Shape, Skyline};
+use {frame_point, merge_many, single_buildings};
+
+// Anything made of straight edges that a skyline can be built from, so that
+// other geometry types (polylines, CAD edges, meshes) don't have to be
+// converted into points first.
+pub trait SegmentSource {
+    // Calls `f(x1, y1, x2, y2)` for every edge.
+    fn each_segment(&self, f: |f64, f64, f64, f64|);
+}
+
+impl SegmentSource for Segment {
+    fn each_segment(&self, f: |f64, f64, f64, f64|) {
+        f(self.x0, self.y0, self.x1, self.y1);
+    }
+}
+
+impl SegmentSource for Rect {
+    fn each_segment(&self, f: |f64, f64, f64, f64|) {
+        f(self.x0, self.y0, self.x1, self.y0);
+        f(self.x0, self.y1, self.x1, self.y1);
+    }
+}
+
+// Every edge of the outline, including the closing one.
+impl SegmentSource for Shape {
+    fn each_segment(&self, f: |f64, f64, f64, f64|) {
+        let points = self.points();
+        let n = points.len();
+        for i in range(0, n) {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % n];
+            f(x1, y1, x2, y2);
+        }
+    }
+}
+
+impl<'a, S: SegmentSource> SegmentSource for &'a [S] {
+    fn each_segment(&self, f: |f64, f64, f64, f64|) {
+        for s in self.iter() {
+            s.each_segment(|x1, y1, x2, y2| f(x1, y1, x2, y2));
+        }
+    }
+}
+
+impl<T: Direction> Skyline<T> {
+    // The envelope of all the edges of `src`. Every edge contributes, as in
+    // `from_path`.
+    pub fn from_source<S: SegmentSource>(src: &S) -> Skyline<T> {
+        let mult = Direction::direction_multiplier(None::<T>);
+        let mut edges = Vec::new();
+        src.each_segment(|x1, y1, x2, y2| {
+            let ((u1, v1), (u2, v2)) = (frame_point::<T>(x1, y1), frame_point::<T>(x2, y2));
+            edges.push(single_buildings(mult, u1, v1, u2, v2));
+        });
+        Skyline::from_buildings(merge_many(edges))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Shape, Skyline, Up, Down};
+    use super::SegmentSource;
+
+    struct Polyline {
+        points: Vec<(f64, f64)>
+    }
+
+    impl SegmentSource for Polyline {
+        fn each_segment(&self, f: |f64, f64, f64, f64|) {
+            for w in self.points.as_slice().windows(2) {
+                let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+                f(x1, y1, x2, y2);
+            }
+        }
+    }
+
+    #[test]
+    fn custom_source() {
+        let line = Polyline { points: vec![(0.0, 0.0), (1.0, 2.0), (3.0, 0.0)] };
+        let sky = Skyline::<Up>::from_source(&line);
+        assert!(sky.height_at(1.0).approx_eq(2.0));
+        assert!(sky.height_at(2.0).approx_eq(1.0));
+        assert_eq!(sky.height_at(4.0), Float::neg_infinity());
+    }
+
+    #[test]
+    fn builtin_sources() {
+        let rects = [Rect::new(0.0, 0.0, 2.0, 1.0), Rect::new(1.0, 0.0, 3.0, 2.0)];
+        let from_source = Skyline::<Up>::from_source(&rects.as_slice());
+        assert!(from_source.approx_eq_envelope(&Skyline::from_rects(&rects), 1e-9));
+
+        let tri = Shape::from_polygon(&[(0.0, 0.0), (2.0, 0.0), (1.0, 1.0)]);
+        let bottom = Skyline::<Down>::from_source(&tri);
+        assert!(bottom.approx_eq_envelope(&tri.skyline(), 1e-9));
+    }
+}
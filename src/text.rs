@@ -0,0 +1,108 @@
+// Envelopes of text, for laying out labels and word clouds tightly. This
+// module doesn't read font files itself: implement `GlyphOutlines` on top of
+// whatever font library you're using.
+
+use {Direction, PathCommand, Skyline};
+use PathCommand::{MoveTo, LineTo, QuadTo, CubicTo, Close};
+
+// Curves are flattened to within this fraction of the font size.
+static TOLERANCE: f64 = 0.01;
+
+pub trait GlyphOutlines {
+    fn units_per_em(&self) -> f64;
+
+    // The outline of a glyph in font units, with y pointing up, or `None`
+    // if the glyph has no outline (like a space).
+    fn outline(&self, glyph: u16) -> Option<Vec<PathCommand>>;
+}
+
+// A glyph in a shaped string. The position of its origin is in font units,
+// as shapers usually report it.
+#[deriving(Clone, PartialEq, Show)]
+pub struct PositionedGlyph {
+    pub id: u16,
+    pub x: f64,
+    pub y: f64
+}
+
+// Scales a path by `s` after moving it by (dx, dy).
+fn transform(cmds: &[PathCommand], s: f64, dx: f64, dy: f64) -> Vec<PathCommand> {
+    let x = |x: f64| (x + dx) * s;
+    let y = |y: f64| (y + dy) * s;
+    cmds.iter().map(|c| match *c {
+        MoveTo(a, b) => MoveTo(x(a), y(b)),
+        LineTo(a, b) => LineTo(x(a), y(b)),
+        QuadTo(a, b, c, d) => QuadTo(x(a), y(b), x(c), y(d)),
+        CubicTo(a, b, c, d, e, f) => CubicTo(x(a), y(b), x(c), y(d), x(e), y(f)),
+        Close => Close,
+    }).collect()
+}
+
+impl<T: Direction> Skyline<T> {
+    // The envelope of a glyph at the given size (in output units per em),
+    // with its origin at (0, 0). Use an upward skyline for the top of the
+    // glyph and a downward one for the bottom.
+    pub fn from_glyph<F: GlyphOutlines>(face: &F, glyph: u16, size: f64) -> Skyline<T> {
+        text_envelope(face, &[PositionedGlyph { id: glyph, x: 0.0, y: 0.0 }], size)
+    }
+}
+
+// The envelope of a whole shaped string at the given size.
+pub fn text_envelope<T: Direction, F: GlyphOutlines>(face: &F, glyphs: &[PositionedGlyph],
+                                                     size: f64) -> Skyline<T> {
+    assert!(size > 0.0, "invalid font size {}", size);
+    let scale = size / face.units_per_em();
+    let mut path = Vec::new();
+    for g in glyphs.iter() {
+        match face.outline(g.id) {
+            Some(cmds) => path.push_all(transform(cmds.as_slice(), scale, g.x, g.y).as_slice()),
+            None => {},
+        }
+    }
+    Skyline::from_path(path.as_slice(), size * TOLERANCE)
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {PathCommand, Skyline, Up, Down};
+    use PathCommand::{MoveTo, LineTo, Close};
+    use super::{GlyphOutlines, PositionedGlyph, text_envelope};
+
+    // Glyph 1 is a box, glyph 2 is a space.
+    struct BoxFont;
+
+    impl GlyphOutlines for BoxFont {
+        fn units_per_em(&self) -> f64 { 1000.0 }
+
+        fn outline(&self, glyph: u16) -> Option<Vec<PathCommand>> {
+            if glyph == 1 {
+                Some(vec![MoveTo(100.0, -200.0), LineTo(500.0, -200.0), LineTo(500.0, 700.0),
+                          LineTo(100.0, 700.0), Close])
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn glyph_envelopes() {
+        let top = Skyline::<Up>::from_glyph(&BoxFont, 1, 10.0);
+        let bottom = Skyline::<Down>::from_glyph(&BoxFont, 1, 10.0);
+        assert!(top.height_at(3.0).approx_eq(7.0));
+        assert!(bottom.height_at(3.0).approx_eq(-2.0));
+        assert_eq!(top.height_at(0.5), Float::neg_infinity());
+        assert_eq!(Skyline::<Up>::from_glyph(&BoxFont, 2, 10.0).segments().count(), 0);
+    }
+
+    #[test]
+    fn string_envelope() {
+        let glyphs = [PositionedGlyph { id: 1, x: 0.0, y: 0.0 },
+                      PositionedGlyph { id: 2, x: 600.0, y: 0.0 },
+                      PositionedGlyph { id: 1, x: 1200.0, y: 100.0 }];
+        let top = text_envelope::<Up, BoxFont>(&BoxFont, &glyphs, 10.0);
+        assert!(top.height_at(3.0).approx_eq(7.0));
+        assert_eq!(top.height_at(9.0), Float::neg_infinity());
+        assert!(top.height_at(15.0).approx_eq(8.0));
+    }
+}
@@ -0,0 +1,95 @@
+use {Affine, Building, Direction, Skyline};
+
+// Returned by `Skyline::transform` for maps that don't take skylines to
+// skylines: anything that rotates, shears, or reflects.
+#[deriving(Clone, PartialEq, Show)]
+pub struct UnsupportedTransform {
+    pub xform: Affine
+}
+
+// Applies x -> sx * x + tx and h -> sy * h + th to a list of buildings,
+// where sx and sy are positive.
+fn transform_buildings(bldgs: &[Building], sx: f64, sy: f64, tx: f64, th: f64,
+                       max_slope: f64) -> Vec<Building> {
+    let mut out = Vec::with_capacity(bldgs.len());
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        let end = sx * b.end + tx;
+        if b.b == Float::neg_infinity() {
+            out.push(Building::empty(end));
+        } else if start.is_finite() && b.end.is_finite() && start < b.end {
+            // Going through the end points keeps the slope within bounds.
+            out.push(Building::from_points_capped(sx * start + tx, sy * b.y(start) + th,
+                                                  end, sy * b.y(b.end) + th, max_slope));
+        } else {
+            let m = b.m * sy / sx;
+            out.push(Building { m: m, b: sy * b.b + th - m * tx, end: end });
+        }
+        start = b.end;
+    }
+    out
+}
+
+impl<T: Direction> Skyline<T> {
+    // Scales the skyline about the origin. Both factors must be positive.
+    pub fn scale(&mut self, sx: f64, sy: f64) {
+        assert!(sx > 0.0 && sy > 0.0, "invalid scale ({}, {})", sx, sy);
+        self.transform(&Affine::scaling(sx, sy)).unwrap();
+    }
+
+    // Applies an affine map to the skyline. Only maps that scale each axis
+    // by a positive amount and then translate are supported; anything else
+    // (rotations, shears, reflections) would turn the skyline into
+    // something that isn't a skyline, so it returns an error and leaves the
+    // skyline alone. To reflect a skyline, see `mirrored`.
+    pub fn transform(&mut self, xform: &Affine) -> Result<(), UnsupportedTransform> {
+        if xform.xy != 0.0 || xform.yx != 0.0 || !(xform.xx > 0.0) || !(xform.yy > 0.0) {
+            return Err(UnsupportedTransform { xform: *xform });
+        }
+
+        let mult = Direction::direction_multiplier(None::<T>);
+        let out = transform_buildings(self.buildings.as_slice(), xform.xx, xform.yy,
+                                      xform.x0, xform.y0 * mult, self.config.max_slope);
+        self.buildings = out;
+        self.domain.lo = self.domain.lo * xform.xx + xform.x0;
+        self.domain.hi = self.domain.hi * xform.xx + xform.x0;
+        self.debug_assert_valid();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Affine, Interval, Skyline, Up, Down};
+    use super::UnsupportedTransform;
+
+    #[test]
+    fn scale() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        sky.scale(2.0, 3.0);
+        assert!(sky.approx_eq(&Skyline::single(0.0, 0.0, 2.0, 3.0)));
+    }
+
+    #[test]
+    fn scale_and_translate() {
+        let mut sky = Skyline::<Down>::bounded(0.0, 4.0);
+        sky.merge(&Skyline::single(1.0, 1.0, 2.0, 2.0));
+        let xform = Affine::scaling(0.5, 2.0).then(&Affine::translation(1.0, 1.0));
+        sky.transform(&xform).unwrap();
+
+        assert_eq!(sky.domain(), Interval::new(1.0, 3.0));
+        assert!(sky.height_at(1.5).approx_eq(3.0));
+        assert!(sky.height_at(2.0).approx_eq(5.0));
+        assert_eq!(sky.height_at(2.5), Float::infinity());
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        let shear = Affine { xx: 1.0, xy: 1.0, yx: 0.0, yy: 1.0, x0: 0.0, y0: 0.0 };
+        assert_eq!(sky.transform(&shear), Err(UnsupportedTransform { xform: shear }));
+        assert!(sky.transform(&Affine::scaling(-1.0, 1.0)).is_err());
+        assert!(sky.approx_eq(&Skyline::single(0.0, 0.0, 1.0, 1.0)));
+    }
+}
@@ -0,0 +1,175 @@
+use {Direction, Flip, Rect, Segment, Skyline};
+
+// Maps user coordinates (millimeters, pixels, meters...) to the internal
+// coordinates that skylines are computed in: internal = (user - offset) /
+// scale. Choosing the offset near the middle of the scene and the scale
+// near its size keeps the internal numbers close to 1, where floating
+// point is most precise.
+#[deriving(Clone, PartialEq, Show)]
+pub struct CoordinateSystem {
+    pub scale: f64,
+    pub x_offset: f64,
+    pub y_offset: f64
+}
+
+impl CoordinateSystem {
+    pub fn new(scale: f64, x_offset: f64, y_offset: f64) -> CoordinateSystem {
+        assert!(scale > 0.0 && scale.is_finite(), "invalid scale {}", scale);
+        CoordinateSystem {
+            scale: scale,
+            x_offset: x_offset,
+            y_offset: y_offset
+        }
+    }
+
+    pub fn identity() -> CoordinateSystem {
+        CoordinateSystem::new(1.0, 0.0, 0.0)
+    }
+
+    // A coordinate system in which `r` becomes roughly the unit square
+    // around the origin.
+    pub fn fitting(r: &Rect) -> CoordinateSystem {
+        let scale = r.width().max(r.height());
+        CoordinateSystem::new(if scale > 0.0 { scale } else { 1.0 },
+                              (r.x0 + r.x1) / 2.0,
+                              (r.y0 + r.y1) / 2.0)
+    }
+
+    pub fn normalize(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.x_offset) / self.scale, (y - self.y_offset) / self.scale)
+    }
+
+    pub fn denormalize(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale + self.x_offset, y * self.scale + self.y_offset)
+    }
+}
+
+// Returned when combining skylines that use different coordinate systems.
+#[deriving(Clone, PartialEq, Show)]
+pub struct CoordinateMismatch {
+    pub left: CoordinateSystem,
+    pub right: CoordinateSystem
+}
+
+fn check(a: &CoordinateSystem, b: &CoordinateSystem) -> Result<(), CoordinateMismatch> {
+    if a == b {
+        Ok(())
+    } else {
+        Err(CoordinateMismatch { left: *a, right: *b })
+    }
+}
+
+// A skyline together with the coordinate system it was built in. All of
+// the arguments and results are in user coordinates.
+#[deriving(Clone, Show)]
+pub struct ScaledSkyline<T: Direction> {
+    coords: CoordinateSystem,
+    skyline: Skyline<T>
+}
+
+impl<T: Direction> ScaledSkyline<T> {
+    pub fn empty(coords: CoordinateSystem) -> ScaledSkyline<T> {
+        ScaledSkyline {
+            coords: coords,
+            skyline: Skyline::empty()
+        }
+    }
+
+    pub fn single(coords: CoordinateSystem, x1: f64, y1: f64, x2: f64, y2: f64)
+            -> ScaledSkyline<T> {
+        let (x1, y1) = coords.normalize(x1, y1);
+        let (x2, y2) = coords.normalize(x2, y2);
+        ScaledSkyline {
+            coords: coords,
+            skyline: Skyline::single(x1, y1, x2, y2)
+        }
+    }
+
+    // Wraps a skyline that is already in the internal coordinates of
+    // `coords`.
+    pub fn from_normalized(coords: CoordinateSystem, sky: Skyline<T>) -> ScaledSkyline<T> {
+        ScaledSkyline {
+            coords: coords,
+            skyline: sky
+        }
+    }
+
+    pub fn coords(&self) -> CoordinateSystem {
+        self.coords
+    }
+
+    // The underlying skyline, in internal coordinates.
+    pub fn normalized(&self) -> &Skyline<T> {
+        &self.skyline
+    }
+
+    pub fn overlap<S: Flip<T>>(&self, other: &ScaledSkyline<S>)
+            -> Result<f64, CoordinateMismatch> {
+        try!(check(&self.coords, &other.coords));
+        // The offsets cancel out, since the distance is a difference of
+        // heights.
+        Ok(self.skyline.overlap(&other.skyline) * self.coords.scale)
+    }
+
+    pub fn merge(&mut self, other: &ScaledSkyline<T>) -> Result<(), CoordinateMismatch> {
+        try!(check(&self.coords, &other.coords));
+        self.skyline.merge(&other.skyline);
+        Ok(())
+    }
+
+    pub fn slide(&mut self, x: f64) {
+        self.skyline.slide(x / self.coords.scale);
+    }
+
+    pub fn bump(&mut self, y: f64) {
+        self.skyline.bump(y / self.coords.scale);
+    }
+
+    pub fn height_at(&self, x: f64) -> f64 {
+        let (x, _) = self.coords.normalize(x, 0.0);
+        let (_, y) = self.coords.denormalize(0.0, self.skyline.height_at(x));
+        y
+    }
+
+    pub fn segments(&self) -> Vec<Segment> {
+        self.skyline.segments().map(|s| {
+            let (x0, y0) = self.coords.denormalize(s.x0, s.y0);
+            let (x1, y1) = self.coords.denormalize(s.x1, s.y1);
+            Segment { x0: x0, y0: y0, x1: x1, y1: y1 }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_utils::ApproxEq;
+    use {Rect, Up, Down};
+    use super::{CoordinateSystem, ScaledSkyline};
+
+    #[test]
+    fn scaled_round_trip() {
+        let mm = CoordinateSystem::fitting(&Rect::new(1000.0, 2000.0, 1100.0, 2050.0));
+        assert!(mm.scale.approx_eq(100.0));
+
+        let mut floor = ScaledSkyline::<Up>::single(mm, 1000.0, 2010.0, 1100.0, 2010.0);
+        let lid = ScaledSkyline::<Down>::single(mm, 1020.0, 2005.0, 1030.0, 2005.0);
+        assert!(floor.overlap(&lid).unwrap().approx_eq(5.0));
+
+        floor.bump(2.5);
+        assert!(floor.height_at(1050.0).approx_eq(2012.5));
+        let segs = floor.segments();
+        assert!(segs[0].x0.approx_eq(1000.0) && segs[0].y1.approx_eq(2012.5));
+    }
+
+    #[test]
+    fn scaled_mismatch() {
+        let mm = CoordinateSystem::new(1.0, 0.0, 0.0);
+        let m = CoordinateSystem::new(1000.0, 0.0, 0.0);
+        let mut a = ScaledSkyline::<Up>::single(mm, 0.0, 1.0, 1.0, 1.0);
+        let b = ScaledSkyline::<Up>::single(m, 0.0, 1.0, 1.0, 1.0);
+        let err = a.merge(&b).unwrap_err();
+        assert_eq!(err.left, mm);
+        assert_eq!(err.right, m);
+        assert!(a.overlap(&ScaledSkyline::<Down>::empty(m)).is_err());
+    }
+}
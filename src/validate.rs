@@ -0,0 +1,160 @@
+use {Direction, Skyline};
+use within;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+#[cfg(feature = "arbitrary")]
+use quickcheck::{Arbitrary, Gen};
+
+// The ways in which a skyline's buildings can be broken. Building indices
+// count from zero, from the left.
+#[deriving(Clone, PartialEq, Show)]
+pub enum InvariantError {
+    // There are no buildings at all.
+    NoBuildings,
+    // The last building ends here, instead of at positive infinity.
+    OpenEnd(f64),
+    // This building ends before the one before it does.
+    Unsorted(uint),
+    // This building has a NaN, an infinite slope, a slope steeper than the
+    // configured maximum, or an infinite height that is neither negative
+    // infinity (empty) nor a flat positive infinity (solid).
+    Degenerate(uint),
+    // Some of the buildings stick out of the skyline's domain.
+    OutsideDomain
+}
+
+impl<T: Direction> Skyline<T> {
+    // Checks the invariants that every operation is supposed to preserve.
+    // This takes linear time, so it's meant for tracking down bugs; debug
+    // builds check it after every operation that changes a skyline.
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        self.validate_with_max_slope(self.config.max_slope)
+    }
+
+    // Like `validate`, but with a different slope cap. Merging in a skyline
+    // with a laxer cap keeps its steeper buildings, so the result only
+    // passes `validate` against the laxer cap.
+    pub fn validate_with_max_slope(&self, max_slope: f64) -> Result<(), InvariantError> {
+        let last = match self.buildings.last() {
+            Some(b) => b.end,
+            None => return Err(InvariantError::NoBuildings),
+        };
+        if last != Float::infinity() {
+            return Err(InvariantError::OpenEnd(last));
+        }
+
+        let mut start: f64 = Float::neg_infinity();
+        for (i, b) in self.buildings.iter().enumerate() {
+            if b.end.is_nan() || b.m.is_nan() || b.b.is_nan()
+                    || b.m.abs() > max_slope || (b.b == Float::infinity() && b.m != 0.0) {
+                return Err(InvariantError::Degenerate(i));
+            }
+            if b.end < start {
+                return Err(InvariantError::Unsorted(i));
+            }
+            start = b.end;
+        }
+
+        if !within(self.buildings.as_slice(), &self.domain) {
+            return Err(InvariantError::OutsideDomain);
+        }
+        Ok(())
+    }
+}
+
+// Random skylines for property tests: the envelope of up to `g.size()`
+// random segments.
+#[cfg(feature = "arbitrary")]
+impl<T: Direction + Clone + Send> Arbitrary for Skyline<T> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Skyline<T> {
+        let n = g.gen_range(0, g.size() + 1);
+        let mut sky = Skyline::empty();
+        for _ in range(0, n) {
+            let x: f64 = g.gen_range(-100.0, 100.0);
+            let w: f64 = g.gen_range(0.0, 20.0);
+            sky.merge(&Skyline::single(x, g.gen_range(-100.0, 100.0),
+                                        x + w, g.gen_range(-100.0, 100.0)));
+        }
+        sky
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Building, Skyline, SkylineConfig, Up, Down};
+    use super::InvariantError;
+
+    #[test]
+    fn valid_skylines() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        sky.merge(&Skyline::single(0.5, 2.0, 0.5, 3.0));
+        assert_eq!(sky.validate(), Ok(()));
+        sky.bound(0.0, 0.75);
+        assert_eq!(sky.validate(), Ok(()));
+        assert_eq!(Skyline::<Down>::empty().validate(), Ok(()));
+    }
+
+    #[test]
+    fn broken_skylines() {
+        let sky = Skyline::<Up>::from_buildings(vec![]);
+        assert_eq!(sky.validate(), Err(InvariantError::NoBuildings));
+
+        let sky = Skyline::<Up>::from_buildings(vec![Building::empty(3.0)]);
+        assert_eq!(sky.validate(), Err(InvariantError::OpenEnd(3.0)));
+
+        let sky = Skyline::<Up>::from_buildings(
+            vec!(Building::empty(2.0),
+                 Building { m: 0.0, b: 1.0, end: 1.0 },
+                 Building::empty(Float::infinity())));
+        assert_eq!(sky.validate(), Err(InvariantError::Unsorted(1)));
+
+        let sky = Skyline::<Up>::from_buildings(
+            vec!(Building { m: Float::nan(), b: 1.0, end: 1.0 },
+                 Building::empty(Float::infinity())));
+        assert_eq!(sky.validate(), Err(InvariantError::Degenerate(0)));
+
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 1.0, 1.0);
+        sky.domain.hi = 0.5;
+        assert_eq!(sky.validate(), Err(InvariantError::OutsideDomain));
+    }
+
+    #[test]
+    fn parallel_merges_stay_valid() {
+        // Identical and nearly parallel buildings used to have NaN
+        // intersections.
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 2.0, 1.0);
+        sky.merge(&Skyline::single(0.0, 0.0, 2.0, 1.0));
+        assert_eq!(sky.validate(), Ok(()));
+        sky.merge(&Skyline::single(0.0, 1e-15, 2.0, 1.0 + 1e-15));
+        assert_eq!(sky.validate(), Ok(()));
+        sky.merge(&Skyline::single(1.0, 0.5, 3.0, 1.5));
+        assert_eq!(sky.validate(), Ok(()));
+    }
+
+    #[test]
+    fn snapped_merges_stay_sorted() {
+        // Snapping and simplifying after a merge used to leave ends out of
+        // order when several breakpoints landed in the same grid cell.
+        let config = SkylineConfig::new().with_grid(0.1).with_simplification(0.05);
+        let mut sky = Skyline::<Down>::with_config(config);
+        for i in range(0u, 40) {
+            let x = i as f64 * 0.037;
+            sky.merge(&Skyline::single(x, x * 0.5, x + 0.04, x * 0.5 - 0.01));
+            assert_eq!(sky.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn merges_stay_valid() {
+        use quickcheck::quickcheck;
+
+        fn prop(a: Skyline<Up>, b: Skyline<Up>) -> bool {
+            let mut a = a;
+            a.merge(&b);
+            a.validate().is_ok()
+        }
+        quickcheck(prop as fn(Skyline<Up>, Skyline<Up>) -> bool);
+    }
+}
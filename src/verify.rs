@@ -0,0 +1,169 @@
+// Cross-checks merges and overlaps against a direct evaluation of the
+// inputs, for tracking down envelope bugs. This is only compiled with the
+// "verify" feature, in debug builds; otherwise the checks are no-ops.
+//
+// We sample every breakpoint of the inputs and output, the midpoints
+// between them, and a point beyond each end. At a breakpoint we compare
+// the limits from the left and from the right separately, so we don't
+// depend on how the skylines treat the breakpoints themselves.
+
+use {Building, Extremum};
+use add_heights;
+
+static TOLERANCE: f64 = 1e-5;
+
+fn close(a: f64, b: f64) -> bool {
+    a == b || (a - b).abs() <= TOLERANCE * a.abs().max(b.abs()).max(1.0)
+}
+
+// The height just to the left of `x` (or at `x`, if nothing changes there).
+fn left(bldgs: &[Building], x: f64) -> f64 {
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if start < x && x <= b.end {
+            return b.y(x);
+        }
+        start = b.end;
+    }
+    Float::neg_infinity()
+}
+
+// The height just to the right of `x`.
+fn right(bldgs: &[Building], x: f64) -> f64 {
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if start <= x && x < b.end {
+            return b.y(x);
+        }
+        start = b.end;
+    }
+    Float::neg_infinity()
+}
+
+// The highest building that touches `x`, including zero-width ones.
+fn closed(bldgs: &[Building], x: f64) -> f64 {
+    let mut h: f64 = Float::neg_infinity();
+    let mut start: f64 = Float::neg_infinity();
+    for b in bldgs.iter() {
+        if start <= x && x <= b.end {
+            h = h.max(b.y(x));
+        }
+        start = b.end;
+    }
+    h
+}
+
+fn samples(lists: &[&[Building]]) -> Vec<f64> {
+    let mut xs = Vec::new();
+    for bldgs in lists.iter() {
+        for b in bldgs.iter() {
+            if b.end.is_finite() {
+                xs.push(b.end);
+            }
+        }
+    }
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.dedup();
+    if xs.is_empty() {
+        return vec![0.0];
+    }
+
+    let mut out = vec![xs[0] - 1.0];
+    for i in range(0, xs.len()) {
+        if i > 0 {
+            out.push((xs[i - 1] + xs[i]) / 2.0);
+        }
+        out.push(xs[i]);
+    }
+    out.push(xs[xs.len() - 1] + 1.0);
+    out
+}
+
+fn combine(a: f64, b: f64, ext: Extremum) -> f64 {
+    match ext {
+        Extremum::Max => a.max(b),
+        Extremum::Min => {
+            if a == Float::neg_infinity() {
+                b
+            } else if b == Float::neg_infinity() {
+                a
+            } else {
+                a.min(b)
+            }
+        },
+    }
+}
+
+pub fn check_merge(in1: &[Building], in2: &[Building], ext: Extremum, out: &[Building]) {
+    let dump = || format!("\n  in1 = {}\n  in2 = {}\n  out = {}", in1, in2, out);
+
+    for i in range(1, out.len()) {
+        if !(out[i - 1].end <= out[i].end) {
+            panic!("merge produced out-of-order buildings at {} and {}:{}", i - 1, i, dump());
+        }
+    }
+    match out.last() {
+        Some(b) if b.end == Float::infinity() => {},
+        _ => panic!("merge output doesn't extend to infinity:{}", dump()),
+    }
+
+    for &x in samples(&[in1, in2, out]).iter() {
+        let expected = combine(left(in1, x), left(in2, x), ext);
+        let got = left(out, x);
+        if !close(expected, got) {
+            panic!("{} merge mismatch just left of x = {}: expected {}, got {}:{}",
+                   ext, x, expected, got, dump());
+        }
+
+        let expected = combine(right(in1, x), right(in2, x), ext);
+        let got = right(out, x);
+        if !close(expected, got) {
+            panic!("{} merge mismatch just right of x = {}: expected {}, got {}:{}",
+                   ext, x, expected, got, dump());
+        }
+    }
+}
+
+pub fn check_overlap(in1: &[Building], in2: &[Building], got: f64) {
+    // The overlap is at least the sum of the heights on either side of
+    // any point, and at most the sum of the highest heights that touch it.
+    let mut lower: f64 = Float::neg_infinity();
+    let mut upper: f64 = Float::neg_infinity();
+    for &x in samples(&[in1, in2]).iter() {
+        lower = lower.max(add_heights(left(in1, x), left(in2, x)))
+            .max(add_heights(right(in1, x), right(in2, x)));
+        upper = upper.max(add_heights(closed(in1, x), closed(in2, x)));
+    }
+
+    if !(got >= lower || close(got, lower)) || !(got <= upper || close(got, upper)) {
+        panic!("overlap mismatch: expected between {} and {}, got {}\n  in1 = {}\n  in2 = {}",
+               lower, upper, got, in1, in2);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {Building, Extremum, Skyline, Up, Down};
+    use super::check_merge;
+
+    #[test]
+    fn verify_accepts_good_merges() {
+        let mut sky = Skyline::<Up>::single(0.0, 0.0, 2.0, 2.0);
+        sky.merge(&Skyline::single(0.0, 2.0, 2.0, 0.0));
+        sky.merge_with(&Skyline::single(1.0, 1.0, 3.0, 1.0), Extremum::Min);
+        sky.overlap(&Skyline::<Down>::single(0.0, 0.0, 4.0, 0.0));
+    }
+
+    #[test]
+    #[should_fail]
+    fn verify_catches_bad_merges() {
+        let a = vec![Building::empty(0.0),
+                     Building { m: 0.0, b: 1.0, end: 1.0 },
+                     Building::empty(Float::infinity())];
+        let b = vec![Building::empty(Float::infinity())];
+        let wrong = vec![Building::empty(0.0),
+                         Building { m: 0.0, b: 2.0, end: 1.0 },
+                         Building::empty(Float::infinity())];
+        check_merge(a.as_slice(), b.as_slice(), Extremum::Max, wrong.as_slice());
+    }
+}
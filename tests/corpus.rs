@@ -0,0 +1,85 @@
+// Rebuilds the envelopes of the outlines in tests/corpus and compares them
+// with the golden envelopes stored there. See tests/corpus/README for the
+// file formats.
+
+extern crate skyline;
+
+use std::io::File;
+use std::io::fs;
+use skyline::{Direction, Rect, Skyline, Up, Down};
+
+static TOLERANCE: f64 = 1e-6;
+
+fn corpus_dir() -> Path {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus")
+}
+
+// Reads a file of whitespace-separated numbers, `n` to a line.
+fn read_rows(path: &Path, n: uint) -> Vec<Vec<f64>> {
+    let contents = File::open(path).read_to_string().unwrap();
+    let mut rows = Vec::new();
+    for line in contents.as_slice().lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        let row: Vec<f64> = line.words().map(|w| {
+            from_str(w).expect(format!("bad number {} in {}", w, path.display()).as_slice())
+        }).collect();
+        assert!(row.len() == n, "expected {} numbers per line in {}", n, path.display());
+        rows.push(row);
+    }
+    rows
+}
+
+fn build<T: Direction>(shape: &Path) -> Skyline<T> {
+    let mut sky = match shape.extension_str() {
+        Some("poly") => {
+            let points: Vec<(f64, f64)> =
+                read_rows(shape, 2).iter().map(|r| (r[0], r[1])).collect();
+            Skyline::from_polygon(points.as_slice())
+        },
+        Some("rects") => {
+            let rects: Vec<Rect> =
+                read_rows(shape, 4).iter().map(|r| Rect::new(r[0], r[1], r[2], r[3])).collect();
+            Skyline::from_rects(rects.as_slice())
+        },
+        _ => panic!("unknown shape {}", shape.display()),
+    };
+    sky.simplify(TOLERANCE);
+    sky
+}
+
+fn golden<T: Direction>(path: &Path) -> Skyline<T> {
+    let mut sky = Skyline::empty();
+    for r in read_rows(path, 4).iter() {
+        sky.merge(&Skyline::single(r[0], r[1], r[2], r[3]));
+    }
+    sky
+}
+
+fn check<T: Direction>(shape: &Path, ext: &str) {
+    let golden_path = shape.with_extension(ext);
+    assert!(golden_path.exists(), "missing golden file {}", golden_path.display());
+    let built = build::<T>(shape);
+    let expected = golden::<T>(&golden_path);
+    assert!(built.approx_eq_envelope(&expected, TOLERANCE),
+            "{} doesn't match {}: got {}", shape.display(), golden_path.display(),
+            built.segments().collect::<Vec<_>>());
+}
+
+#[test]
+fn corpus_matches_golden() {
+    let mut count = 0u;
+    for path in fs::readdir(&corpus_dir()).unwrap().iter() {
+        match path.extension_str() {
+            Some("poly") | Some("rects") => {
+                check::<Up>(path, "up");
+                check::<Down>(path, "down");
+                count += 1;
+            },
+            _ => {},
+        }
+    }
+    assert!(count > 0, "the corpus is empty");
+}
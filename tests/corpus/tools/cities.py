@@ -0,0 +1,29 @@
+# European cities with populations over about half a million, with their
+# coordinates rounded to a tenth of a degree (latitude, longitude).
+CITIES = [
+    ("Amsterdam", 52.4, 4.9), ("Athens", 38.0, 23.7), ("Barcelona", 41.4, 2.2),
+    ("Belgrade", 44.8, 20.5), ("Berlin", 52.5, 13.4), ("Bilbao", 43.3, -2.9),
+    ("Birmingham", 52.5, -1.9), ("Bologna", 44.5, 11.3), ("Bordeaux", 44.8, -0.6),
+    ("Bratislava", 48.1, 17.1), ("Bremen", 53.1, 8.8), ("Brussels", 50.8, 4.4),
+    ("Bucharest", 44.4, 26.1), ("Budapest", 47.5, 19.0), ("Cologne", 50.9, 7.0),
+    ("Copenhagen", 55.7, 12.6), ("Dortmund", 51.5, 7.5), ("Dresden", 51.1, 13.7),
+    ("Dublin", 53.3, -6.3), ("Duisburg", 51.4, 6.8), ("Düsseldorf", 51.2, 6.8),
+    ("Edinburgh", 56.0, -3.2), ("Essen", 51.5, 7.0), ("Florence", 43.8, 11.3),
+    ("Frankfurt", 50.1, 8.7), ("Gdańsk", 54.4, 18.6), ("Genoa", 44.4, 8.9),
+    ("Glasgow", 55.9, -4.3), ("Gothenburg", 57.7, 12.0), ("Hamburg", 53.6, 10.0),
+    ("Hanover", 52.4, 9.7), ("Helsinki", 60.2, 24.9), ("Kraków", 50.1, 19.9),
+    ("Leeds", 53.8, -1.5), ("Leipzig", 51.3, 12.4), ("Lisbon", 38.7, -9.1),
+    ("Liverpool", 53.4, -3.0), ("Łódź", 51.8, 19.5), ("London", 51.5, -0.1),
+    ("Lyon", 45.8, 4.8), ("Madrid", 40.4, -3.7), ("Málaga", 36.7, -4.4),
+    ("Manchester", 53.5, -2.2), ("Marseille", 43.3, 5.4), ("Milan", 45.5, 9.2),
+    ("Munich", 48.1, 11.6), ("Naples", 40.9, 14.3), ("Nuremberg", 49.5, 11.1),
+    ("Oslo", 59.9, 10.8), ("Palermo", 38.1, 13.4), ("Paris", 48.9, 2.4),
+    ("Porto", 41.2, -8.6), ("Poznań", 52.4, 16.9), ("Prague", 50.1, 14.4),
+    ("Riga", 56.9, 24.1), ("Rome", 41.9, 12.5), ("Rotterdam", 51.9, 4.5),
+    ("Seville", 37.4, -6.0), ("Sheffield", 53.4, -1.5), ("Sofia", 42.7, 23.3),
+    ("Stockholm", 59.3, 18.1), ("Stuttgart", 48.8, 9.2), ("Thessaloniki", 40.6, 22.9),
+    ("Toulouse", 43.6, 1.4), ("Turin", 45.1, 7.7), ("Valencia", 39.5, -0.4),
+    ("Vienna", 48.2, 16.4), ("Vilnius", 54.7, 25.3), ("Warsaw", 52.2, 21.0),
+    ("Wrocław", 51.1, 17.0), ("Zagreb", 45.8, 16.0), ("Zaragoza", 41.7, -0.9),
+    ("Zürich", 47.4, 8.5),
+]
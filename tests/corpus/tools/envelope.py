@@ -0,0 +1,82 @@
+MAX_SLOPE = 1e3
+
+
+def capped(x1, y1, x2, y2):
+    """The line through an edge, with its slope capped the way skylines cap
+    it (and raised to stay above both ends)."""
+    m = (y2 - y1) / (x2 - x1)
+    m = max(-MAX_SLOPE, min(MAX_SLOPE, m))
+    b = max(y1 - m * x1, y2 - m * x2)
+    return (min(x1, x2), max(x1, x2), m, b)
+
+
+def polygon_segments(points, mult):
+    """The edges of a polygon that face in the direction `mult` (1 for up,
+    -1 for down), in internal coordinates (heights times `mult`)."""
+    n = len(points)
+    area = 0.0
+    for i in range(n):
+        x1, y1 = points[i]
+        x2, y2 = points[(i + 1) % n]
+        area += x1 * y2 - x2 * y1
+    orientation = 1.0 if area > 0 else -1.0
+    segs = []
+    for i in range(n):
+        x1, y1 = points[i]
+        x2, y2 = points[(i + 1) % n]
+        facing = -(x2 - x1) * orientation * mult
+        if facing > 0 or (area == 0 and x1 != x2):
+            segs.append(capped(x1, y1 * mult, x2, y2 * mult))
+    return segs
+
+
+def rect_segments(rects, mult):
+    segs = []
+    for x0, y0, x1, y1 in rects:
+        h = y1 if mult > 0 else -y0
+        segs.append((x0, x1, 0.0, h))
+    return segs
+
+
+def upper_envelope(segs):
+    """The upper envelope of a set of segments (x1, x2, m, b), as a list of
+    (x1, y1, x2, y2) pieces."""
+    xs = set()
+    for s in segs:
+        xs.add(s[0])
+        xs.add(s[1])
+    for i in range(len(segs)):
+        a = segs[i]
+        for j in range(i + 1, len(segs)):
+            c = segs[j]
+            lo, hi = max(a[0], c[0]), min(a[1], c[1])
+            if lo >= hi or a[2] == c[2]:
+                continue
+            x = (c[3] - a[3]) / (a[2] - c[2])
+            if lo < x < hi:
+                xs.add(x)
+    xs = sorted(xs)
+    by_start = sorted(range(len(segs)), key=lambda k: segs[k][0])
+
+    pieces = []
+    active = []
+    nxt = 0
+    for xa, xb in zip(xs, xs[1:]):
+        while nxt < len(by_start) and segs[by_start[nxt]][0] <= xa:
+            active.append(by_start[nxt])
+            nxt += 1
+        active = [k for k in active if segs[k][1] >= xb]
+        if not active:
+            continue
+        mid = (xa + xb) / 2.0
+        best = max(active, key=lambda k: segs[k][2] * mid + segs[k][3])
+        m, b = segs[best][2], segs[best][3]
+        if pieces and pieces[-1][0] == best and pieces[-1][3] == xa:
+            pieces[-1] = (best, pieces[-1][1], pieces[-1][2], xb, m * xb + b)
+        else:
+            pieces.append((best, xa, m * xa + b, xb, m * xb + b))
+    return [(x1, y1, x2, y2) for (_, x1, y1, x2, y2) in pieces]
+
+
+def envelope(segs, mult):
+    return [(x1, y1 * mult, x2, y2 * mult) for (x1, y1, x2, y2) in upper_envelope(segs)]
@@ -0,0 +1,140 @@
+import math
+import sys
+from ttf import Font, flatten
+from envelope import polygon_segments, rect_segments, envelope
+from cities import CITIES
+
+OUT = sys.argv[1]
+FONTS = sys.argv[2] if len(sys.argv) > 2 else '/usr/share/fonts/truetype/dejavu/'
+
+
+def fmt(x):
+    r = repr(float(x))
+    return '0' if r in ('0.0', '-0.0') else (r[:-2] if r.endswith('.0') else r)
+
+
+def area(pts):
+    return sum(pts[i][0] * pts[(i + 1) % len(pts)][1] - pts[(i + 1) % len(pts)][0] * pts[i][1]
+               for i in range(len(pts)))
+
+
+def write_goldens(name, segs_for, header):
+    for mult, ext in [(1, 'up'), (-1, 'down')]:
+        with open('%s/%s.%s' % (OUT, name, ext), 'w') as f:
+            f.write('# %s envelope of %s, computed independently of the crate.\n'
+                    % ('Upward' if mult > 0 else 'Downward', header))
+            for seg in envelope(segs_for(mult), mult):
+                f.write(' '.join(fmt(round(v, 9)) for v in seg) + '\n')
+
+
+def write_poly(name, pts, comment):
+    # Drop repeated points (and the closing one).
+    clean = []
+    for p in pts:
+        if not clean or clean[-1] != p:
+            clean.append(p)
+    if clean[0] == clean[-1]:
+        clean.pop()
+    with open('%s/%s.poly' % (OUT, name), 'w') as f:
+        for line in comment:
+            f.write('# %s\n' % line)
+        for x, y in clean:
+            f.write('%s %s\n' % (fmt(x), fmt(y)))
+    write_goldens(name, lambda mult: polygon_segments(clean, mult), name + '.poly')
+    return len(clean)
+
+
+def glyph(name, font_file, ch):
+    font = Font(FONTS + font_file)
+    contours = [flatten(c, 8) for c in font.contours(ch)]
+    outer = max(contours, key=lambda c: abs(area(c)))
+    pts = [(round(x, 3), round(y, 3)) for x, y in outer]
+    n = write_poly(name, pts, [
+        'The outer contour of U+%04X (%s) from %s (DejaVu fonts 2.37),' % (ord(ch), ch, font_file),
+        'in font units (%d to the em). Each quadratic spline segment of the' % font.units_per_em,
+        'TrueType outline is flattened into 8 chords, and coordinates are rounded',
+        'to 0.001 units. Counters (holes) are left out: they never touch the',
+        'envelope.'])
+    print(name, n, 'points')
+
+
+def gear(name, module, teeth, pressure_deg, flank_pts=12, arc_pts=4):
+    alpha = math.radians(pressure_deg)
+    rp = module * teeth / 2.0
+    rb = rp * math.cos(alpha)
+    ra = rp + module
+    rf = rp - 1.25 * module
+    inv = lambda a: math.tan(a) - a
+    # Half the angular width of a tooth at radius r.
+    half_base = math.pi / (2 * teeth) + inv(alpha)
+
+    def half_width(r):
+        return half_base - inv(math.acos(min(1.0, rb / r)))
+
+    pitch = 2 * math.pi / teeth
+    r0 = max(rb, rf)
+    radii = [r0 + (ra - r0) * i / float(flank_pts - 1) for i in range(flank_pts)]
+    pts = []
+    for k in range(teeth):
+        c = k * pitch
+        # Root arc from the previous tooth's flank to this one's.
+        prev_edge = c - pitch + half_width(r0)
+        this_edge = c - half_width(r0)
+        for i in range(arc_pts + 1):
+            a = prev_edge + (this_edge - prev_edge) * i / float(arc_pts)
+            pts.append((rf * math.cos(a), rf * math.sin(a)))
+        # Rising flank, tip, falling flank.
+        for r in radii:
+            a = c - half_width(r)
+            pts.append((r * math.cos(a), r * math.sin(a)))
+        tip = half_width(ra)
+        for i in range(1, arc_pts):
+            a = c - tip + 2 * tip * i / float(arc_pts)
+            pts.append((ra * math.cos(a), ra * math.sin(a)))
+        for r in reversed(radii):
+            a = c + half_width(r)
+            pts.append((r * math.cos(a), r * math.sin(a)))
+    pts = [(round(x, 4), round(y, 4)) for x, y in pts]
+    n = write_poly(name, pts, [
+        'A spur gear: module %g mm, %d teeth, %g degree pressure angle, no profile'
+        % (module, teeth, pressure_deg),
+        'shift, standard ISO 53 basic rack (addendum 1 m, dedendum 1.25 m).',
+        'Coordinates are in millimetres about the gear axis, rounded to 0.0001.',
+        'The flanks are exact involutes sampled at %d radii from the base' % flank_pts,
+        'circle to the tip circle; the tip and root are circular arcs. It stands',
+        'in for a DXF part, but was computed from the defining equations rather',
+        'than exported from a CAD file.'])
+    print(name, n, 'points')
+
+
+def labels(name, font_file, size_pt, deg_pt):
+    font = Font(FONTS + font_file)
+    scale = size_pt / float(font.units_per_em)
+    height = (font.ascent - font.descent) * scale
+    rects = []
+    for city, lat, lon in CITIES:
+        width = sum(font.advance(font.cmap[ord(c)]) for c in city) * scale
+        x, y = lon * deg_pt, lat * deg_pt
+        # The label sits to the right of the city's dot, centred on it.
+        x0, y0 = round(x + 3.0, 3), round(y - height / 2.0, 3)
+        rects.append((x0, y0, round(x0 + width, 3), round(y0 + height, 3)))
+    with open('%s/%s.rects' % (OUT, name), 'w') as f:
+        f.write('# Label boxes for %d European cities on an equirectangular map at\n'
+                % len(CITIES))
+        f.write('# %g pt per degree, set in %s at %g pt. Each box starts 3 pt to\n'
+                % (deg_pt, font_file, size_pt))
+        f.write('# the right of the city and is centred on it vertically. Widths are\n')
+        f.write('# the sums of the glyph advances, and heights are the ascent plus the\n')
+        f.write('# descent from the font. City coordinates are rounded to 0.1 degree.\n')
+        for r in rects:
+            f.write(' '.join(fmt(v) for v in r) + '\n')
+    write_goldens(name, lambda mult: rect_segments(rects, mult), name + '.rects')
+    print(name, len(rects), 'labels')
+
+
+glyph('dejavu_sans_S', 'DejaVuSans.ttf', 'S')
+glyph('dejavu_sans_bold_ampersand', 'DejaVuSans-Bold.ttf', '&')
+glyph('dejavu_serif_R', 'DejaVuSerif.ttf', 'R')
+glyph('dejavu_serif_italic_f', 'DejaVuSerif-Italic.ttf', 'f')
+gear('spur_gear_m2_z24', 2.0, 24, 20.0)
+labels('europe_labels', 'DejaVuSans.ttf', 9.0, 20.0)
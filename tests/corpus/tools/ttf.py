@@ -0,0 +1,151 @@
+import struct
+
+
+class Font:
+    def __init__(self, path):
+        self.data = open(path, 'rb').read()
+        d = self.data
+        num_tables = struct.unpack('>H', d[4:6])[0]
+        self.tables = {}
+        for i in range(num_tables):
+            tag, _, off, length = struct.unpack('>4sIII', d[12 + 16 * i:28 + 16 * i])
+            self.tables[tag.decode('latin1')] = (off, length)
+        head = self.tables['head'][0]
+        self.units_per_em = struct.unpack('>H', d[head + 18:head + 20])[0]
+        self.loc_format = struct.unpack('>h', d[head + 50:head + 52])[0]
+        maxp = self.tables['maxp'][0]
+        self.num_glyphs = struct.unpack('>H', d[maxp + 4:maxp + 6])[0]
+        hhea = self.tables['hhea'][0]
+        self.ascent, self.descent = struct.unpack('>hh', d[hhea + 4:hhea + 8])
+        self.num_hmetrics = struct.unpack('>H', d[hhea + 34:hhea + 36])[0]
+        self._cmap()
+
+    def _cmap(self):
+        d = self.data
+        base = self.tables['cmap'][0]
+        n = struct.unpack('>H', d[base + 2:base + 4])[0]
+        self.cmap = {}
+        for i in range(n):
+            pid, eid, off = struct.unpack('>HHI', d[base + 4 + 8 * i:base + 12 + 8 * i])
+            if pid == 3 and eid == 1:
+                sub = base + off
+                assert struct.unpack('>H', d[sub:sub + 2])[0] == 4
+                segx2 = struct.unpack('>H', d[sub + 6:sub + 8])[0]
+                segs = segx2 // 2
+                ends = struct.unpack('>%dH' % segs, d[sub + 14:sub + 14 + segx2])
+                starts_off = sub + 16 + segx2
+                starts = struct.unpack('>%dH' % segs, d[starts_off:starts_off + segx2])
+                deltas = struct.unpack('>%dh' % segs, d[starts_off + segx2:starts_off + 2 * segx2])
+                ro_off = starts_off + 2 * segx2
+                ros = struct.unpack('>%dH' % segs, d[ro_off:ro_off + segx2])
+                for k in range(segs):
+                    for c in range(starts[k], min(ends[k], 0xfffe) + 1):
+                        if ros[k] == 0:
+                            g = (c + deltas[k]) & 0xffff
+                        else:
+                            a = ro_off + 2 * k + ros[k] + 2 * (c - starts[k])
+                            g = struct.unpack('>H', d[a:a + 2])[0]
+                            if g:
+                                g = (g + deltas[k]) & 0xffff
+                        self.cmap[c] = g
+                return
+        raise ValueError('no unicode cmap')
+
+    def advance(self, gid):
+        d = self.data
+        hmtx = self.tables['hmtx'][0]
+        i = min(gid, self.num_hmetrics - 1)
+        return struct.unpack('>H', d[hmtx + 4 * i:hmtx + 4 * i + 2])[0]
+
+    def glyph_offset(self, gid):
+        d = self.data
+        loca = self.tables['loca'][0]
+        if self.loc_format == 0:
+            a, b = struct.unpack('>HH', d[loca + 2 * gid:loca + 2 * gid + 4])
+            return a * 2, b * 2
+        return struct.unpack('>II', d[loca + 4 * gid:loca + 4 * gid + 8])
+
+    def contours(self, ch):
+        """The contours of a simple glyph, as lists of (x, y, on_curve)."""
+        d = self.data
+        gid = self.cmap[ord(ch)]
+        a, b = self.glyph_offset(gid)
+        g = self.tables['glyf'][0] + a
+        n = struct.unpack('>h', d[g:g + 2])[0]
+        if n <= 0:
+            raise ValueError('%r is not a simple glyph' % ch)
+        ends = struct.unpack('>%dH' % n, d[g + 10:g + 10 + 2 * n])
+        npts = ends[-1] + 1
+        p = g + 10 + 2 * n
+        ilen = struct.unpack('>H', d[p:p + 2])[0]
+        p += 2 + ilen
+        flags = []
+        while len(flags) < npts:
+            f = d[p]; p += 1
+            flags.append(f)
+            if f & 8:
+                r = d[p]; p += 1
+                flags.extend([f] * r)
+        xs = []
+        x = 0
+        for f in flags:
+            if f & 2:
+                v = d[p]; p += 1
+                x += v if f & 16 else -v
+            elif not f & 16:
+                x += struct.unpack('>h', d[p:p + 2])[0]; p += 2
+            xs.append(x)
+        ys = []
+        y = 0
+        for f in flags:
+            if f & 4:
+                v = d[p]; p += 1
+                y += v if f & 32 else -v
+            elif not f & 32:
+                y += struct.unpack('>h', d[p:p + 2])[0]; p += 2
+            ys.append(y)
+        out = []
+        start = 0
+        for e in ends:
+            out.append([(xs[i], ys[i], bool(flags[i] & 1)) for i in range(start, e + 1)])
+            start = e + 1
+        return out
+
+
+def flatten(contour, steps):
+    """Flattens a TrueType contour (quadratic B-splines) into a polygon."""
+    pts = list(contour)
+    # Start at an on-curve point, inserting one if there are none.
+    k = next((i for i, p in enumerate(pts) if p[2]), None)
+    if k is None:
+        a, b = pts[0], pts[1]
+        pts.insert(0, ((a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0, True))
+        k = 0
+    pts = pts[k:] + pts[:k]
+    # Make the implied on-curve points between consecutive off-curve points
+    # explicit.
+    full = []
+    n = len(pts)
+    for i in range(n):
+        p, q = pts[i], pts[(i + 1) % n]
+        full.append(p)
+        if not p[2] and not q[2]:
+            full.append(((p[0] + q[0]) / 2.0, (p[1] + q[1]) / 2.0, True))
+    out = []
+    i = 0
+    n = len(full)
+    while i < n:
+        p = full[i]
+        q = full[(i + 1) % n]
+        out.append((float(p[0]), float(p[1])))
+        if q[2]:
+            i += 1
+            continue
+        r = full[(i + 2) % n]
+        for s in range(1, steps):
+            t = s / float(steps)
+            u = 1 - t
+            out.append((u * u * p[0] + 2 * u * t * q[0] + t * t * r[0],
+                        u * u * p[1] + 2 * u * t * q[1] + t * t * r[1]))
+        i += 2
+    return out
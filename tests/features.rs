@@ -0,0 +1,178 @@
+// Exercises every API that's behind a feature, in whatever configuration
+// the tests are built with, and checks that the APIs are missing when their
+// features are off. CI runs these tests with no features, with all of them,
+// and with each one alone (see .travis.yml).
+
+extern crate skyline;
+#[cfg(feature = "serialize")]
+extern crate serialize;
+
+use skyline::{Skyline, Up, Down};
+
+// This runs in every configuration, to make sure that the features don't
+// change the basics.
+#[test]
+fn default_api() {
+    let mut sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+    sky.merge(&Skyline::single(1.0, 2.0, 3.0, 2.0));
+    let d = sky.overlap(&Skyline::<Down>::single(0.0, 0.0, 3.0, 0.0));
+    assert_eq!(d, 2.0);
+    assert_eq!(Skyline::merge_all(vec![sky.clone(), sky.clone()]).overlap(
+        &Skyline::<Down>::single(0.0, 0.0, 3.0, 0.0)), 2.0);
+}
+
+// When a feature is off, its methods must be too. Method calls prefer
+// inherent methods to trait methods, so if one of these were defined
+// without its feature, the call would go to it instead of the stand-in
+// and fail to compile.
+#[test]
+#[cfg(not(feature = "compact-fmt"))]
+fn no_compact_format() {
+    trait Absent { fn compact(&self) -> &'static str { "absent" } }
+    impl Absent for Skyline<Up> {}
+    assert_eq!(Skyline::<Up>::empty().compact(), "absent");
+}
+
+#[test]
+#[cfg(not(feature = "arrow"))]
+fn no_columns() {
+    trait Absent { fn to_columns(&self) -> &'static str { "absent" } }
+    impl Absent for Skyline<Up> {}
+    assert_eq!(Skyline::<Up>::empty().to_columns(), "absent");
+}
+
+#[test]
+#[cfg(not(feature = "rand"))]
+fn no_jitter() {
+    trait Absent { fn jitter(&self) -> &'static str { "absent" } }
+    impl Absent for Skyline<Up> {}
+    assert_eq!(Skyline::<Up>::empty().jitter(), "absent");
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn serialize_round_trip() {
+    use serialize::json;
+    let sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0);
+    let back: Skyline<Up> = json::decode(json::encode(&sky).as_slice()).unwrap();
+    assert!(back.approx_eq_envelope(&sky, 0.0));
+}
+
+#[test]
+#[cfg(feature = "serialize")]
+fn envelope_file() {
+    use skyline::archive::EnvelopeFile;
+    let mut file = EnvelopeFile::new();
+    file.insert_skyline("top", &Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0));
+    let back = EnvelopeFile::from_json(file.to_json().as_slice()).unwrap();
+    assert!(back.skyline::<Up>("top").is_some());
+}
+
+#[test]
+#[cfg(feature = "exact")]
+fn exact_merge_is_consistent() {
+    // The ramp passes exactly through (6, 0.8), where the flat segment
+    // starts, but the floating point intersection is a little to the right
+    // of 6. Without exact arithmetic, the flat segment wins on that sliver.
+    let mut a = Skyline::<Up>::single(4.0, 0.0, 8.0, 1.6);
+    a.merge(&Skyline::single(6.0, 0.8, 9.0, 0.8));
+    assert!(a.segments().filter(|s| s.x1 <= 8.0).all(|s| s.y1 > s.y0));
+}
+
+#[test]
+#[cfg(feature = "verify")]
+fn verified_merges() {
+    let mut sky = Skyline::<Up>::empty();
+    for i in range(0u, 20) {
+        let x = i as f64;
+        sky.merge(&Skyline::single(x, (x * 0.7).sin(), x + 1.5, (x * 1.3).cos()));
+    }
+    sky.overlap(&Skyline::<Down>::single(0.0, 0.0, 30.0, 0.0));
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn parallel_merge_all() {
+    let skylines: Vec<Skyline<Up>> = range(0u, 100)
+        .map(|i| Skyline::single(i as f64, 0.0, i as f64 + 2.0, (i % 7) as f64))
+        .collect();
+    let merged = Skyline::merge_all(skylines);
+    assert_eq!(merged.height_at(6.5), 6.0);
+}
+
+#[test]
+#[cfg(feature = "deterministic-parallel")]
+fn deterministic_merge_all() {
+    let skylines: Vec<Skyline<Up>> = range(0u, 200)
+        .map(|i| {
+            let x = i as f64 * 0.37;
+            Skyline::single(x, x.sin(), x + 3.1, (x * 1.7).cos())
+        })
+        .collect();
+    // `merge_k` runs on one thread and pairs things up the same way.
+    let serial = {
+        let refs: Vec<&Skyline<Up>> = skylines.iter().collect();
+        Skyline::merge_k(refs.as_slice())
+    };
+    let parallel = Skyline::merge_all(skylines);
+    assert_eq!(parallel.segments(), serial.segments());
+}
+
+#[test]
+#[cfg(feature = "compact-fmt")]
+fn compact_format() {
+    let sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 1.0);
+    assert!(format!("{}", sky.compact()).as_slice().starts_with("Up["));
+}
+
+#[test]
+#[cfg(feature = "text")]
+fn glyph_envelope() {
+    use skyline::PathCommand;
+    use skyline::PathCommand::{MoveTo, LineTo, Close};
+    use skyline::text::GlyphOutlines;
+
+    struct Square;
+    impl GlyphOutlines for Square {
+        fn units_per_em(&self) -> f64 { 1.0 }
+        fn outline(&self, _: u16) -> Option<Vec<PathCommand>> {
+            Some(vec![MoveTo(0.0, 0.0), LineTo(1.0, 0.0), LineTo(1.0, 1.0), LineTo(0.0, 1.0),
+                      Close])
+        }
+    }
+
+    let top = Skyline::<Up>::from_glyph(&Square, 0, 2.0);
+    assert_eq!(top.height_at(1.0), 2.0);
+}
+
+#[test]
+#[cfg(feature = "arrow")]
+fn columnar_round_trip() {
+    let sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0);
+    let cols = sky.to_columns();
+    let back = Skyline::<Up>::from_columns(cols.columns()).unwrap();
+    assert!(back.approx_eq_envelope(&sky, 0.0));
+}
+
+#[test]
+#[cfg(feature = "rand")]
+fn jittered_skylines_are_valid() {
+    use std::rand::{SeedableRng, StdRng};
+    let seed: &[uint] = &[5, 6, 7];
+    let mut rng: StdRng = SeedableRng::from_seed(seed);
+    let sky = Skyline::<Up>::single(0.0, 1.0, 2.0, 3.0);
+    assert!(sky.jitter(&mut rng, 0.1, 0.1).validate().is_ok());
+}
+
+#[test]
+#[cfg(feature = "ffi")]
+fn c_interface() {
+    use skyline::ffi::{skyline_free, skyline_overlap, skyline_single, SKYLINE_DOWN, SKYLINE_UP};
+    unsafe {
+        let up = skyline_single(SKYLINE_UP, 0.0, 1.0, 2.0, 1.0);
+        let down = skyline_single(SKYLINE_DOWN, 0.0, 0.0, 2.0, 0.0);
+        assert_eq!(skyline_overlap(up as *const _, down as *const _), 1.0);
+        skyline_free(up);
+        skyline_free(down);
+    }
+}
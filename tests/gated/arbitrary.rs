@@ -0,0 +1,11 @@
+extern crate quickcheck;
+extern crate skyline;
+
+use quickcheck::Arbitrary;
+use skyline::{Skyline, Up};
+
+pub fn arbitrary<T: Arbitrary>() {}
+
+pub fn probe() {
+    arbitrary::<Skyline<Up>>();
+}
@@ -0,0 +1,8 @@
+extern crate skyline;
+
+use skyline::{Skyline, Up};
+use skyline::arrow::ColumnBuffers;
+
+pub fn probe(sky: &Skyline<Up>) -> ColumnBuffers {
+    sky.to_columns()
+}
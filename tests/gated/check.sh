@@ -0,0 +1,42 @@
+#!/bin/sh
+# Checks that everything behind a feature stays out of the default API. Each
+# probe here uses the items that one feature adds (compact_fmt.rs is for the
+# compact-fmt feature, and so on). Every probe has to compile against the
+# crate built with all the features, and fail against the crate built with
+# none of them.
+#
+# Run it from the top of the crate:
+#
+#     sh tests/gated/check.sh
+
+set -e
+
+ALL="serialize exact verify parallel deterministic-parallel compact-fmt text arbitrary arrow rand ffi"
+OUT=target/gated
+
+probe() {
+    rustc --crate-type lib --crate-name probe -L target -L target/deps --out-dir $OUT "$1"
+}
+
+status=0
+
+cargo build --features "$ALL"
+mkdir -p $OUT
+for p in tests/gated/*.rs; do
+    if ! probe $p; then
+        echo "$p doesn't compile with every feature"
+        status=1
+    fi
+done
+
+cargo clean
+cargo build
+mkdir -p $OUT
+for p in tests/gated/*.rs; do
+    if probe $p 2>/dev/null; then
+        echo "$p compiles without the $(basename $p .rs | tr _ -) feature"
+        status=1
+    fi
+done
+
+exit $status
@@ -0,0 +1,8 @@
+extern crate skyline;
+
+use skyline::{Skyline, Up};
+use skyline::compact::Compact;
+
+pub fn probe<'a>(sky: &'a Skyline<Up>) -> Compact<'a, Up> {
+    sky.compact()
+}
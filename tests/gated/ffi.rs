@@ -0,0 +1,3 @@
+extern crate skyline;
+
+pub use skyline::ffi::{skyline_free, skyline_single};
@@ -0,0 +1,8 @@
+extern crate skyline;
+
+use std::rand::Rng;
+use skyline::{Skyline, Up};
+
+pub fn probe<R: Rng>(sky: &Skyline<Up>, rng: &mut R) -> Skyline<Up> {
+    sky.jitter(rng, 0.1, 0.1)
+}
@@ -0,0 +1,14 @@
+extern crate serialize;
+extern crate skyline;
+
+use serialize::Encodable;
+use serialize::json::Encoder;
+use std::io::IoError;
+use skyline::{Skyline, Up};
+pub use skyline::archive::EnvelopeFile;
+
+pub fn encodable<'a, T: Encodable<Encoder<'a>, IoError>>() {}
+
+pub fn probe() {
+    encodable::<Skyline<Up>>();
+}
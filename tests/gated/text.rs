@@ -0,0 +1,8 @@
+extern crate skyline;
+
+use skyline::{Skyline, Up};
+use skyline::text::GlyphOutlines;
+
+pub fn probe<F: GlyphOutlines>(face: &F) -> Skyline<Up> {
+    Skyline::from_glyph(face, 0, 1.0)
+}